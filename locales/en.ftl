@@ -0,0 +1,92 @@
+# Text shown by blocks. Translations go in
+# $XDG_CONFIG_HOME/subar/locales/<language>.ftl and only need the messages
+# that differ from these.
+
+## Shared
+
+duration-hours = { $hours }h { $minutes }m
+duration-minutes = { $minutes }m
+duration-seconds = { $seconds }s
+state-playing = playing
+state-paused = paused
+state-stopped = stopped
+
+## Battery
+
+battery-charging = charging
+battery-discharging = discharging
+battery-full = full
+battery-not-charging = not charging
+battery-unknown = unknown
+
+## Camera
+
+camera-on = on
+camera-off = off
+
+## CI
+
+ci-failed = CI failed
+
+## Fortune
+
+fortune-title = Fortune
+
+## Moon
+
+moon-new = new moon
+moon-waxing-crescent = waxing crescent
+moon-first-quarter = first quarter
+moon-waxing-gibbous = waxing gibbous
+moon-full = full moon
+moon-waning-gibbous = waning gibbous
+moon-last-quarter = last quarter
+moon-waning-crescent = waning crescent
+
+## Parcels
+
+parcel-pending = pending
+parcel-info-received = info received
+parcel-in-transit = in transit
+parcel-out-for-delivery = out for delivery
+parcel-attempt-fail = delivery failed
+parcel-available-for-pickup = ready for pickup
+parcel-exception = exception
+
+## Pomodoro
+
+pomodoro-work = work
+pomodoro-short-break = short break
+pomodoro-long-break = long break
+
+## Power profile
+
+power-profile-power-saver = power saver
+power-profile-balanced = balanced
+power-profile-performance = performance
+
+## Sun
+
+sun-sunrise = sunrise
+sun-golden-hour = golden hour
+sun-sunset = sunset
+
+## Transit
+
+transit-now = now
+
+## Volume
+
+volume-title = Volume
+volume-nothing-playing = Nothing is playing
+volume-muted = (muted)
+
+## Report
+
+report-today = Today
+report-week = The last 7 days
+report-empty = Nothing recorded yet. Set history = true in the config to keep statistics.
+report-mpd-listened = Time listening to MPD: { $value }
+report-mpd-songs = Songs played: { $value }
+report-pomodoros = Pomodoros finished: { $value }
+report-battery-charges = Times the battery was charged: { $value }
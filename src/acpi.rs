@@ -0,0 +1,87 @@
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{sleep, Duration};
+
+use crate::backoff::Backoff;
+use crate::events::BlockEvent;
+use crate::system::Sys;
+
+static ACPI_MAX_RETRY: u64 = 10 * 60 * 1000;
+
+/// Events from acpid, like "ac_adapter ACPI0003:00 00000080 00000001" or
+/// "button/lid LID close", as printed by `acpi_listen`
+pub type AcpiEvents = broadcast::Sender<String>;
+
+/// Start listening for ACPI events, restarting `acpi_listen` if it stops
+pub fn listen(sys: &Sys) -> AcpiEvents {
+    let (tx, _) = broadcast::channel(16);
+    tokio::spawn(read_events(sys.clone(), tx.clone()));
+    tx
+}
+
+async fn read_events(sys: Sys, tx: AcpiEvents) {
+    let mut backoff = Backoff::new(
+        Duration::from_secs(1),
+        Duration::from_millis(ACPI_MAX_RETRY),
+    );
+    loop {
+        match sys.stream(&mut Command::new("acpi_listen")) {
+            Ok(reader) => {
+                let mut lines = BufReader::new(reader).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    backoff.reset();
+                    let _ = tx.send(line);
+                }
+                eprintln!("acpi_listen stopped");
+            }
+            Err(err) => eprintln!("Couldn't listen for ACPI events. {err}"),
+        }
+        sleep(backoff.fail()).await;
+    }
+}
+
+/// Refresh a block whenever an event starting with one of `prefixes` comes in
+pub fn refresh_on(acpi: &AcpiEvents, prefixes: Vec<String>, block: mpsc::Sender<BlockEvent>) {
+    let mut events = acpi.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if prefixes.iter().any(|p| event.starts_with(p.as_str())) => {
+                    if block.send(BlockEvent::Refresh).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn acpi_events_refresh_blocks() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c cat /sys/class/power_supply/AC/online", 0, "1\n");
+        mock.stream(
+            "acpi_listen",
+            b"button/lid LID close\nac_adapter ACPI0003:00 00000080 00000000\n".to_vec(),
+        );
+        let config = r#"
+            [[block]]
+            block = "fortune"
+            source = { type = "command", command = "cat /sys/class/power_supply/AC/online" }
+            refresh_on = ["ac_adapter"]
+        "#;
+        let mut tasks = start(config, &mock);
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        let runs = ran.iter().filter(|c| c.contains("power_supply")).count();
+        assert_eq!(runs, 2);
+    }
+}
@@ -0,0 +1,97 @@
+use std::hash::{BuildHasher, Hasher, RandomState};
+
+use tokio::time::Duration;
+
+/// Exponential backoff with jitter for retrying things that keep failing,
+/// such as a missing command or an unreachable server.
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            failures: 0,
+        }
+    }
+
+    /// Record a failure and return how long to wait before trying again.
+    /// The delay doubles with each consecutive failure up to the maximum, and
+    /// is randomly adjusted by up to 20% so retries don't line up.
+    pub fn fail(&mut self) -> Duration {
+        let delay = self
+            .initial
+            .saturating_mul(2u32.saturating_pow(self.failures))
+            .min(self.max);
+        self.failures = self.failures.saturating_add(1);
+        let jitter = (random() % 401) as f64 / 1000.0 - 0.2;
+        delay.mul_f64(1.0 + jitter)
+    }
+
+    /// Record a success so the next failure starts from the initial delay
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+fn random() -> u64 {
+    // Each RandomState is seeded differently, which is plenty for jitter
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `delay` is within 20% of `expected`
+    fn near(delay: Duration, expected: Duration) -> bool {
+        let ratio = delay.as_secs_f64() / expected.as_secs_f64();
+        (0.8 - 1e-9..=1.2 + 1e-9).contains(&ratio)
+    }
+
+    #[test]
+    fn delays_double_up_to_the_maximum() {
+        let second = Duration::from_secs(1);
+        let mut backoff = Backoff::new(second, 10 * second);
+        for expected in [1, 2, 4, 8, 10, 10] {
+            let delay = backoff.fail();
+            assert!(near(delay, expected * second), "{delay:?} for {expected}s");
+        }
+    }
+
+    #[test]
+    fn resetting_starts_from_the_initial_delay() {
+        let second = Duration::from_secs(1);
+        let mut backoff = Backoff::new(second, 60 * second);
+        for _ in 0..5 {
+            backoff.fail();
+        }
+        backoff.reset();
+        assert!(near(backoff.fail(), second));
+        assert!(near(backoff.fail(), 2 * second));
+    }
+
+    #[test]
+    fn jitter_stays_within_20_percent() {
+        let initial = Duration::from_millis(1000);
+        let delays: Vec<_> = (0..200)
+            .map(|_| Backoff::new(initial, initial).fail())
+            .collect();
+        assert!(delays.iter().all(|d| near(*d, initial)));
+        // Retries are spread out rather than all lining up
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[test]
+    fn many_failures_dont_overflow() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(30));
+        for _ in 0..100 {
+            backoff.fail();
+        }
+        assert!(near(backoff.fail(), Duration::from_secs(30)));
+    }
+}
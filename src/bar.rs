@@ -0,0 +1,961 @@
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+use crate::acpi;
+#[cfg(feature = "mpd")]
+use crate::blocks::mpd;
+#[cfg(feature = "script")]
+use crate::blocks::script;
+#[cfg(feature = "weather")]
+use crate::blocks::{aqi, weather};
+use crate::blocks::{
+    battery, calendar, camera, clipboard, clock, cpu, fortune, gpu, load, memory, moon, pomodoro,
+    schedule, stopwatch, sun, swap, temperature, timer,
+};
+#[cfg(feature = "http")]
+use crate::blocks::{chat, ci, github, holiday, parcel, transit};
+#[cfg(feature = "audio")]
+use crate::blocks::{easyeffects, headset, input_level, volume};
+#[cfg(feature = "dbus")]
+use crate::blocks::{mpris, power_profile};
+use crate::click::{OnClick, Route};
+use crate::config::{Block, BlockConfig, Loading, Stale};
+#[cfg(feature = "dbus")]
+use crate::dbus;
+use crate::events::{BlockEvent, Events};
+use crate::output::BlockOutput;
+use crate::sandbox::{self, Sandbox};
+use crate::system::Sys;
+use crate::udev;
+use crate::watchdog::{Heartbeat, Stage};
+
+/// Start a task for each block, and the local clock at the end if it isn't
+/// placed somewhere else. Tasks stop updating while `paused` is true.
+pub fn spawn(mut blocks: Vec<Block>, sys: &Sys, paused: &watch::Receiver<bool>) -> Vec<Taskmaster> {
+    let has_clock = blocks
+        .iter()
+        .any(|b| matches!(&b.kind, BlockConfig::Clock(clock) if clock.timezone.is_none()));
+    if !has_clock {
+        blocks.push(BlockConfig::Clock(clock::Config::default()).into());
+    }
+    let mut tasks = Vec::new();
+    #[cfg(feature = "mpd")]
+    let players = mpd::Players::default();
+    // Only listened for if a block wants them
+    let acpi_events = blocks
+        .iter()
+        .any(|b| !b.refresh_on.is_empty())
+        .then(|| acpi::listen(sys));
+    let mut subsystems: Vec<String> = blocks
+        .iter()
+        .flat_map(|b| b.refresh_on_devices.iter().cloned())
+        .collect();
+    subsystems.sort();
+    subsystems.dedup();
+    let device_events = (!subsystems.is_empty()).then(|| udev::monitor(sys, &subsystems));
+    for block in blocks {
+        let sys = &match &block.sandbox {
+            Some(sandbox) => sandbox::wrap(sys.clone(), sandbox.clone()),
+            None => sys.clone(),
+        };
+        let mut task = match block.kind {
+            #[cfg(feature = "weather")]
+            BlockConfig::Aqi(cfg) => {
+                let interval = Duration::from_millis(aqi::AQI_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("aqi", aqi::AQI_FALLBACK, paused, |tx, events| {
+                    aqi::aqi_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Battery(cfg) => {
+                let interval = Duration::from_millis(battery::BATTERY_UPDATE_FREQUENCY);
+                let fallback = battery::BATTERY_FALLBACK;
+                let task = Taskmaster::new("battery", fallback, paused, |tx, events| {
+                    battery::battery_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Calendar(cfg) => {
+                let interval = Duration::from_millis(calendar::CALENDAR_UPDATE_FREQUENCY);
+                let fallback = calendar::CALENDAR_FALLBACK;
+                let task = Taskmaster::new("calendar", fallback, paused, |tx, events| {
+                    calendar::calendar_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Camera(cfg) => {
+                let interval = Duration::from_millis(camera::CAMERA_UPDATE_FREQUENCY);
+                let task =
+                    Taskmaster::new("camera", camera::CAMERA_FALLBACK, paused, |tx, events| {
+                        camera::camera_task(cfg, sys.clone(), tx, events)
+                    });
+                task.clickable().updates_every(interval)
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Chat(cfg) => {
+                let interval = Duration::from_secs(cfg.interval);
+                let task = Taskmaster::new("chat", chat::CHAT_FALLBACK, paused, |tx, events| {
+                    chat::chat_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Ci(cfg) => {
+                let interval = Duration::from_secs(cfg.interval);
+                let task = Taskmaster::new("ci", ci::CI_FALLBACK, paused, |tx, events| {
+                    ci::ci_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            BlockConfig::Clipboard(cfg) => {
+                let fallback = clipboard::CLIPBOARD_FALLBACK;
+                let task = Taskmaster::new("clipboard", fallback, paused, |tx, events| {
+                    clipboard::clipboard_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            BlockConfig::Clock(cfg) => {
+                let interval = Duration::from_millis(clock::CLOCK_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("clock", clock::CLOCK_FALLBACK, paused, |tx, events| {
+                    clock::clock_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            BlockConfig::Cpu(cfg) => {
+                let interval = Duration::from_secs(cfg.interval);
+                let task = Taskmaster::new("cpu", cpu::CPU_FALLBACK, paused, |tx, events| {
+                    cpu::cpu_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            #[cfg(feature = "audio")]
+            BlockConfig::Easyeffects(cfg) => {
+                let fallback = easyeffects::EASYEFFECTS_FALLBACK;
+                let interval = Duration::from_millis(easyeffects::EASYEFFECTS_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("easyeffects", fallback, paused, |tx, events| {
+                    easyeffects::easyeffects_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            BlockConfig::Fortune(cfg) => {
+                let fallback = fortune::FORTUNE_FALLBACK;
+                let interval = Duration::from_secs(cfg.interval);
+                let task = Taskmaster::new("fortune", fallback, paused, |tx, events| {
+                    fortune::fortune_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Github(cfg) => {
+                let interval = Duration::from_secs(cfg.interval);
+                let task =
+                    Taskmaster::new("github", github::GITHUB_FALLBACK, paused, |tx, events| {
+                        github::github_task(cfg, sys.clone(), tx, events)
+                    });
+                task.clickable().updates_every(interval)
+            }
+            BlockConfig::Gpu(cfg) => {
+                let interval = Duration::from_millis(gpu::GPU_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("gpu", gpu::GPU_FALLBACK, paused, |tx, events| {
+                    gpu::gpu_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            #[cfg(feature = "audio")]
+            BlockConfig::Headset(cfg) => {
+                let interval = Duration::from_millis(headset::HEADSET_UPDATE_FREQUENCY);
+                let task = Taskmaster::new(
+                    "headset",
+                    headset::HEADSET_FALLBACK,
+                    paused,
+                    |tx, events| headset::headset_task(cfg, sys.clone(), tx, events),
+                );
+                task.updates_every(interval)
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Holiday(cfg) => Taskmaster::new(
+                "holiday",
+                holiday::HOLIDAY_FALLBACK,
+                paused,
+                |tx, events| holiday::holiday_task(cfg, sys.clone(), tx, events),
+            ),
+            #[cfg(feature = "audio")]
+            BlockConfig::InputLevel(cfg) => {
+                let fallback = input_level::INPUT_LEVEL_FALLBACK;
+                let task = Taskmaster::new("input_level", fallback, paused, |tx, events| {
+                    input_level::input_level_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            BlockConfig::Load(cfg) => {
+                let interval = Duration::from_millis(load::LOAD_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("load", load::LOAD_FALLBACK, paused, |tx, events| {
+                    load::load_task(cfg, tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Memory(cfg) => {
+                let interval = Duration::from_millis(memory::MEMORY_UPDATE_FREQUENCY);
+                let fallback = memory::MEMORY_FALLBACK;
+                let task = Taskmaster::new("memory", fallback, paused, |tx, events| {
+                    memory::memory_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Moon(cfg) => {
+                Taskmaster::new("moon", moon::MOON_FALLBACK, paused, |tx, events| {
+                    moon::moon_task(cfg, sys.clone(), tx, events)
+                })
+            }
+            #[cfg(feature = "mpd")]
+            BlockConfig::Mpd(cfg) => {
+                let task = Taskmaster::new("mpd", mpd::MPD_FALLBACK, paused, |tx, events| {
+                    mpd::mpd_task(*cfg, players.clone(), sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            #[cfg(feature = "dbus")]
+            BlockConfig::Mpris(cfg) => {
+                let interval = Duration::from_millis(mpris::MPRIS_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("mpris", mpris::MPRIS_FALLBACK, paused, |tx, events| {
+                    mpris::mpris_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Parcel(cfg) => {
+                let interval = Duration::from_secs(cfg.interval);
+                let fallback = parcel::PARCEL_FALLBACK;
+                let task = Taskmaster::new("parcel", fallback, paused, |tx, events| {
+                    parcel::parcel_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Pomodoro(cfg) => {
+                let fallback = pomodoro::POMODORO_FALLBACK;
+                let task = Taskmaster::new("pomodoro", fallback, paused, |tx, events| {
+                    pomodoro::pomodoro_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            #[cfg(feature = "dbus")]
+            BlockConfig::PowerProfile(cfg) => {
+                let interval = Duration::from_millis(power_profile::POWER_PROFILE_UPDATE_FREQUENCY);
+                let fallback = power_profile::POWER_PROFILE_FALLBACK;
+                let task = Taskmaster::new("power_profile", fallback, paused, |tx, events| {
+                    power_profile::power_profile_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable().updates_every(interval)
+            }
+            BlockConfig::Schedule(cfg) => {
+                let interval = Duration::from_millis(schedule::SCHEDULE_UPDATE_FREQUENCY);
+                let fallback = schedule::SCHEDULE_FALLBACK;
+                let task = Taskmaster::new("schedule", fallback, paused, |tx, events| {
+                    schedule::schedule_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            #[cfg(feature = "script")]
+            BlockConfig::Script(cfg) => {
+                let interval = Duration::from_millis(cfg.interval);
+                let task =
+                    Taskmaster::new("script", script::SCRIPT_FALLBACK, paused, |tx, events| {
+                        script::script_task(cfg, tx, events)
+                    });
+                task.updates_every(interval)
+            }
+            BlockConfig::Stopwatch(cfg) => {
+                let fallback = stopwatch::STOPWATCH_FALLBACK;
+                let task = Taskmaster::new("stopwatch", fallback, paused, |tx, events| {
+                    stopwatch::stopwatch_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            BlockConfig::Sun(cfg) => {
+                let interval = Duration::from_millis(sun::SUN_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("sun", sun::SUN_FALLBACK, paused, |tx, events| {
+                    sun::sun_task(cfg, sys.clone(), tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Swap(cfg) => {
+                let interval = Duration::from_millis(swap::SWAP_UPDATE_FREQUENCY);
+                let task = Taskmaster::new("swap", swap::SWAP_FALLBACK, paused, |tx, events| {
+                    swap::swap_task(cfg, tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Temperature(cfg) => {
+                let interval = Duration::from_millis(temperature::TEMPERATURE_UPDATE_FREQUENCY);
+                let fallback = temperature::TEMPERATURE_FALLBACK;
+                let task = Taskmaster::new("temperature", fallback, paused, |tx, events| {
+                    temperature::temperature_task(cfg, tx, events)
+                });
+                task.updates_every(interval)
+            }
+            BlockConfig::Timer(cfg) => {
+                let task = Taskmaster::new("timer", timer::TIMER_FALLBACK, paused, |tx, events| {
+                    timer::timer_task(cfg, sys.clone(), tx, events)
+                });
+                task.clickable()
+            }
+            #[cfg(feature = "http")]
+            BlockConfig::Transit(cfg) => Taskmaster::new(
+                "transit",
+                transit::TRANSIT_FALLBACK,
+                paused,
+                |tx, events| transit::transit_task(cfg, sys.clone(), tx, events),
+            ),
+            #[cfg(feature = "audio")]
+            BlockConfig::Volume(cfg) => {
+                let interval = Duration::from_millis(volume::VOL_UPDATE_FREQUENCY);
+                let polls = cfg.backend.polls();
+                let fallback = cfg.device.fallback();
+                let task = Taskmaster::new("volume", fallback, paused, |tx, events| {
+                    volume::volume_task(cfg, sys.clone(), tx, events)
+                });
+                if polls {
+                    task.clickable().updates_every(interval)
+                } else {
+                    task.clickable()
+                }
+            }
+            #[cfg(feature = "weather")]
+            BlockConfig::Weather(cfg) => Taskmaster::new(
+                "weather",
+                weather::WEATHER_FALLBACK,
+                paused,
+                |tx, events| weather::weather_task(cfg, sys.clone(), tx, events),
+            ),
+        };
+        task.instance = block.instance.unwrap_or_else(|| tasks.len().to_string());
+        task.on_click = block.on_click;
+        task.sandbox = block.sandbox;
+        if let Some(acpi_events) = acpi_events
+            .as_ref()
+            .filter(|_| !block.refresh_on.is_empty())
+        {
+            acpi::refresh_on(acpi_events, block.refresh_on, task.events.clone());
+        }
+        if let Some(device_events) = device_events
+            .as_ref()
+            .filter(|_| !block.refresh_on_devices.is_empty())
+        {
+            udev::refresh_on(device_events, block.refresh_on_devices, task.events.clone());
+        }
+        tasks.push(task);
+    }
+    tasks
+}
+
+/// The JSON for one update of the status line: every visible block
+pub fn status_line(
+    tasks: &mut [Taskmaster],
+    stale: &Stale,
+    loading: &Loading,
+    now: DateTime<Local>,
+) -> String {
+    let mut status_line = Vec::with_capacity(tasks.len());
+    for task in tasks.iter_mut() {
+        task.observe(now);
+        if task.loading && !task.is_hidden() {
+            let mut output = BlockOutput::from(loading.text(task.fallback, now));
+            output.color.clone_from(&loading.color);
+            let block = StatusBlock::new(task.name, task.instance.clone(), &output);
+            status_line.push(block);
+            continue;
+        }
+        let is_stale = task.is_stale(Duration::from_secs(stale.grace), now);
+        let output = task.status();
+        if output.is_empty() || task.is_hidden() {
+            continue;
+        }
+        let mut block = StatusBlock::new(task.name, task.instance.clone(), &output);
+        if is_stale || output.stale {
+            block.mark_stale(stale);
+        }
+        status_line.push(block);
+    }
+    let last = status_line.len().saturating_sub(1);
+    for block in &mut status_line[..last] {
+        block.add_spacing();
+    }
+    serde_json::to_string(&status_line).unwrap()
+}
+
+/// Writes the status line to stdout until the bar stops reading it. It's
+/// shared so it can be restarted if it gets stuck.
+pub struct Renderer {
+    pub tasks: Mutex<Vec<Taskmaster>>,
+    pub stale: Stale,
+    pub loading: Loading,
+    pub sys: Sys,
+    pub interval: Duration,
+    pub heartbeat: Arc<Heartbeat>,
+}
+
+impl Renderer {
+    pub async fn run(self: Arc<Self>, mut paused: watch::Receiver<bool>) -> Result<()> {
+        let mut stdout = io::stdout();
+        loop {
+            self.heartbeat.beat(Stage::Rendering);
+            let line = {
+                // The lock is only poisoned if a previous renderer panicked
+                let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+                status_line(&mut tasks, &self.stale, &self.loading, self.sys.now())
+            };
+            self.heartbeat.beat(Stage::Writing);
+            if !write_line(&mut stdout, &format!("{line},"))? {
+                return Ok(());
+            }
+            self.heartbeat.beat(Stage::Sleeping);
+            sleep(self.interval).await;
+            if *paused.borrow() {
+                self.heartbeat.set_hidden(true);
+                let _ = paused.wait_for(|paused| !paused).await;
+                self.heartbeat.set_hidden(false);
+            }
+        }
+    }
+}
+
+/// Write one line of the protocol and flush it. Returns false once the bar
+/// has closed its end of the pipe, e.g. because swaybar was restarted.
+pub fn write_line(out: &mut impl Write, line: &str) -> Result<bool> {
+    match writeln!(out, "{line}").and_then(|()| out.flush()) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(false),
+        Err(err) => Err(err).context("Couldn't write the status line"),
+    }
+}
+
+#[derive(Serialize)]
+pub struct Header {
+    version: u8,
+    pub click_events: bool,
+    pub cont_signal: u8,
+    pub stop_signal: u8,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            click_events: false,
+            cont_signal: 18,
+            stop_signal: 19,
+        }
+    }
+}
+
+/// One block of the status line. Blocks are drawn without separators so the
+/// bar looks like a single line of text.
+#[derive(Serialize)]
+struct StatusBlock {
+    full_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    name: &'static str,
+    instance: String,
+    separator: bool,
+    separator_block_width: u8,
+}
+
+impl StatusBlock {
+    fn new(name: &'static str, instance: String, output: &BlockOutput) -> Self {
+        Self {
+            full_text: output.full_text.clone(),
+            short_text: output.short_text.clone(),
+            color: output.color.clone(),
+            background: output.background.clone(),
+            urgent: output.urgent,
+            name,
+            instance,
+            separator: false,
+            separator_block_width: 0,
+        }
+    }
+    /// Show that the block's task has stopped updating it, or that it's
+    /// showing something old
+    fn mark_stale(&mut self, stale: &Stale) {
+        self.full_text.push_str(&stale.marker);
+        if let Some(short_text) = &mut self.short_text {
+            short_text.push_str(&stale.marker);
+        }
+        if stale.color.is_some() {
+            self.color = stale.color.clone();
+        }
+    }
+    /// Separate the block from the one after it
+    fn add_spacing(&mut self) {
+        self.full_text.push(' ');
+        if let Some(short_text) = &mut self.short_text {
+            short_text.push(' ');
+        }
+    }
+}
+
+pub struct Taskmaster {
+    _handle: JoinHandle<Result<()>>,
+    rx: watch::Receiver<BlockOutput>,
+    pub name: &'static str,
+    /// Tells blocks with the same name apart in click events
+    pub instance: String,
+    pub events: mpsc::Sender<BlockEvent>,
+    pub clickable: bool,
+    pub on_click: OnClick,
+    pub sandbox: Option<Sandbox>,
+    hidden: Arc<AtomicBool>,
+    fallback: &'static str,
+    /// Whether the task is yet to send its first update
+    loading: bool,
+    /// How often the task normally sends an update, if it does so regularly
+    interval: Option<Duration>,
+    /// When the renderer last saw an update
+    updated: Option<DateTime<Local>>,
+    paused: watch::Receiver<bool>,
+}
+
+impl Taskmaster {
+    pub fn new<F, Fut>(
+        name: &'static str,
+        fallback: &'static str,
+        paused: &watch::Receiver<bool>,
+        task_fn: F,
+    ) -> Self
+    where
+        F: FnOnce(watch::Sender<BlockOutput>, Events) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (tx, rx) = watch::channel(BlockOutput::from(fallback));
+        let (events_tx, events_rx) = mpsc::channel(8);
+        let task = task_fn(tx.clone(), Events::new(events_rx, paused.clone()));
+        let _handle = tokio::spawn(async move {
+            let result = task.await;
+            // A task that stopped is broken, not still loading
+            if let Err(err) = &result {
+                eprintln!("{name}: {err:#}");
+                let _ = tx.send(BlockOutput::from(fallback));
+            }
+            result
+        });
+        Self {
+            _handle,
+            rx,
+            name,
+            instance: String::new(),
+            events: events_tx,
+            clickable: false,
+            on_click: OnClick::default(),
+            sandbox: None,
+            hidden: Arc::new(AtomicBool::new(false)),
+            fallback,
+            loading: true,
+            interval: None,
+            updated: None,
+            paused: paused.clone(),
+        }
+    }
+    /// Send the block's click events to its task
+    pub fn clickable(mut self) -> Self {
+        self.clickable = true;
+        self
+    }
+    /// Mark the block stale if its task hasn't updated it for `interval`
+    /// plus the renderer's grace period
+    pub fn updates_every(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+    pub fn status(&self) -> watch::Ref<'_, BlockOutput> {
+        self.rx.borrow()
+    }
+    /// Note whether the task has sent an update since the last render
+    fn observe(&mut self, now: DateTime<Local>) {
+        // Nothing updates while the bar is hidden, so start counting again
+        // when it's shown
+        let resumed = self.paused.has_changed().unwrap_or(false);
+        // The channel closes when the task stops, after its last update
+        let changed = self.rx.has_changed().unwrap_or(self.loading);
+        if changed {
+            self.loading = false;
+        }
+        if changed || resumed || self.updated.is_none() {
+            self.rx.mark_unchanged();
+            self.paused.mark_unchanged();
+            self.updated = Some(now);
+        }
+    }
+    /// Whether the task has gone quiet for longer than usual. The fallback
+    /// text already shows that something's wrong, so it's never stale.
+    fn is_stale(&self, grace: Duration, now: DateTime<Local>) -> bool {
+        let (Some(interval), Some(updated)) = (self.interval, self.updated) else {
+            return false;
+        };
+        let quiet = (now - updated).to_std().unwrap_or_default();
+        quiet > interval + grace && self.rx.borrow().full_text != self.fallback
+    }
+    pub fn is_hidden(&self) -> bool {
+        self.hidden.load(Ordering::Relaxed)
+    }
+    /// Whether the bar needs to send the block's clicks
+    pub fn wants_clicks(&self) -> bool {
+        self.clickable || !self.on_click.is_empty()
+    }
+    pub fn route(&self) -> Route {
+        Route {
+            name: self.name,
+            instance: self.instance.clone(),
+            events: self.clickable.then(|| self.events.clone()),
+            on_click: self.on_click.clone(),
+            sandbox: self.sandbox.clone(),
+        }
+    }
+    #[cfg(feature = "dbus")]
+    pub fn dbus_handle(&self) -> dbus::BlockHandle {
+        dbus::BlockHandle {
+            name: self.name,
+            instance: self.instance.clone(),
+            rx: self.rx.clone(),
+            events: self.events.clone(),
+            hidden: self.hidden.clone(),
+            route: self.route(),
+        }
+    }
+}
+
+/// Helpers for driving blocks through the renderer in tests
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use crate::click::ClickEvent;
+    use crate::config::Config;
+    use crate::system::mock::Mock;
+    use crate::system::System;
+
+    pub fn start(config: &str, mock: &Arc<Mock>) -> Vec<Taskmaster> {
+        let (_, paused) = watch::channel(false);
+        start_paused(config, mock, &paused)
+    }
+
+    pub fn start_paused(
+        config: &str,
+        mock: &Arc<Mock>,
+        paused: &watch::Receiver<bool>,
+    ) -> Vec<Taskmaster> {
+        let config: Config = toml::from_str(config).unwrap();
+        let sys: Sys = mock.clone();
+        spawn(config.blocks, &sys, paused)
+    }
+
+    pub async fn click(task: &Taskmaster, button: u8, modifiers: &[&str]) {
+        let click = ClickEvent {
+            name: task.name.to_string(),
+            instance: String::new(),
+            button,
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+        };
+        task.events.send(BlockEvent::Click(click)).await.unwrap();
+    }
+
+    /// Let the tasks run, then render the status line like the main loop
+    pub async fn render(tasks: &mut [Taskmaster], mock: &Arc<Mock>) -> String {
+        sleep(Duration::from_millis(50)).await;
+        status_line(tasks, &Stale::default(), &Loading::default(), mock.now())
+    }
+
+    /// A throwaway directory standing in for sysfs, procfs or a config dir,
+    /// removed when it's dropped
+    pub struct Fixture {
+        dir: tempfile::TempDir,
+    }
+
+    impl Fixture {
+        pub fn new() -> Self {
+            let dir = tempfile::Builder::new().prefix("subar-").tempdir().unwrap();
+            Self { dir }
+        }
+
+        /// Where `path` is inside the fixture, ready to go in a config
+        pub fn path(&self, path: &str) -> String {
+            self.dir.path().join(path).display().to_string()
+        }
+
+        /// Write `contents` to `path`, creating any missing directories
+        pub fn write(&self, path: &str, contents: impl AsRef<[u8]>) {
+            let path = self.dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        /// Write sysfs style attributes, one newline terminated value per file
+        pub fn attributes(&self, dir: &str, files: &[(&str, &str)]) {
+            for (file, value) in files {
+                self.write(&format!("{dir}/{file}"), format!("{value}\n"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::watch;
+    use tokio::time::{sleep, Duration};
+
+    #[cfg(feature = "audio")]
+    use super::testing::start_paused;
+    use super::testing::{render, start};
+    use super::*;
+    use crate::click::{ClickEvent, BUTTON_LEFT};
+    use crate::config::Config;
+    use crate::system::mock::Mock;
+    use crate::system::{Sys, System};
+
+    #[cfg(feature = "audio")]
+    #[tokio::test(start_paused = true)]
+    async fn volume_and_clock() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "wpctl get-volume @DEFAULT_AUDIO_SINK@",
+            0,
+            "Volume: 0.45 [MUTED]\n",
+        );
+        let mut tasks = start("[[block]]\nblock = \"volume\"", &mock);
+        assert_eq!(
+            render(&mut tasks, &mock).await,
+            concat!(
+                r#"[{"full_text":"🔇 45% ","name":"volume","instance":"0","separator":false,"separator_block_width":0},"#,
+                r#"{"full_text":"🗓️ Wed Oct 14 🕛 09:30:00","name":"clock","instance":"1","separator":false,"separator_block_width":0}]"#,
+            )
+        );
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test(start_paused = true)]
+    async fn hidden_and_empty_blocks_are_skipped() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("wpctl get-volume @DEFAULT_AUDIO_SINK@", 0, "Volume: 0.45\n");
+        let config = "[[block]]\nblock = \"schedule\"\n[[block]]\nblock = \"volume\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% ","name":"volume","instance":"1""#));
+
+        tasks[1].hidden.store(true, Ordering::Relaxed);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"🗓️ Wed Oct 14 🕛 09:30:00","name":"clock","instance":"2""#
+        ));
+    }
+
+    #[cfg(feature = "audio")]
+    #[tokio::test(start_paused = true)]
+    async fn paused_blocks_refresh_on_resume() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let wpctl = "wpctl get-volume @DEFAULT_AUDIO_SINK@";
+        mock.command(wpctl, 0, "Volume: 0.45\n");
+        let (pause, paused) = watch::channel(false);
+        let mut tasks = start_paused("[[block]]\nblock = \"volume\"", &mock, &paused);
+        let reads = || {
+            mock.ran
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|c| *c == wpctl)
+                .count()
+        };
+        render(&mut tasks, &mock).await;
+        assert_eq!(reads(), 1);
+
+        pause.send_replace(true);
+        mock.command(wpctl, 0, "Volume: 0.80\n");
+        sleep(Duration::from_secs(60)).await;
+        assert_eq!(reads(), 1);
+
+        pause.send_replace(false);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔊 80% ","name":"volume""#));
+        assert_eq!(reads(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clicks_are_routed_by_instance() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c echo sda", 0, "sda\n");
+        mock.command("sh -c echo sdb", 0, "sdb\n");
+        mock.command("sh -c udisksctl unmount -b /dev/sdb", 0, "");
+        let disk = |name: &str| {
+            format!(
+                "[[block]]\nblock = \"fortune\"\ninstance = \"{name}\"\n\
+                 source = {{ type = \"command\", command = \"echo {name}\" }}\n\
+                 format = \"{{text}}\"\n\
+                 on_click.left = \"udisksctl unmount -b /dev/{{instance}}\"\n"
+            )
+        };
+        let mut tasks = start(&format!("{}{}", disk("sda"), disk("sdb")), &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"sda ","name":"fortune","instance":"sda","separator":false,"separator_block_width":0},{"full_text":"sdb ","name":"fortune","instance":"sdb""#
+        ));
+
+        let routes: Vec<_> = tasks.iter().map(|t| t.route()).collect();
+        let event = ClickEvent {
+            name: "fortune".to_string(),
+            instance: "sdb".to_string(),
+            button: BUTTON_LEFT,
+            modifiers: Vec::new(),
+        };
+        let sys: Sys = mock.clone();
+        crate::click::dispatch(&routes, &sys, event);
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        assert_eq!(
+            ran.iter()
+                .filter(|c| c.contains("udisksctl"))
+                .collect::<Vec<_>>(),
+            ["sh -c udisksctl unmount -b /dev/sdb"]
+        );
+        // The fortune block's own click action doesn't run
+        assert!(!ran.iter().any(|c| c.starts_with("notify-send")));
+
+        let unknown = "[[block]]\nblock = \"fortune\"\ninstance = \"a\"\ncolour = \"red\"";
+        assert!(toml::from_str::<Config>(unknown).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocks_can_be_clicked_from_keybindings() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c echo hi", 0, "hi\n");
+        let block = |name: &str| {
+            format!(
+                "[[block]]\nblock = \"fortune\"\ninstance = \"{name}\"\n\
+                 source = {{ type = \"command\", command = \"echo hi\" }}\n\
+                 on_click.left = \"echo left {{instance}}\"\n\
+                 on_click.scroll_up = \"echo up {{instance}}\"\n"
+            )
+        };
+        let mut tasks = start(&format!("{}{}", block("a"), block("b")), &mock);
+        render(&mut tasks, &mock).await;
+        let routes: Vec<_> = tasks.iter().map(|t| t.route()).collect();
+        let sys: Sys = mock.clone();
+        // By name clicks every block of that type
+        crate::click::press(&routes, &sys, "fortune", "left").unwrap();
+        crate::click::press(&routes, &sys, "b", "4").unwrap();
+        assert!(crate::click::press(&routes, &sys, "c", "left").is_err());
+        assert!(crate::click::press(&routes, &sys, "a", "sideways").is_err());
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        let clicks: Vec<_> = ran.iter().filter(|c| !c.ends_with("echo hi")).collect();
+        assert_eq!(
+            clicks,
+            ["sh -c echo left a", "sh -c echo left b", "sh -c echo up b"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quiet_blocks_are_marked_stale() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let (_, paused) = watch::channel(false);
+        let task = Taskmaster::new("test", "🧪 ???", &paused, |tx, _events| async move {
+            tx.send("🧪 ok".into())?;
+            std::future::pending().await
+        });
+        let mut tasks = vec![task.updates_every(Duration::from_secs(10))];
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🧪 ok","name":"test""#));
+
+        sleep(Duration::from_secs(40)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r##"[{"full_text":"🧪 ok*","color":"#888888","name":"test""##));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocks_can_say_they_are_showing_old_data() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let (_, paused) = watch::channel(false);
+        let task = Taskmaster::new("test", "🧪 ???", &paused, |tx, _events| async move {
+            tx.send(BlockOutput {
+                stale: true,
+                ..BlockOutput::from("🧪 cached")
+            })?;
+            sleep(Duration::from_secs(1)).await;
+            tx.send("🧪 fetched".into())?;
+            std::future::pending().await
+        });
+        let mut tasks = vec![task];
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r##"[{"full_text":"🧪 cached*","color":"#888888","name":"test""##));
+        sleep(Duration::from_secs(1)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🧪 fetched","name":"test""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_blocks_show_a_spinner_until_they_update() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let (_, paused) = watch::channel(false);
+        let task = Taskmaster::new("test", "🧪 ???", &paused, |tx, _events| async move {
+            sleep(Duration::from_secs(3)).await;
+            tx.send("🧪 ok".into())?;
+            std::future::pending().await
+        });
+        let mut tasks = vec![task];
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r##"[{"full_text":"🧪 ⠋","color":"#888888","name":"test""##));
+        sleep(Duration::from_millis(100)).await;
+        let line = status_line(
+            &mut tasks,
+            &Stale::default(),
+            &Loading::default(),
+            mock.now(),
+        );
+        assert!(line.starts_with(r#"[{"full_text":"🧪 ⠙""#));
+
+        sleep(Duration::from_secs(3)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🧪 ok","name":"test""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocks_that_stop_show_their_fallback() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let (_, paused) = watch::channel(false);
+        let task = Taskmaster::new("test", "🧪 ???", &paused, |_tx, _events| async {
+            anyhow::bail!("Couldn't start")
+        });
+        let mut tasks = vec![task];
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🧪 ???","name":"test""#));
+    }
+
+    #[test]
+    fn closed_output_stops_the_bar() {
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut out = Vec::new();
+        assert!(write_line(&mut out, "[").unwrap());
+        assert_eq!(out, b"[\n");
+        assert!(!write_line(&mut Closed, "[").unwrap());
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::http;
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::Sys;
+
+pub static AQI_FALLBACK: &str = "🌬️ ???";
+/// Air quality is only measured or modelled every hour or so
+pub static AQI_UPDATE_FREQUENCY: u64 = 30 * 60 * 1000;
+static AQI_MAX_RETRY: u64 = 10 * 60 * 1000;
+static OPEN_METEO_AIR_URL: &str = "https://air-quality-api.open-meteo.com/v1/air-quality";
+static WAQI_URL: &str = "https://api.waqi.info/feed";
+/// Where the WAQI token is read from if `api_key` isn't set
+static WAQI_ENV: &str = "WAQI_TOKEN";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub provider: Provider,
+    /// Needed for "open_meteo", and used by "waqi" without a location
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// A city or station for "waqi", e.g. "sydney" or "@5740". It guesses
+    /// from your IP address without one or coordinates.
+    pub location: Option<String>,
+    /// Needed for "waqi". Read from $WAQI_TOKEN if it's not set.
+    pub api_key: Option<Secret>,
+    /// Which index Open-Meteo reports. WAQI only has the US one.
+    pub scale: Scale,
+    pub icon: String,
+    /// Placeholders: icon, aqi, pm2_5, pm10
+    pub format: Template,
+    /// The colour for each of the scale's levels, from best to worst
+    pub colors: Vec<String>,
+    /// The block is urgent at or above this index
+    pub urgent: Option<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            provider: Provider::default(),
+            latitude: None,
+            longitude: None,
+            location: None,
+            api_key: None,
+            scale: Scale::default(),
+            icon: "🌬️".to_string(),
+            format: Template::parse("{icon} {aqi}").unwrap(),
+            colors: [
+                "#88cc44", "#ffdd00", "#ffaa00", "#ff4444", "#aa44ff", "#aa2244",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            urgent: None,
+        }
+    }
+}
+
+/// Where the air quality comes from
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    /// Open-Meteo's modelled air quality, which needs no API key
+    #[default]
+    OpenMeteo,
+    /// The World Air Quality Index project's monitoring stations
+    Waqi,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scale {
+    /// The US EPA's, from 0 to 500
+    #[default]
+    Us,
+    /// The European Environment Agency's, from 0 to 100 and up
+    European,
+}
+
+impl Scale {
+    /// Where each level after "good" starts
+    fn levels(self) -> [f64; 5] {
+        match self {
+            Scale::Us => [51.0, 101.0, 151.0, 201.0, 301.0],
+            Scale::European => [20.0, 40.0, 60.0, 80.0, 100.0],
+        }
+    }
+}
+
+struct AirQuality {
+    aqi: Option<f64>,
+    /// Micrograms per cubic metre from Open-Meteo, or WAQI's index for them
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+}
+
+/// Show the air quality index, coloured by how bad it is
+pub async fn aqi_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(AQI_UPDATE_FREQUENCY),
+        Duration::from_millis(AQI_MAX_RETRY),
+    );
+    loop {
+        let wait = match fetch(&config, &sys).await {
+            Ok(air) => {
+                backoff.reset();
+                tx.send(render(&config, &air))?;
+                Duration::from_millis(AQI_UPDATE_FREQUENCY)
+            }
+            Err(err) => {
+                eprintln!("Couldn't get the air quality. {err:#}");
+                tx.send(AQI_FALLBACK.into())?;
+                backoff.fail()
+            }
+        };
+        events.wait(wait).await;
+    }
+}
+
+fn render(config: &Config, air: &AirQuality) -> BlockOutput {
+    let whole = |value: Option<f64>| value.map(|v| format!("{v:.0}"));
+    let full_text = config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "aqi" => whole(air.aqi),
+        "pm2_5" => whole(air.pm2_5),
+        "pm10" => whole(air.pm10),
+        _ => None,
+    });
+    let scale = match config.provider {
+        Provider::OpenMeteo => config.scale,
+        Provider::Waqi => Scale::Us,
+    };
+    let color = air.aqi.and_then(|aqi| {
+        let level = scale.levels().iter().filter(|from| aqi >= **from).count();
+        config.colors.get(level).cloned()
+    });
+    BlockOutput {
+        full_text,
+        color,
+        urgent: config
+            .urgent
+            .zip(air.aqi)
+            .is_some_and(|(urgent, aqi)| aqi >= urgent),
+        ..BlockOutput::default()
+    }
+}
+
+async fn fetch(config: &Config, sys: &Sys) -> Result<AirQuality> {
+    let coordinates = config.latitude.zip(config.longitude);
+    match config.provider {
+        Provider::OpenMeteo => {
+            let Some((latitude, longitude)) = coordinates else {
+                bail!("The open_meteo provider needs a latitude and longitude");
+            };
+            let index = match config.scale {
+                Scale::Us => "us_aqi",
+                Scale::European => "european_aqi",
+            };
+            let url = format!(
+                "{OPEN_METEO_AIR_URL}?latitude={latitude}&longitude={longitude}\
+                 &current={index},pm2_5,pm10"
+            );
+            let air: Value = serde_json::from_str(&sys.http_get(&url, Vec::new()).await?)?;
+            let now = &air["current"];
+            Ok(AirQuality {
+                aqi: now[index].as_f64(),
+                pm2_5: now["pm2_5"].as_f64(),
+                pm10: now["pm10"].as_f64(),
+            })
+        }
+        Provider::Waqi => {
+            let token = http::encode(&match &config.api_key {
+                Some(key) => key.resolve(sys).await?,
+                None => std::env::var(WAQI_ENV).with_context(|| {
+                    format!("The waqi provider needs an api_key or ${WAQI_ENV}")
+                })?,
+            });
+            let place = match (&config.location, coordinates) {
+                (Some(location), _) => http::encode(location),
+                (None, Some((latitude, longitude))) => format!("geo:{latitude};{longitude}"),
+                (None, None) => "here".to_string(),
+            };
+            let url = format!("{WAQI_URL}/{place}/?token={token}");
+            // The token has to be in the URL, so it's kept out of the logs
+            let body = sys
+                .http_get(&url, Vec::new())
+                .await
+                .map_err(|err| anyhow!(format!("{err:#}").replace(&token, "<api_key>")))?;
+            let air: Value = serde_json::from_str(&body)?;
+            if air["status"] != "ok" {
+                bail!("WAQI sent {}", air["data"]);
+            }
+            let data = &air["data"];
+            Ok(AirQuality {
+                // "-" when the station has no reading
+                aqi: data["aqi"].as_f64(),
+                pm2_5: data["iaqi"]["pm25"]["v"].as_f64(),
+                pm10: data["iaqi"]["pm10"]["v"].as_f64(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn aqi_is_coloured_by_level() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://air-quality-api.open-meteo.com/v1/air-quality?latitude=-33.87&longitude=151.21&current=us_aqi,pm2_5,pm10",
+            r#"{"current":{"us_aqi":162,"pm2_5":76.4,"pm10":98.1}}"#,
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"aqi\"\nlatitude = -33.87\nlongitude = 151.21\n",
+            "format = \"{icon} {aqi} PM2.5 {pm2_5}\"\nurgent = 151",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = r##"🌬️ 162 PM2.5 76 ","color":"#ff4444","urgent":true"##;
+        assert!(line.starts_with(&format!(r#"[{{"full_text":"{expected},"name":"aqi""#)));
+    }
+}
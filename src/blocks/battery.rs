@@ -0,0 +1,900 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::bsd;
+use crate::events::Events;
+use crate::format::{format_countdown, Template};
+use crate::history::{self, BATTERY_CHARGES};
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static BATTERY_FALLBACK: &str = "🔋 ???";
+pub static BATTERY_UPDATE_FREQUENCY: u64 = 5000;
+static BATTERY_MAX_RETRY: u64 = 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    /// Where the kernel lists power supplies, for the "sysfs" source
+    pub path: PathBuf,
+    /// Which batteries to show, by their directory like "BAT0" or model
+    /// name. Every battery is shown, including ones in keyboards and mice,
+    /// without any.
+    pub batteries: Vec<String>,
+    pub charging_icon: String,
+    pub discharging_icon: String,
+    /// Shown instead of `discharging_icon` at or below `low`
+    pub low_icon: String,
+    pub low: f64,
+    /// How each battery is shown. Placeholders: icon, capacity, status,
+    /// name, power (watts drawn or charged with), remaining (until it's
+    /// empty or full)
+    pub format: Template,
+    /// Seconds the power is averaged over for `{power}` and `{remaining}`,
+    /// since it jumps around with what the computer is doing
+    pub window: u64,
+    /// Put between batteries
+    pub separator: String,
+    /// The colour of the block from each percentage down, going by the
+    /// lowest battery that isn't charging
+    pub colors: Vec<BatteryColor>,
+    /// The block is urgent while a battery is discharging at or below this
+    /// percentage
+    pub urgent: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::default(),
+            path: PathBuf::from("/sys/class/power_supply"),
+            batteries: Vec::new(),
+            charging_icon: "⚡".to_string(),
+            discharging_icon: "🔋".to_string(),
+            low_icon: "🪫".to_string(),
+            low: 20.0,
+            format: Template::parse("{icon} {capacity}%").unwrap(),
+            separator: " ".to_string(),
+            window: 120,
+            colors: vec![
+                BatteryColor::new(30.0, "#ffaa00"),
+                BatteryColor::new(15.0, "#ff4444"),
+            ],
+            urgent: 10.0,
+        }
+    }
+}
+
+/// Where the batteries are read from
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// The kernel's power supply class, read every few seconds
+    Sysfs,
+    /// UPower on the system bus, which tells the block as soon as anything
+    /// changes, like the charger being plugged in
+    Upower,
+    /// FreeBSD's `hw.acpi.battery` sysctls, which add up every battery into
+    /// one called "battery"
+    Sysctl,
+    /// OpenBSD's apm(8), which also shows one "battery" for all of them
+    Apm,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        if cfg!(target_os = "openbsd") {
+            Self::Apm
+        } else if cfg!(target_os = "freebsd") {
+            Self::Sysctl
+        } else {
+            Self::Sysfs
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatteryColor {
+    pub below: f64,
+    pub color: String,
+}
+
+impl BatteryColor {
+    fn new(below: f64, color: &str) -> Self {
+        Self {
+            below,
+            color: color.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    Unknown,
+}
+
+impl Status {
+    fn parse(status: &str) -> Self {
+        match status {
+            "Charging" => Status::Charging,
+            "Discharging" => Status::Discharging,
+            "Full" => Status::Full,
+            "Not charging" => Status::NotCharging,
+            _ => Status::Unknown,
+        }
+    }
+
+    fn name(self) -> String {
+        let id = match self {
+            Status::Charging => "battery-charging",
+            Status::Discharging => "battery-discharging",
+            Status::Full => "battery-full",
+            Status::NotCharging => "battery-not-charging",
+            Status::Unknown => "battery-unknown",
+        };
+        tr(id, &[])
+    }
+}
+
+struct Battery {
+    /// The directory, like "BAT0"
+    id: String,
+    /// The model name, or the directory if it doesn't have one
+    name: String,
+    capacity: f64,
+    status: Status,
+    /// Watts
+    power: Option<f64>,
+    /// Watt hours left and when full
+    energy: Option<(f64, f64)>,
+    /// Until it's empty while discharging, or full while charging
+    remaining: Option<Duration>,
+}
+
+impl Battery {
+    /// Average the power over the last `window` of `samples`, and work out
+    /// how long is left from it
+    fn smooth(
+        &mut self,
+        samples: &mut VecDeque<(DateTime<Local>, f64)>,
+        window: Duration,
+        now: DateTime<Local>,
+    ) {
+        if let Some(power) = self.power {
+            samples.push_back((now, power));
+        }
+        while samples
+            .front()
+            .is_some_and(|(at, _)| (now - *at).to_std().unwrap_or_default() > window)
+        {
+            samples.pop_front();
+        }
+        if samples.is_empty() {
+            return;
+        }
+        let power = samples.iter().map(|(_, power)| power).sum::<f64>() / samples.len() as f64;
+        self.power = Some(power);
+        let Some((now, full)) = self.energy.filter(|_| power > 0.0) else {
+            return;
+        };
+        let hours = match self.status {
+            Status::Discharging => now / power,
+            Status::Charging => (full - now).max(0.0) / power,
+            _ => return,
+        };
+        self.remaining = Some(Duration::from_secs_f64(hours * 3600.0));
+    }
+}
+
+/// Show how charged each battery is, from sysfs, UPower, or the BSDs' own
+/// tools. The block is hidden if there aren't any.
+pub async fn battery_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let window = Duration::from_secs(config.window);
+    let wait = Duration::from_millis(BATTERY_UPDATE_FREQUENCY);
+    let mut backoff = Backoff::new(wait, Duration::from_millis(BATTERY_MAX_RETRY));
+    let mut statuses: HashMap<String, Status> = HashMap::new();
+    let mut samples: HashMap<String, VecDeque<(DateTime<Local>, f64)>> = HashMap::new();
+    #[cfg(feature = "dbus")]
+    let mut upower: Option<upower::UPower> = None;
+    loop {
+        let read = match config.source {
+            Source::Sysfs => read_sysfs(&config.path).await,
+            Source::Sysctl => read_sysctl(&sys).await,
+            Source::Apm => read_apm(&sys).await,
+            #[cfg(feature = "dbus")]
+            Source::Upower => match &upower {
+                Some(upower) => upower.batteries().await,
+                // UPower may not have started yet, or it's restarting
+                None => match upower::UPower::connect(&sys).await {
+                    Ok(connected) => {
+                        let read = connected.batteries().await;
+                        upower = Some(connected);
+                        read
+                    }
+                    Err(err) => Err(err.context("Couldn't connect to UPower")),
+                },
+            },
+            #[cfg(not(feature = "dbus"))]
+            Source::Upower => {
+                anyhow::bail!("UPower needs subar to be built with the dbus feature")
+            }
+        };
+        let mut batteries = match read {
+            Ok(batteries) => batteries,
+            Err(err) => {
+                eprintln!("Couldn't read the batteries. {err:#}");
+                tx.send(BATTERY_FALLBACK.into())?;
+                #[cfg(feature = "dbus")]
+                {
+                    upower = None;
+                }
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        if !config.batteries.is_empty() {
+            batteries.retain(|b| config.batteries.iter().any(|c| *c == b.id || *c == b.name));
+        }
+        let now = sys.now();
+        for battery in &mut batteries {
+            let last = statuses.insert(battery.id.clone(), battery.status);
+            let samples = samples.entry(battery.id.clone()).or_default();
+            if last.is_some_and(|last| last != battery.status) {
+                // Power while charging says nothing about discharging
+                samples.clear();
+                if battery.status == Status::Charging {
+                    history::record(&BATTERY_CHARGES, 1.0, now).await;
+                }
+            }
+            battery.smooth(samples, window, now);
+        }
+        tx.send(render(&config, &batteries))?;
+        #[cfg(feature = "dbus")]
+        if let Some(upower) = &mut upower {
+            tokio::select! {
+                _ = events.wait(wait) => {}
+                _ = upower.changed() => {}
+            }
+            continue;
+        }
+        events.wait(wait).await;
+    }
+}
+
+fn render(config: &Config, batteries: &[Battery]) -> BlockOutput {
+    let texts: Vec<_> = batteries
+        .iter()
+        .map(|battery| {
+            let icon = match battery.status {
+                Status::Charging => &config.charging_icon,
+                _ if battery.capacity <= config.low => &config.low_icon,
+                _ => &config.discharging_icon,
+            };
+            config.format.render(|name| match name {
+                "icon" => Some(icon.clone()),
+                "capacity" => Some(format!("{:.0}", battery.capacity)),
+                "status" => Some(battery.status.name()),
+                "name" => Some(battery.name.clone()),
+                "power" => battery.power.map(|watts| format!("{watts:.1}")),
+                "remaining" => battery.remaining.map(format_countdown),
+                _ => None,
+            })
+        })
+        .collect();
+    let lowest = batteries
+        .iter()
+        .filter(|b| b.status != Status::Charging)
+        .map(|b| b.capacity)
+        .min_by(f64::total_cmp);
+    let color = lowest.and_then(|capacity| {
+        let color = config
+            .colors
+            .iter()
+            .filter(|c| capacity < c.below)
+            .min_by(|a, b| a.below.total_cmp(&b.below))?;
+        Some(color.color.clone())
+    });
+    BlockOutput {
+        full_text: texts.join(&config.separator),
+        color,
+        urgent: batteries
+            .iter()
+            .any(|b| b.status == Status::Discharging && b.capacity <= config.urgent),
+        ..BlockOutput::default()
+    }
+}
+
+async fn read_sysfs(path: &Path) -> Result<Vec<Battery>> {
+    let mut entries = tokio::fs::read_dir(path)
+        .await
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let mut batteries = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let dir = entry.path();
+        if read(&dir, "type").await.as_deref() != Some("Battery") {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().into_owned();
+        let name = read(&dir, "model_name")
+            .await
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| id.clone());
+        // Batteries report energy in µWh or charge in µAh, or just a
+        // percentage
+        let capacity = match number(&dir, "capacity").await {
+            Some(capacity) => Some(capacity),
+            None => match percent(&dir, "energy_now", "energy_full").await {
+                Some(capacity) => Some(capacity),
+                None => percent(&dir, "charge_now", "charge_full").await,
+            },
+        };
+        let Some(capacity) = capacity else {
+            continue;
+        };
+        let power = match number(&dir, "power_now").await {
+            Some(microwatts) => Some(microwatts / 1e6),
+            None => match (
+                number(&dir, "current_now").await,
+                number(&dir, "voltage_now").await,
+            ) {
+                (Some(microamps), Some(microvolts)) => Some(microamps * microvolts / 1e12),
+                _ => None,
+            },
+        };
+        // Charge is turned into energy with the voltage, in µV
+        let energy = match (
+            number(&dir, "energy_now").await,
+            number(&dir, "energy_full").await,
+        ) {
+            (Some(now), Some(full)) => Some((now / 1e6, full / 1e6)),
+            _ => match (
+                number(&dir, "charge_now").await,
+                number(&dir, "charge_full").await,
+                number(&dir, "voltage_now").await,
+            ) {
+                (Some(now), Some(full), Some(volts)) => {
+                    Some((now * volts / 1e12, full * volts / 1e12))
+                }
+                _ => None,
+            },
+        };
+        let status = read(&dir, "status").await.unwrap_or_default();
+        batteries.push(Battery {
+            id,
+            name,
+            capacity: capacity.clamp(0.0, 100.0),
+            status: Status::parse(&status),
+            power: power.map(f64::abs),
+            energy,
+            remaining: None,
+        });
+    }
+    batteries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(batteries)
+}
+
+/// FreeBSD's ACPI battery, which is every battery added together
+async fn read_sysctl(sys: &Sys) -> Result<Vec<Battery>> {
+    let [capacity, state, minutes, acline] = bsd::sysctl_numbers(
+        sys,
+        [
+            "hw.acpi.battery.life",
+            "hw.acpi.battery.state",
+            "hw.acpi.battery.time",
+            "hw.acpi.acline",
+        ],
+    )
+    .await?;
+    // Bit 0 is set while discharging, and bit 1 while charging. All three
+    // bits mean there's no battery.
+    let state = state as u32;
+    if state == 7 {
+        return Ok(Vec::new());
+    }
+    let status = if state & 2 != 0 {
+        Status::Charging
+    } else if state & 1 != 0 {
+        Status::Discharging
+    } else if acline == 1.0 {
+        plugged_in(capacity)
+    } else {
+        Status::Unknown
+    };
+    Ok(vec![bsd_battery(capacity, status, Some(minutes))])
+}
+
+/// OpenBSD's battery, from apm(8), which adds every battery together
+async fn read_apm(sys: &Sys) -> Result<Vec<Battery>> {
+    let apm = |flag: &'static str| async move {
+        let stdout = bsd::run(sys, Command::new("apm").arg(flag)).await?;
+        Ok::<_, anyhow::Error>(stdout.trim().to_string())
+    };
+    // 3 is charging and 4 is no battery. The rest are how charged it is.
+    let state = apm("-b").await?;
+    if state == "4" {
+        return Ok(Vec::new());
+    }
+    let capacity = bsd::number("apm -l", &apm("-l").await?)?;
+    let status = match (state.as_str(), apm("-a").await?.as_str()) {
+        ("3", _) => Status::Charging,
+        (_, "0") => Status::Discharging,
+        (_, "1") => plugged_in(capacity),
+        _ => Status::Unknown,
+    };
+    let minutes = apm("-m").await?.parse().ok();
+    Ok(vec![bsd_battery(capacity, status, minutes)])
+}
+
+/// What a battery that's plugged in but isn't charging is doing
+fn plugged_in(capacity: f64) -> Status {
+    if capacity >= 100.0 {
+        Status::Full
+    } else {
+        Status::NotCharging
+    }
+}
+
+/// The BSDs only give the minutes left while discharging, without the
+/// power, and -1 or "unknown" otherwise
+fn bsd_battery(capacity: f64, status: Status, minutes: Option<f64>) -> Battery {
+    let remaining = minutes
+        .filter(|minutes| *minutes >= 0.0 && status == Status::Discharging)
+        .map(|minutes| Duration::from_secs_f64(minutes * 60.0));
+    Battery {
+        id: "battery".to_string(),
+        name: "battery".to_string(),
+        capacity: capacity.clamp(0.0, 100.0),
+        status,
+        power: None,
+        energy: None,
+        remaining,
+    }
+}
+
+async fn read(dir: &Path, file: &str) -> Option<String> {
+    let contents = tokio::fs::read_to_string(dir.join(file)).await.ok()?;
+    Some(contents.trim().to_string())
+}
+
+async fn number(dir: &Path, file: &str) -> Option<f64> {
+    read(dir, file).await?.parse().ok()
+}
+
+async fn percent(dir: &Path, now: &str, full: &str) -> Option<f64> {
+    Some(number(dir, now).await? / number(dir, full).await? * 100.0)
+}
+
+#[cfg(feature = "dbus")]
+mod upower {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use anyhow::Result;
+    use zbus::export::futures_core::Stream;
+    use zbus::message::Type;
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::{proxy, Connection, MatchRule, MessageStream};
+
+    use super::{Battery, Status};
+    use crate::system::Sys;
+
+    static UPOWER_SERVICE: &str = "org.freedesktop.UPower";
+    /// UPower's device type for mains power, which is the only kind that
+    /// isn't a battery of some sort
+    static UPOWER_LINE_POWER: u32 = 1;
+
+    #[proxy(
+        interface = "org.freedesktop.UPower",
+        default_service = "org.freedesktop.UPower",
+        default_path = "/org/freedesktop/UPower"
+    )]
+    trait Manager {
+        fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.UPower.Device",
+        default_service = "org.freedesktop.UPower"
+    )]
+    trait Device {
+        #[zbus(property, name = "Type")]
+        fn kind(&self) -> zbus::Result<u32>;
+        #[zbus(property)]
+        fn is_present(&self) -> zbus::Result<bool>;
+        #[zbus(property)]
+        fn native_path(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn model(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn percentage(&self) -> zbus::Result<f64>;
+        #[zbus(property)]
+        fn state(&self) -> zbus::Result<u32>;
+        #[zbus(property)]
+        fn energy(&self) -> zbus::Result<f64>;
+        #[zbus(property)]
+        fn energy_full(&self) -> zbus::Result<f64>;
+        #[zbus(property)]
+        fn energy_rate(&self) -> zbus::Result<f64>;
+    }
+
+    pub struct UPower {
+        conn: Connection,
+        /// Every signal UPower sends, for devices changing or being added
+        /// and removed
+        signals: MessageStream,
+    }
+
+    impl UPower {
+        pub async fn connect(sys: &Sys) -> Result<Self> {
+            let conn = sys.system_bus().await?;
+            let rule = MatchRule::builder()
+                .msg_type(Type::Signal)
+                .sender(UPOWER_SERVICE)?
+                .build();
+            let signals = MessageStream::for_match_rule(rule, &conn, None).await?;
+            Ok(Self { conn, signals })
+        }
+
+        pub async fn batteries(&self) -> Result<Vec<Battery>> {
+            let paths = ManagerProxy::new(&self.conn)
+                .await?
+                .enumerate_devices()
+                .await?;
+            let mut batteries = Vec::new();
+            for path in paths {
+                let device = DeviceProxy::builder(&self.conn).path(path)?.build().await?;
+                if device.kind().await? == UPOWER_LINE_POWER || !device.is_present().await? {
+                    continue;
+                }
+                let id = device.native_path().await?;
+                let model = device.model().await.unwrap_or_default();
+                let status = match device.state().await? {
+                    1 => Status::Charging,
+                    2 | 3 | 6 => Status::Discharging,
+                    4 => Status::Full,
+                    5 => Status::NotCharging,
+                    _ => Status::Unknown,
+                };
+                let full = device.energy_full().await.unwrap_or_default();
+                let rate = device.energy_rate().await.unwrap_or_default();
+                batteries.push(Battery {
+                    name: if model.is_empty() { id.clone() } else { model },
+                    id,
+                    capacity: device.percentage().await?,
+                    status,
+                    power: (rate != 0.0).then_some(rate.abs()),
+                    energy: (full > 0.0).then_some((device.energy().await?, full)),
+                    // UPower's own estimate isn't used, so both sources are
+                    // averaged the same way
+                    remaining: None,
+                });
+            }
+            batteries.sort_by(|a, b| a.id.cmp(&b.id));
+            Ok(batteries)
+        }
+
+        /// Wait for UPower to say something has changed
+        pub async fn changed(&mut self) {
+            let signal = poll_fn(|cx| Pin::new(&mut self.signals).poll_next(cx)).await;
+            if signal.is_none() {
+                // The connection closed, so the block goes back to polling
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn unreadable_batteries_are_retried() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        let config = format!(
+            "[[block]]\nblock = \"battery\"\npath = {:?}\nformat = \"{{capacity}}%\"",
+            sys.path("power_supply")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🔋 ??? ","name":"battery""#));
+
+        sys.attributes(
+            "power_supply/BAT0",
+            &[("type", "Battery"), ("status", "Full"), ("capacity", "100")],
+        );
+        sleep(Duration::from_secs(10)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"100% ","name":"battery""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn batteries_are_read_from_sysfs() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        sys.attributes("AC", &[("type", "Mains"), ("online", "0")]);
+        sys.attributes(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "12"),
+                ("power_now", "8500000"),
+            ],
+        );
+        sys.attributes(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("model_name", "MX Keys"),
+                ("capacity", "60"),
+            ],
+        );
+        let config = format!(
+            "[[block]]\nblock = \"battery\"\npath = {:?}\nformat = \"{{icon}} {{capacity}}%[ {{power}}W]\"",
+            sys.path("")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"🪫 12% 8.5W 🔋 60% ","color":"#ff4444","name":"battery""##
+        ));
+        sys.attributes("BAT0", &[("capacity", "9")]);
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"🪫 9% 8.5W 🔋 60% ","color":"#ff4444","urgent":true"##
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn battery_time_left_uses_the_average_power() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        sys.attributes(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "60"),
+                ("energy_now", "30000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        let config = format!(
+            "[[block]]\nblock = \"battery\"\npath = {:?}\nformat = \"{{power}}W {{remaining}}\"",
+            sys.path("")
+        );
+        let mut tasks = start(&config, &mock);
+        let text = |line: String| line.split('"').nth(3).unwrap().to_string();
+        assert_eq!(text(render(&mut tasks, &mock).await), "10.0W 3h 00m ");
+        sys.attributes("BAT1", &[("power_now", "20000000")]);
+        sleep(Duration::from_secs(5)).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "15.0W 2h 00m ");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn freebsd_batteries_are_read_with_sysctl() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "sysctl -n hw.acpi.battery.life hw.acpi.battery.state hw.acpi.battery.time hw.acpi.acline",
+            0,
+            "80\n1\n150\n0\n",
+        );
+        let config = r#"
+[[block]]
+block = "battery"
+source = "sysctl"
+format = "{icon} {capacity}% {remaining}"
+"#;
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🔋 80% 2h 30m ","name":"battery""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn openbsd_batteries_are_read_with_apm() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("apm -b", 0, "3\n");
+        mock.command("apm -l", 0, "45\n");
+        mock.command("apm -a", 0, "1\n");
+        mock.command("apm -m", 0, "unknown\n");
+        let config = r#"
+[[block]]
+block = "battery"
+source = "apm"
+format = "{icon} {capacity}%[ {remaining}]"
+"#;
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"⚡ 45% ","name":"battery""#));
+    }
+
+    /// UPower over a fake system bus
+    #[cfg(feature = "dbus")]
+    mod upower {
+        use zbus::interface;
+        use zbus::zvariant::OwnedObjectPath;
+
+        use crate::bar::testing::{render, start};
+        use crate::events::BlockEvent;
+        use crate::system::mock::Mock;
+
+        static BATTERY: &str = "/org/freedesktop/UPower/devices/battery_BAT0";
+        static LINE_POWER: &str = "/org/freedesktop/UPower/devices/line_power_AC";
+
+        struct Manager;
+
+        #[interface(name = "org.freedesktop.UPower")]
+        impl Manager {
+            fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+                [LINE_POWER, BATTERY]
+                    .into_iter()
+                    .map(|path| OwnedObjectPath::try_from(path).unwrap())
+                    .collect()
+            }
+        }
+
+        struct Device {
+            kind: u32,
+            native_path: &'static str,
+            state: u32,
+            energy_rate: f64,
+        }
+
+        #[interface(name = "org.freedesktop.UPower.Device")]
+        impl Device {
+            #[zbus(property, name = "Type")]
+            fn kind(&self) -> u32 {
+                self.kind
+            }
+
+            #[zbus(property)]
+            fn is_present(&self) -> bool {
+                true
+            }
+
+            #[zbus(property)]
+            fn native_path(&self) -> String {
+                self.native_path.to_string()
+            }
+
+            #[zbus(property)]
+            fn model(&self) -> String {
+                "Framework".to_string()
+            }
+
+            #[zbus(property)]
+            fn percentage(&self) -> f64 {
+                40.0
+            }
+
+            #[zbus(property)]
+            fn state(&self) -> u32 {
+                self.state
+            }
+
+            #[zbus(property)]
+            fn energy(&self) -> f64 {
+                20.0
+            }
+
+            #[zbus(property)]
+            fn energy_full(&self) -> f64 {
+                50.0
+            }
+
+            #[zbus(property)]
+            fn energy_rate(&self) -> f64 {
+                self.energy_rate
+            }
+        }
+
+        static CONFIG: &str = r#"
+            [[block]]
+            block = "battery"
+            source = "upower"
+            format = "{name} {status} {capacity}%[ {power}W]"
+        "#;
+
+        /// Start UPower with mains power and a discharging battery
+        async fn serve_upower(bus: &zbus::Connection) {
+            let server = bus.object_server();
+            server.at("/org/freedesktop/UPower", Manager).await.unwrap();
+            let line_power = Device {
+                kind: 1,
+                native_path: "AC",
+                state: 0,
+                energy_rate: 0.0,
+            };
+            server.at(LINE_POWER, line_power).await.unwrap();
+            let battery = Device {
+                kind: 2,
+                native_path: "BAT0",
+                state: 2,
+                energy_rate: -10.0,
+            };
+            server.at(BATTERY, battery).await.unwrap();
+        }
+
+        /// Real time, since paused time would skip ahead while zbus waits on
+        /// the socket
+        #[tokio::test]
+        async fn upower_batteries_update_when_they_change() {
+            let mock = Mock::new("2026-10-14 09:30:00");
+            let bus = mock.serve_system_bus().await;
+            serve_upower(&bus).await;
+            let server = bus.object_server();
+            let mut tasks = start(CONFIG, &mock);
+            assert!(render(&mut tasks, &mock).await.starts_with(
+                r#"[{"full_text":"Framework discharging 40% 10.0W ","name":"battery""#
+            ));
+
+            // Plugging in the charger is shown straight away, well before the
+            // block would look again
+            let device = server.interface::<_, Device>(BATTERY).await.unwrap();
+            let mut battery = device.get_mut().await;
+            battery.state = 1;
+            battery.energy_rate = 0.0;
+            battery
+                .state_changed(device.signal_emitter())
+                .await
+                .unwrap();
+            drop(battery);
+            assert!(render(&mut tasks, &mock)
+                .await
+                .starts_with(r#"[{"full_text":"Framework charging 40% ","name":"battery""#));
+        }
+
+        #[tokio::test]
+        async fn the_block_waits_for_upower_to_start() {
+            let mock = Mock::new("2026-10-14 09:30:00");
+            let mut tasks = start(CONFIG, &mock);
+            assert!(render(&mut tasks, &mock)
+                .await
+                .starts_with(r#"[{"full_text":"🔋 ??? ","name":"battery""#));
+
+            // The bus is up but UPower isn't yet
+            let bus = mock.serve_system_bus().await;
+            tasks[0].events.send(BlockEvent::Refresh).await.unwrap();
+            assert!(render(&mut tasks, &mock)
+                .await
+                .starts_with(r#"[{"full_text":"🔋 ??? ","name":"battery""#));
+
+            serve_upower(&bus).await;
+            tasks[0].events.send(BlockEvent::Refresh).await.unwrap();
+            assert!(render(&mut tasks, &mock).await.starts_with(
+                r#"[{"full_text":"Framework discharging 40% 10.0W ","name":"battery""#
+            ));
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{
+    DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Weekday,
+};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::{format_countdown, Template};
+use crate::output::BlockOutput;
+use crate::secret::expand_home;
+use crate::system::{command_line, Sys};
+use crate::timezone::Zone;
+
+pub static CALENDAR_FALLBACK: &str = "📅 ???";
+pub static CALENDAR_UPDATE_FREQUENCY: u64 = 1000;
+/// How often the calendar is read again, in seconds
+static CALENDAR_RELOAD: i64 = 60;
+/// The most occurrences of a repeating event looked through, which is
+/// daily for a few centuries
+static CALENDAR_MAX_REPEATS: usize = 100_000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    pub icon: String,
+    /// Placeholders: icon, title, time, countdown
+    pub format: Template,
+    /// Turn urgent this many minutes before an event starts
+    pub urgent: i64,
+    /// Only show events starting within this many hours
+    pub lookahead: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::Ics {
+                path: PathBuf::from("~/.local/share/calendars"),
+            },
+            icon: "📅".to_string(),
+            format: Template::parse("{icon} {title} in {countdown}").unwrap(),
+            urgent: 5,
+            lookahead: 24,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Source {
+    /// An .ics file, or a directory with them in, like vdirsyncer keeps
+    Ics { path: PathBuf },
+    /// `khal list`, with `datetime_format` matching khal's
+    /// `datetimeformat`
+    Khal {
+        #[serde(default = "default_khal_format")]
+        datetime_format: String,
+    },
+    /// The next appointment from `calcurse -n`
+    Calcurse,
+}
+
+fn default_khal_format() -> String {
+    "%d/%m/%Y %H:%M".to_string()
+}
+
+struct Upcoming {
+    start: DateTime<Local>,
+    title: String,
+}
+
+/// Show the next event from a calendar with a countdown, turning urgent
+/// shortly before it starts. The block is hidden while nothing is coming up.
+pub async fn calendar_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut upcoming: Vec<Upcoming> = Vec::new();
+    let mut read_at: Option<DateTime<Local>> = None;
+    loop {
+        let now = sys.now();
+        if read_at.is_none_or(|at| (now - at).num_seconds() >= CALENDAR_RELOAD) {
+            let until = now + chrono::Duration::hours(config.lookahead);
+            match read(&config.source, &sys, now, until).await {
+                Ok(read) => upcoming = read,
+                Err(err) => {
+                    eprintln!("Couldn't read the calendar. {err:#}");
+                    tx.send(CALENDAR_FALLBACK.into())?;
+                    events
+                        .wait(Duration::from_secs(CALENDAR_RELOAD as u64))
+                        .await;
+                    continue;
+                }
+            }
+            read_at = Some(now);
+        }
+        let next = upcoming
+            .iter()
+            .filter(|event| event.start > now)
+            .min_by_key(|event| event.start);
+        let output = match next {
+            Some(event) => {
+                let remaining = (event.start - now).to_std().unwrap_or_default();
+                let full_text = config.format.render(|name| match name {
+                    "icon" => Some(config.icon.clone()),
+                    "title" => Some(event.title.clone()),
+                    "time" => Some(event.start.format("%H:%M").to_string()),
+                    "countdown" => Some(format_countdown(remaining)),
+                    _ => None,
+                });
+                BlockOutput {
+                    full_text,
+                    urgent: remaining.as_secs() as i64 <= config.urgent * 60,
+                    ..BlockOutput::default()
+                }
+            }
+            None => BlockOutput::default(),
+        };
+        tx.send(output)?;
+        events
+            .wait(Duration::from_millis(CALENDAR_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// The events starting between `now` and `until`
+async fn read(
+    source: &Source,
+    sys: &Sys,
+    now: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Result<Vec<Upcoming>> {
+    match source {
+        Source::Ics { path } => {
+            let path = expand_home(path);
+            let mut upcoming = Vec::new();
+            for file in ics_files(&path).await? {
+                let ics = tokio::fs::read_to_string(&file)
+                    .await
+                    .with_context(|| format!("Couldn't read {}", file.display()))?;
+                for event in parse_ics(&ics) {
+                    if let Some(start) = event.next_start(now, until) {
+                        upcoming.push(Upcoming {
+                            start,
+                            title: event.summary,
+                        });
+                    }
+                }
+            }
+            Ok(upcoming)
+        }
+        Source::Khal { datetime_format } => {
+            let mut khal = Command::new("khal");
+            khal.args(["list", "--day-format", "", "--format", "{start}|{title}"])
+                .arg("now")
+                .arg(format!("{}m", (until - now).num_minutes()));
+            let stdout = run(sys, &mut khal).await?;
+            // All day events only have a date, so they don't parse and are
+            // left out
+            Ok(stdout
+                .lines()
+                .filter_map(|line| {
+                    let (start, title) = line.split_once('|')?;
+                    let start =
+                        NaiveDateTime::parse_from_str(start.trim(), datetime_format).ok()?;
+                    Some(Upcoming {
+                        start: Local.from_local_datetime(&start).earliest()?,
+                        title: title.trim().to_string(),
+                    })
+                })
+                .collect())
+        }
+        Source::Calcurse => {
+            let stdout = run(sys, Command::new("calcurse").arg("-n")).await?;
+            // Like "next appointment:\n   [01:05] Standup", with how long
+            // until it starts
+            let upcoming = stdout.lines().find_map(|line| {
+                let (countdown, title) = line.trim().strip_prefix('[')?.split_once(']')?;
+                let (hours, minutes) = countdown.split_once(':')?;
+                let minutes = hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?;
+                let start = now + chrono::Duration::minutes(minutes);
+                (start <= until).then(|| Upcoming {
+                    start,
+                    title: title.trim().to_string(),
+                })
+            });
+            Ok(upcoming.into_iter().collect())
+        }
+    }
+}
+
+async fn run(sys: &Sys, command: &mut Command) -> Result<String> {
+    let output = sys.output(command).await?;
+    if !output.status.success() {
+        bail!("`{}` failed with {}", command_line(command), output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `path` if it's a file, or the .ics files anywhere under it
+async fn ics_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    if !tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("Couldn't read {}", path.display()))?
+        .is_dir()
+    {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "ics") {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// What a VEVENT's local times are in
+enum Clock {
+    Utc,
+    /// Times without a zone are in whatever zone the computer is in
+    Floating,
+    Zone(Zone),
+}
+
+struct Vevent {
+    start: NaiveDateTime,
+    clock: Clock,
+    summary: String,
+    repeats: Option<Repeats>,
+}
+
+/// The parts of an RRULE that are understood
+struct Repeats {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    /// For weekly events
+    weekdays: Vec<Weekday>,
+}
+
+#[derive(Clone, Copy)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The timed events in an iCalendar file. All day and cancelled events are
+/// left out, as are repeats that were moved or cancelled one by one.
+fn parse_ics(ics: &str) -> Vec<Vevent> {
+    // Long lines are folded onto the next line, starting with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, Vec<String>, String)>> = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = name.split(';');
+        let name = params.next().unwrap_or_default().to_ascii_uppercase();
+        let params: Vec<String> = params.map(str::to_string).collect();
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = current.take().and_then(|props| vevent(&props)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(props) = &mut current {
+                    props.push((name, params, value.to_string()));
+                }
+            }
+        }
+    }
+    events
+}
+
+fn vevent(props: &[(String, Vec<String>, String)]) -> Option<Vevent> {
+    let prop = |name: &str| props.iter().find(|(n, _, _)| n == name);
+    if prop("RECURRENCE-ID").is_some()
+        || prop("STATUS").is_some_and(|(_, _, status)| status == "CANCELLED")
+    {
+        return None;
+    }
+    let (_, params, value) = prop("DTSTART")?;
+    if params.iter().any(|p| p == "VALUE=DATE") {
+        return None;
+    }
+    let (start, utc) = parse_ics_time(value)?;
+    let zone = params.iter().find_map(|p| p.strip_prefix("TZID="));
+    let clock = match (utc, zone) {
+        (true, _) => Clock::Utc,
+        // Zones that aren't in the tz database, like Windows' names, are
+        // taken to be local
+        (false, Some(zone)) => {
+            Zone::load(zone.trim_matches('"')).map_or(Clock::Floating, Clock::Zone)
+        }
+        (false, None) => Clock::Floating,
+    };
+    let summary = prop("SUMMARY").map_or_else(String::new, |(_, _, summary)| unescape(summary));
+    let repeats = prop("RRULE").and_then(|(_, _, rule)| parse_rrule(rule));
+    Some(Vevent {
+        start,
+        clock,
+        summary,
+        repeats,
+    })
+}
+
+/// A DATE-TIME like "20261014T093000", and whether it ended in Z for UTC
+fn parse_ics_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y%m%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        })
+        .ok()?;
+    Some((time, utc))
+}
+
+fn parse_rrule(rule: &str) -> Option<Repeats> {
+    let mut repeats = Repeats {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        weekdays: Vec::new(),
+    };
+    let mut frequency = None;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key {
+            "FREQ" => {
+                frequency = Some(match value {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    // Anything more often isn't worth a countdown
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => repeats.interval = value.parse().ok()?,
+            "COUNT" => repeats.count = Some(value.parse().ok()?),
+            "UNTIL" => repeats.until = Some(parse_ics_time(value)?.0),
+            "BYDAY" => {
+                repeats.weekdays = value
+                    .split(',')
+                    .map(|day| match day {
+                        "MO" => Some(Weekday::Mon),
+                        "TU" => Some(Weekday::Tue),
+                        "WE" => Some(Weekday::Wed),
+                        "TH" => Some(Weekday::Thu),
+                        "FR" => Some(Weekday::Fri),
+                        "SA" => Some(Weekday::Sat),
+                        "SU" => Some(Weekday::Sun),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+            }
+            _ => {}
+        }
+    }
+    repeats.frequency = frequency?;
+    repeats.interval = repeats.interval.max(1);
+    Some(repeats)
+}
+
+/// Text with iCalendar's backslash escapes, on one line
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push(' '),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+impl Vevent {
+    fn localise(&self, time: NaiveDateTime) -> Option<DateTime<Local>> {
+        match &self.clock {
+            Clock::Utc => Some(time.and_utc().with_timezone(&Local)),
+            Clock::Floating => Local.from_local_datetime(&time).earliest(),
+            Clock::Zone(zone) => {
+                // The offset at the time as though it were UTC is nearly
+                // always the right one, and checking again fixes the rest
+                let guess = time.and_utc().timestamp();
+                let first = guess - zone.at(guess).offset as i64;
+                let timestamp = guess - zone.at(first).offset as i64;
+                DateTime::from_timestamp(timestamp, 0).map(|t| t.with_timezone(&Local))
+            }
+        }
+    }
+
+    /// When it next starts after `now`, if that's before `until`
+    fn next_start(&self, now: DateTime<Local>, until: DateTime<Local>) -> Option<DateTime<Local>> {
+        let Some(repeats) = &self.repeats else {
+            return self
+                .localise(self.start)
+                .filter(|s| *s > now && *s <= until);
+        };
+        let mut seen = 0;
+        for period in 0..CALENDAR_MAX_REPEATS {
+            let step = period as u32 * repeats.interval;
+            let starts = match repeats.frequency {
+                Frequency::Daily => vec![self.start.checked_add_days(Days::new(step.into()))],
+                Frequency::Weekly if !repeats.weekdays.is_empty() => {
+                    let monday = self.start.date()
+                        - chrono::Duration::days(
+                            self.start.weekday().num_days_from_monday().into(),
+                        );
+                    let week = monday + chrono::Duration::weeks(step.into());
+                    let mut days: Vec<_> = repeats
+                        .weekdays
+                        .iter()
+                        .map(|day| {
+                            let date =
+                                week + chrono::Duration::days(day.num_days_from_monday().into());
+                            Some(date.and_time(self.start.time()))
+                        })
+                        .collect();
+                    days.sort();
+                    days
+                }
+                Frequency::Weekly => vec![self.start.checked_add_days(Days::new(step as u64 * 7))],
+                Frequency::Monthly => vec![self.start.checked_add_months(Months::new(step))],
+                Frequency::Yearly => vec![self.start.checked_add_months(Months::new(step * 12))],
+            };
+            // Months without the day, like the 31st, are skipped rather than
+            // moved to their last day
+            let starts = starts.into_iter().flatten().filter(|s| {
+                s.day() == self.start.day()
+                    || matches!(repeats.frequency, Frequency::Daily | Frequency::Weekly)
+            });
+            for start in starts {
+                // BYDAY can put days in the first week before DTSTART
+                if start < self.start {
+                    continue;
+                }
+                if repeats.until.is_some_and(|until| start > until)
+                    || repeats.count.is_some_and(|count| seen >= count)
+                {
+                    return None;
+                }
+                seen += 1;
+                let local = self.localise(start)?;
+                if local > until {
+                    return None;
+                }
+                if local > now {
+                    return Some(local);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn calendar_counts_down_to_the_next_event() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let dir = Fixture::new();
+        let ics = [
+            "BEGIN:VCALENDAR",
+            "BEGIN:VEVENT",
+            "DTSTART:20260101T094200Z",
+            "RRULE:FREQ=DAILY",
+            "SUMMARY:Standup",
+            "END:VEVENT",
+            "BEGIN:VEVENT",
+            "DTSTART;TZID=Australia/Sydney:20261015T020000",
+            "SUMMARY:Dentist\\, proba",
+            " bly",
+            "END:VEVENT",
+            "BEGIN:VEVENT",
+            "DTSTART;VALUE=DATE:20261014",
+            "SUMMARY:Holiday",
+            "END:VEVENT",
+            "END:VCALENDAR",
+        ];
+        dir.write("calendar.ics", ics.join("\r\n"));
+        let config = format!(
+            "[[block]]\nblock = \"calendar\"\nsource = {{ type = \"ics\", path = {:?} }}",
+            dir.path("calendar.ics")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"📅 Standup in 12m ","name""#));
+        sleep(Duration::from_secs(7 * 60)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"📅 Standup in 5m ","urgent":true"#));
+        sleep(Duration::from_secs(6 * 60)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"📅 Dentist, probably in 5h 17m ","name""#));
+    }
+}
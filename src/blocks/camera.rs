@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::Template;
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static CAMERA_FALLBACK: &str = "📷 ???";
+pub static CAMERA_UPDATE_FREQUENCY: u64 = 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The camera is considered enabled while this path exists
+    pub path: PathBuf,
+    /// Shell command run when clicking the block while the camera is disabled
+    pub enable_command: String,
+    /// Shell command run when clicking the block while the camera is enabled
+    pub disable_command: String,
+    pub enabled_icon: String,
+    pub disabled_icon: String,
+    /// Placeholders: icon, state
+    pub format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/sys/module/uvcvideo"),
+            enable_command: "pkexec modprobe uvcvideo".to_string(),
+            disable_command: "pkexec modprobe -r uvcvideo".to_string(),
+            enabled_icon: "📷".to_string(),
+            disabled_icon: "🚫".to_string(),
+            format: Template::parse("{icon}").unwrap(),
+        }
+    }
+}
+
+pub async fn camera_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    loop {
+        let enabled = is_enabled(&config.path).await;
+        let (icon, state) = if enabled {
+            (&config.enabled_icon, tr("camera-on", &[]))
+        } else {
+            (&config.disabled_icon, tr("camera-off", &[]))
+        };
+        let status = config.format.render(|name| match name {
+            "icon" => Some(icon.clone()),
+            "state" => Some(state.clone()),
+            _ => None,
+        });
+        tx.send(status.into())?;
+
+        let click = events
+            .wait(Duration::from_millis(CAMERA_UPDATE_FREQUENCY))
+            .await;
+        if click.is_some_and(|c| c.button == BUTTON_LEFT) {
+            let cmd = if enabled {
+                &config.disable_command
+            } else {
+                &config.enable_command
+            };
+            toggle(&sys, cmd).await;
+        }
+    }
+}
+
+async fn is_enabled(path: &Path) -> bool {
+    tokio::fs::try_exists(path).await.unwrap_or(false)
+}
+
+async fn toggle(sys: &Sys, cmd: &str) {
+    match sys.output(Command::new("sh").arg("-c").arg(cmd)).await {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => eprintln!("`{cmd}` failed with {}", output.status),
+        Err(err) => eprintln!("Couldn't run `{cmd}`. {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start, Fixture};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn camera_toggles_on_click() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c enable", 0, "");
+        let config = r#"
+            [[block]]
+            block = "camera"
+            path = "/nonexistent"
+            enable_command = "enable"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🚫 ","name":"camera""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        render(&mut tasks, &mock).await;
+        assert_eq!(*mock.ran.lock().unwrap(), ["sh -c enable"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn camera_shows_whether_it_is_enabled() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c disable", 0, "");
+        let sys = Fixture::new();
+        let config = format!(
+            r#"
+            [[block]]
+            block = "camera"
+            path = {:?}
+            disable_command = "disable"
+            format = "{{icon}} {{state}}"
+            "#,
+            sys.path("module/uvcvideo")
+        );
+        let mut tasks = start(&config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🚫 off ","name":"camera""#));
+
+        // Loading the module outside subar shows up within a second
+        sys.write("module/uvcvideo/refcnt", "0\n");
+        sleep(Duration::from_secs(1)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"📷 on ","name":"camera""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        render(&mut tasks, &mock).await;
+        assert_eq!(*mock.ran.lock().unwrap(), ["sh -c disable"]);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::http::encode;
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::Sys;
+
+pub static CHAT_FALLBACK: &str = "💬 ???";
+static CHAT_MAX_RETRY: u64 = 10 * 60 * 1000;
+/// Only fetch what's needed for the unread counts
+pub static MATRIX_FILTER: &str = r#"{"room":{"timeline":{"limit":1},"state":{"types":[]},"ephemeral":{"types":[]},"account_data":{"types":[]}},"presence":{"types":[]},"account_data":{"types":[]}}"#;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub provider: Provider,
+    /// Seconds between checks
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    /// Placeholders: icon, highlights, unread
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// Show the block when there are unread messages but no highlights
+    #[serde(default)]
+    pub show_unread: bool,
+}
+
+fn default_interval() -> u64 {
+    30
+}
+
+fn default_icon() -> String {
+    "💬".to_string()
+}
+
+fn default_format() -> Template {
+    Template::parse("{icon} {highlights}").unwrap()
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Provider {
+    Matrix {
+        /// e.g. "https://matrix.org"
+        homeserver: String,
+        /// An access token, found under Help & About in Element
+        token: Secret,
+    },
+    /// A shell command that prints the number of highlights, optionally
+    /// followed by the number of unread messages on the next line. Useful
+    /// for IRC through a weechat or soju relay, or XMPP.
+    Command { command: String },
+}
+
+#[derive(Clone, Copy, Default)]
+struct Counts {
+    highlights: u64,
+    unread: u64,
+}
+
+/// Unread counts per room, kept up to date with incremental syncs
+#[derive(Default)]
+struct MatrixState {
+    since: Option<String>,
+    rooms: HashMap<String, Counts>,
+}
+
+pub async fn chat_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(CHAT_MAX_RETRY));
+    let mut matrix = MatrixState::default();
+    loop {
+        let counts = match &config.provider {
+            Provider::Matrix { homeserver, token } => {
+                sync_matrix(&sys, homeserver, token, &mut matrix).await
+            }
+            Provider::Command { command } => run_command(&sys, command).await,
+        };
+        let wait = match counts {
+            Ok(counts) => {
+                backoff.reset();
+                let visible = counts.highlights > 0 || (config.show_unread && counts.unread > 0);
+                let status = if visible {
+                    config.format.render(|name| match name {
+                        "icon" => Some(config.icon.clone()),
+                        "highlights" => Some(counts.highlights.to_string()),
+                        "unread" => Some(counts.unread.to_string()),
+                        _ => None,
+                    })
+                } else {
+                    String::new()
+                };
+                tx.send(status.into())?;
+                interval
+            }
+            Err(err) => {
+                eprintln!("Couldn't get unread messages. {err:#}");
+                tx.send(CHAT_FALLBACK.into())?;
+                backoff.fail()
+            }
+        };
+        events.wait(wait).await;
+    }
+}
+
+async fn sync_matrix(
+    sys: &Sys,
+    homeserver: &str,
+    token: &Secret,
+    state: &mut MatrixState,
+) -> Result<Counts> {
+    let token = token.resolve(sys).await?;
+    let mut url = format!(
+        "{}/_matrix/client/v3/sync?timeout=0&filter={}",
+        homeserver.trim_end_matches('/'),
+        encode(MATRIX_FILTER)
+    );
+    if let Some(since) = &state.since {
+        url.push_str(&format!("&since={}", encode(since)));
+    }
+    let headers = vec![("Authorization".to_string(), format!("Bearer {token}"))];
+    let body = sys.http_get(&url, headers).await?;
+    let json: Value = serde_json::from_str(&body)?;
+    let next_batch = json["next_batch"]
+        .as_str()
+        .context("Sync response has no next_batch")?;
+
+    // Incremental syncs only include rooms that changed
+    if let Some(joined) = json.pointer("/rooms/join").and_then(Value::as_object) {
+        for (room, data) in joined {
+            let unread = &data["unread_notifications"];
+            let counts = Counts {
+                highlights: unread["highlight_count"].as_u64().unwrap_or_default(),
+                unread: unread["notification_count"].as_u64().unwrap_or_default(),
+            };
+            state.rooms.insert(room.clone(), counts);
+        }
+    }
+    if let Some(left) = json.pointer("/rooms/leave").and_then(Value::as_object) {
+        for room in left.keys() {
+            state.rooms.remove(room);
+        }
+    }
+    state.since = Some(next_batch.to_string());
+
+    Ok(state
+        .rooms
+        .values()
+        .fold(Counts::default(), |total, room| Counts {
+            highlights: total.highlights + room.highlights,
+            unread: total.unread + room.unread,
+        }))
+}
+
+async fn run_command(sys: &Sys, command: &str) -> Result<Counts> {
+    let output = sys
+        .output(Command::new("sh").arg("-c").arg(command))
+        .await?;
+    if !output.status.success() {
+        bail!("`{command}` failed with {}", output.status);
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.lines().map(str::trim);
+    let highlights = lines
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .with_context(|| format!("`{command}` didn't print a number"))?;
+    let unread = lines
+        .next()
+        .and_then(|l| l.parse().ok())
+        .unwrap_or_default();
+    Ok(Counts { highlights, unread })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn matrix_highlights_from_incremental_syncs() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sync = format!(
+            "https://matrix.example/_matrix/client/v3/sync?timeout=0&filter={}",
+            crate::http::encode(MATRIX_FILTER)
+        );
+        mock.url(
+            &sync,
+            r#"{"next_batch": "s1", "rooms": {"join": {
+                "!a": {"unread_notifications": {"highlight_count": 2, "notification_count": 5}},
+                "!b": {"unread_notifications": {"highlight_count": 1, "notification_count": 1}}
+            }}}"#,
+        );
+        mock.url(
+            &format!("{sync}&since=s1"),
+            r#"{"next_batch": "s2", "rooms": {"join": {
+                "!b": {"unread_notifications": {"highlight_count": 0, "notification_count": 0}}
+            }}}"#,
+        );
+        let config = r#"
+            [[block]]
+            block = "chat"
+            provider = { type = "matrix", homeserver = "https://matrix.example/", token = "syt_token" }
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"💬 3 ","name":"chat""#));
+
+        sleep(Duration::from_secs(30)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"💬 2 ","name":"chat""#));
+    }
+}
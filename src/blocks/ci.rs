@@ -0,0 +1,333 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::blocks::github::{api_headers, GITHUB_API};
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::Template;
+use crate::http::encode;
+use crate::locale::tr;
+use crate::notify::notify;
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::Sys;
+
+pub static CI_FALLBACK: &str = "🏗️ ???";
+static CI_MAX_RETRY: u64 = 30 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "repo", default)]
+    pub repos: Vec<Repo>,
+    /// Seconds between checks
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    /// Placeholders: icon, repos
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// How each repo is shown. Placeholders: name, status, state
+    #[serde(default = "default_repo_format")]
+    pub repo_format: Template,
+    /// Put between repos
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    /// Text colour while any run has failed
+    #[serde(default = "default_failure_color")]
+    pub failure_color: String,
+}
+
+fn default_interval() -> u64 {
+    5 * 60
+}
+
+fn default_icon() -> String {
+    "🏗️".to_string()
+}
+
+fn default_format() -> Template {
+    Template::parse("{icon} {repos}").unwrap()
+}
+
+fn default_repo_format() -> Template {
+    Template::parse("{name} {status}").unwrap()
+}
+
+fn default_separator() -> String {
+    " ".to_string()
+}
+
+fn default_failure_color() -> String {
+    "#ff5555".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Repo {
+    #[serde(default)]
+    pub forge: Forge,
+    /// owner/name on GitHub, or the project's path on GitLab
+    pub repo: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Only needed for private repos
+    pub token: Option<Secret>,
+    /// The GitLab instance, if it's not gitlab.com
+    #[serde(default = "default_gitlab_url")]
+    pub gitlab_url: String,
+    /// Shown instead of the repo's name
+    pub label: Option<String>,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn default_gitlab_url() -> String {
+    "https://gitlab.com".to_string()
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Forge {
+    #[default]
+    Github,
+    Gitlab,
+}
+
+enum State {
+    Success,
+    Running,
+    /// The names of the workflows or jobs that failed
+    Failure(Vec<String>),
+}
+
+impl State {
+    fn icon(&self) -> &str {
+        match self {
+            Self::Success => "✅",
+            Self::Running => "🔄",
+            Self::Failure(_) => "❌",
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Success => "success",
+            Self::Running => "running",
+            Self::Failure(_) => "failure",
+        }
+    }
+}
+
+impl Repo {
+    fn name(&self) -> &str {
+        match &self.label {
+            Some(label) => label,
+            None => self.repo.rsplit('/').next().unwrap_or(&self.repo),
+        }
+    }
+}
+
+pub async fn ci_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(CI_MAX_RETRY));
+    loop {
+        let mut statuses = Vec::new();
+        let mut failures = Vec::new();
+        let mut failed = false;
+        for repo in &config.repos {
+            match latest_run(&sys, repo).await {
+                Ok(state) => {
+                    statuses.push(config.repo_format.render(|name| match name {
+                        "name" => Some(repo.name().to_string()),
+                        "status" => Some(state.icon().to_string()),
+                        "state" => Some(state.name().to_string()),
+                        _ => None,
+                    }));
+                    if let State::Failure(names) = state {
+                        failures.push(format!("{}: {}", repo.name(), names.join(", ")));
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Couldn't get CI status for {}. {err:#}", repo.repo);
+                    failed = true;
+                }
+            }
+        }
+
+        let output = if failed && statuses.is_empty() {
+            CI_FALLBACK.into()
+        } else if statuses.is_empty() {
+            BlockOutput::default()
+        } else {
+            let repos = statuses.join(&config.separator);
+            let full_text = config.format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                "repos" => Some(repos.clone()),
+                _ => None,
+            });
+            BlockOutput {
+                full_text,
+                color: (!failures.is_empty()).then(|| config.failure_color.clone()),
+                ..Default::default()
+            }
+        };
+        tx.send(output)?;
+        let wait = if failed {
+            backoff.fail()
+        } else {
+            backoff.reset();
+            interval
+        };
+
+        // Clicking shows what failed until the next check
+        while let Some(click) = events.wait(wait).await {
+            if click.button == BUTTON_LEFT && !failures.is_empty() {
+                notify(&sys, &tr("ci-failed", &[]), &failures.join("\n")).await;
+            }
+        }
+    }
+}
+
+async fn latest_run(sys: &Sys, repo: &Repo) -> Result<State> {
+    let token = match &repo.token {
+        Some(token) => Some(token.resolve(sys).await?),
+        None => None,
+    };
+    match repo.forge {
+        Forge::Github => github_run(sys, repo, token.as_deref()).await,
+        Forge::Gitlab => gitlab_pipeline(sys, repo, token).await,
+    }
+}
+
+/// The state of every workflow run for the branch's latest commit
+async fn github_run(sys: &Sys, repo: &Repo, token: Option<&str>) -> Result<State> {
+    let url = format!(
+        "{GITHUB_API}/repos/{}/actions/runs?branch={}&per_page=20",
+        repo.repo, repo.branch
+    );
+    let body = sys.http_get(&url, api_headers(token)).await?;
+    let json: Value = serde_json::from_str(&body)?;
+    let runs = json["workflow_runs"]
+        .as_array()
+        .context("Response has no workflow runs")?;
+    let head_sha = runs
+        .first()
+        .and_then(|r| r["head_sha"].as_str())
+        .context("The branch has no workflow runs")?;
+    let latest: Vec<_> = runs
+        .iter()
+        .filter(|r| r["head_sha"].as_str() == Some(head_sha))
+        .collect();
+    let failures: Vec<String> = latest
+        .iter()
+        .filter(|r| {
+            matches!(
+                r["conclusion"].as_str(),
+                Some("failure" | "timed_out" | "startup_failure")
+            )
+        })
+        .map(|r| r["name"].as_str().unwrap_or("???").to_string())
+        .collect();
+    if !failures.is_empty() {
+        Ok(State::Failure(failures))
+    } else if latest.iter().any(|r| r["status"] != "completed") {
+        Ok(State::Running)
+    } else {
+        Ok(State::Success)
+    }
+}
+
+async fn gitlab_pipeline(sys: &Sys, repo: &Repo, token: Option<String>) -> Result<State> {
+    let project = format!(
+        "{}/api/v4/projects/{}",
+        repo.gitlab_url.trim_end_matches('/'),
+        encode(&repo.repo)
+    );
+    let headers: Vec<_> = token
+        .map(|token| ("PRIVATE-TOKEN".to_string(), token))
+        .into_iter()
+        .collect();
+    let url = format!(
+        "{project}/pipelines?ref={}&per_page=1",
+        encode(&repo.branch)
+    );
+    let body = sys.http_get(&url, headers.clone()).await?;
+    let json: Value = serde_json::from_str(&body)?;
+    let pipeline = json.get(0).context("The branch has no pipelines")?;
+    match pipeline["status"].as_str() {
+        Some("success" | "skipped" | "manual" | "canceled") => Ok(State::Success),
+        Some("failed") => {
+            let id = &pipeline["id"];
+            let url = format!("{project}/pipelines/{id}/jobs?scope[]=failed");
+            let body = sys.http_get(&url, headers).await?;
+            let jobs: Vec<Value> = serde_json::from_str(&body)?;
+            let names = jobs
+                .iter()
+                .map(|j| j["name"].as_str().unwrap_or("???").to_string())
+                .collect();
+            Ok(State::Failure(names))
+        }
+        _ => Ok(State::Running),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn ci_failure_is_red_and_notifies_on_click() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://api.github.com/repos/sublipri/subar/actions/runs?branch=main&per_page=20",
+            r#"{"workflow_runs": [
+                {"name": "Tests", "head_sha": "b", "status": "completed", "conclusion": "failure"},
+                {"name": "Lint", "head_sha": "b", "status": "completed", "conclusion": "success"},
+                {"name": "Tests", "head_sha": "a", "status": "completed", "conclusion": "success"}
+            ]}"#,
+        );
+        mock.url(
+            "https://gitlab.com/api/v4/projects/group%2Fproject/pipelines?ref=dev&per_page=1",
+            r#"[{"id": 7, "status": "running"}]"#,
+        );
+        mock.command("notify-send --app-name=subar CI failed subar: Tests", 0, "");
+        let config = r#"
+            [[block]]
+            block = "ci"
+            [[block.repo]]
+            repo = "sublipri/subar"
+            [[block.repo]]
+            forge = "gitlab"
+            repo = "group/project"
+            branch = "dev"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r##"[{"full_text":"🏗️ subar ❌ project 🔄 ","color":"#ff5555","name":"ci""##
+        ));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        assert_eq!(
+            *ran,
+            ["notify-send --app-name=subar CI failed subar: Tests"]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::{truncate, Template};
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static CLIPBOARD_FALLBACK: &str = "📋 ???";
+static CLIPBOARD_MAX_RETRY: u64 = 60 * 1000;
+/// Offered alongside passwords copied from KeePassXC and other password
+/// managers
+static PASSWORD_HINT: &str = "x-kde-passwordManagerHint";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub icon: String,
+    /// Placeholders: icon, preview, type
+    pub format: Template,
+    /// How many characters of text to show in `{preview}`
+    pub max_length: usize,
+    /// Shown in `{preview}` instead of passwords
+    pub secret_text: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            icon: "📋".to_string(),
+            format: Template::parse("{icon} {preview}").unwrap(),
+            max_length: 20,
+            secret_text: "••••••".to_string(),
+        }
+    }
+}
+
+pub async fn clipboard_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(CLIPBOARD_MAX_RETRY),
+    );
+    loop {
+        // Prints a line every time the clipboard changes
+        let mut watch = Command::new("wl-paste");
+        watch.arg("--watch").arg("echo");
+        let mut changes = match sys.stream(&mut watch) {
+            Ok(stdout) => BufReader::new(stdout).lines(),
+            Err(err) => {
+                eprintln!("Couldn't watch the clipboard. {err}");
+                tx.send(CLIPBOARD_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        loop {
+            match contents(&sys, &config).await {
+                Ok(status) => tx.send(status.into())?,
+                Err(err) => {
+                    eprintln!("Couldn't read the clipboard. {err:#}");
+                    tx.send(CLIPBOARD_FALLBACK.into())?;
+                }
+            }
+            tokio::select! {
+                change = changes.next_line() => match change {
+                    Ok(Some(_)) => backoff.reset(),
+                    _ => break,
+                },
+                click = events.wait(Duration::MAX) => {
+                    if click.is_some_and(|c| c.button == BUTTON_LEFT) {
+                        clear(&sys).await;
+                    }
+                }
+            }
+        }
+        eprintln!("Stopped watching the clipboard");
+        tx.send(CLIPBOARD_FALLBACK.into())?;
+        events.wait(backoff.fail()).await;
+    }
+}
+
+/// The block's text for whatever is on the clipboard, which is empty if
+/// nothing is
+async fn contents(sys: &Sys, config: &Config) -> Result<String> {
+    let output = sys
+        .output(Command::new("wl-paste").arg("--list-types"))
+        .await?;
+    // wl-paste fails when nothing is copied
+    if !output.status.success() {
+        return Ok(String::new());
+    }
+    let types = String::from_utf8(output.stdout)?;
+    let types: Vec<_> = types.lines().map(str::trim).collect();
+    let Some(&kind) = types.first() else {
+        return Ok(String::new());
+    };
+    let text_type = types
+        .iter()
+        .find(|t| t.starts_with("text/plain") || **t == "UTF8_STRING");
+    let preview = if types.contains(&PASSWORD_HINT) {
+        config.secret_text.clone()
+    } else if let Some(text_type) = text_type {
+        let mut paste = Command::new("wl-paste");
+        paste.arg("--no-newline").arg("--type").arg(text_type);
+        let output = sys.output(&mut paste).await?;
+        if !output.status.success() {
+            bail!("wl-paste failed with {}", output.status);
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        truncate(&text, config.max_length)
+    } else {
+        kind.to_string()
+    };
+    Ok(config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "preview" => Some(preview.clone()),
+        "type" => Some(kind.to_string()),
+        _ => None,
+    }))
+}
+
+async fn clear(sys: &Sys) {
+    match sys.output(Command::new("wl-copy").arg("--clear")).await {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => eprintln!("wl-copy failed with {}", output.status),
+        Err(err) => eprintln!("Couldn't clear the clipboard. {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn clipboard_previews_and_clears() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.stream("wl-paste --watch echo", b"\n".to_vec());
+        mock.command(
+            "wl-paste --list-types",
+            0,
+            "text/plain;charset=utf-8\nTEXT\n",
+        );
+        mock.command(
+            "wl-paste --no-newline --type text/plain;charset=utf-8",
+            0,
+            "Copied from\na long document",
+        );
+        mock.command("wl-copy --clear", 0, "");
+        let mut tasks = start("[[block]]\nblock = \"clipboard\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"📋 Copied from a long d… ","name":"clipboard""#));
+
+        mock.command(
+            "wl-paste --list-types",
+            0,
+            "x-kde-passwordManagerHint\ntext/plain;charset=utf-8\n",
+        );
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(mock
+            .ran
+            .lock()
+            .unwrap()
+            .contains(&"wl-copy --clear".to_string()));
+        assert!(line.starts_with(r#"[{"full_text":"📋 •••••• ","name":"clipboard""#));
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use chrono::FixedOffset;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+use crate::timezone::Zone;
+
+pub static CLOCK_FALLBACK: &str = "🕛 ???";
+/// The clock ticks on each second of the system time, so the seconds don't
+/// stutter when the task is woken early by a click
+pub static CLOCK_UPDATE_FREQUENCY: u64 = 1000;
+static CLOCK_RETRY_FREQUENCY: u64 = 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub date_icon: String,
+    pub time_icon: String,
+    /// A strftime format string for `{date}`
+    pub date_format: String,
+    /// A strftime format string for `{time}`. Without one it's made from
+    /// `twelve_hour` and `seconds`.
+    pub time_format: Option<String>,
+    pub twelve_hour: bool,
+    pub seconds: bool,
+    /// Show the time in a zone from the tz database, like "UTC" or
+    /// "America/New_York", rather than local time
+    pub timezone: Option<String>,
+    /// A short name for the clock, like "NYC"
+    pub label: Option<String>,
+    /// Placeholders: date_icon, date, time_icon, time, label, zone (the
+    /// zone's abbreviation, like "EDT"), week (the ISO week), day_of_year
+    pub format: Template,
+    /// More formats to switch to with a left click, in turn after `format`
+    pub formats: Vec<Template>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            date_icon: "🗓️".to_string(),
+            time_icon: "🕛".to_string(),
+            date_format: "%a %b %d".to_string(),
+            time_format: None,
+            twelve_hour: false,
+            seconds: true,
+            timezone: None,
+            label: None,
+            format: Template::parse("{date_icon} {date} {time_icon} {time}").unwrap(),
+            formats: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    fn time_format(&self) -> String {
+        if let Some(format) = &self.time_format {
+            return format.clone();
+        }
+        let format = match (self.twelve_hour, self.seconds) {
+            (false, true) => "%H:%M:%S",
+            (false, false) => "%H:%M",
+            (true, true) => "%-I:%M:%S %p",
+            (true, false) => "%-I:%M %p",
+        };
+        format.to_string()
+    }
+}
+
+/// Show the date and time, locally or in another time zone. Clicking
+/// switches between the formats until the bar is restarted.
+pub async fn clock_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let time_format = config.time_format();
+    // A mistyped zone shows the fallback, and it's looked for again in case
+    // the tz database is being installed
+    let zone = loop {
+        match config.timezone.as_deref().map(Zone::load).transpose() {
+            Ok(zone) => break zone,
+            Err(err) => {
+                eprintln!("{err:#}");
+                tx.send(CLOCK_FALLBACK.into())?;
+                events
+                    .wait(Duration::from_millis(CLOCK_RETRY_FREQUENCY))
+                    .await;
+            }
+        }
+    };
+    let formats: Vec<&Template> = std::iter::once(&config.format)
+        .chain(&config.formats)
+        .collect();
+    let mut selected = 0;
+    loop {
+        let now = sys.now();
+        let (now, abbreviation) = match &zone {
+            Some(zone) => {
+                let local = zone.at(now.timestamp());
+                let Some(offset) = FixedOffset::east_opt(local.offset) else {
+                    let name = config.timezone.as_deref().unwrap_or_default();
+                    eprintln!(
+                        "Couldn't show the time in {name}. It's {}s from UTC.",
+                        local.offset
+                    );
+                    tx.send(CLOCK_FALLBACK.into())?;
+                    events
+                        .wait(Duration::from_millis(CLOCK_RETRY_FREQUENCY))
+                        .await;
+                    continue;
+                };
+                (now.with_timezone(&offset), local.abbreviation)
+            }
+            None => (now.fixed_offset(), now.format("%Z").to_string()),
+        };
+        let status = formats[selected].render(|name| match name {
+            "label" => config.label.clone(),
+            "zone" => Some(abbreviation.clone()),
+            "date_icon" => Some(config.date_icon.clone()),
+            "date" => Some(now.format(&config.date_format).to_string()),
+            "time_icon" => Some(config.time_icon.clone()),
+            "time" => Some(now.format(&time_format).to_string()),
+            "week" => Some(now.format("%V").to_string()),
+            "day_of_year" => Some(now.format("%j").to_string()),
+            _ => None,
+        });
+        tx.send(status.into())?;
+        let into_second = sys.now().timestamp_subsec_millis() as u64 % CLOCK_UPDATE_FREQUENCY;
+        let click = events
+            .wait(Duration::from_millis(CLOCK_UPDATE_FREQUENCY - into_second))
+            .await;
+        if click.is_some_and(|c| c.button == BUTTON_LEFT) {
+            selected = (selected + 1) % formats.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn unknown_time_zones_show_the_fallback() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = "[[block]]\nblock = \"clock\"\ntimezone = \"America/NewYork\"";
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🕛 ??? ","name":"clock","instance":"0""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clocks_in_other_time_zones() {
+        for (now, expected) in [
+            ("2026-10-14 09:30:00", "NYC 05:30 EDT SYD 20:30 AEDT "),
+            // After the zone files' transitions run out in 2037
+            ("2041-07-01 09:30:00", "NYC 05:30 EDT SYD 19:30 AEST "),
+        ] {
+            let mock = Mock::new(now);
+            let clock = |zone: &str, label: &str| {
+                format!(
+                    "[[block]]\nblock = \"clock\"\ntimezone = \"{zone}\"\nlabel = \"{label}\"\n\
+                     seconds = false\nformat = \"{{label}} {{time}} {{zone}}\"\n"
+                )
+            };
+            let config = [
+                clock("America/New_York", "NYC"),
+                clock("Australia/Sydney", "SYD"),
+            ]
+            .concat();
+            let mut tasks = start(&config, &mock);
+            let line = render(&mut tasks, &mock).await;
+            let texts: Vec<_> = serde_json::from_str::<Vec<serde_json::Value>>(&line)
+                .unwrap()
+                .iter()
+                .map(|block| block["full_text"].as_str().unwrap().to_string())
+                .collect();
+            assert_eq!(texts.len(), 3);
+            assert_eq!(texts[..2].concat(), expected);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clock_can_be_configured() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = r#"
+            [[block]]
+            block = "clock"
+            date_format = "%d/%m"
+            twelve_hour = true
+            seconds = false
+        "#;
+        let mut tasks = start(config, &mock);
+        assert_eq!(
+            render(&mut tasks, &mock).await,
+            r#"[{"full_text":"🗓️ 14/10 🕛 9:30 AM","name":"clock","instance":"0","separator":false,"separator_block_width":0}]"#,
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clock_ticks_on_the_second() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let mut tasks = start(
+            "[[block]]\nblock = \"clock\"\nformats = [\"{time}\"]",
+            &mock,
+        );
+        render(&mut tasks, &mock).await;
+        // The click redraws it 50ms into the second, but the next tick is
+        // still on the second
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        sleep(Duration::from_millis(920)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"09:30:01""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clicking_the_clock_cycles_formats() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = r#"
+            [[block]]
+            block = "clock"
+            seconds = false
+            format = "{time}"
+            formats = ["{date} {time}", "W{week} D{day_of_year}"]
+        "#;
+        let mut tasks = start(config, &mock);
+        let mut texts = vec![render(&mut tasks, &mock).await];
+        for _ in 0..3 {
+            click(&tasks[0], BUTTON_LEFT, &[]).await;
+            texts.push(render(&mut tasks, &mock).await);
+        }
+        let texts: Vec<_> = texts
+            .iter()
+            .map(|line| line.split('"').nth(3).unwrap().to_string())
+            .collect();
+        assert_eq!(texts, ["09:30", "Wed Oct 14 09:30", "W42 D287", "09:30"]);
+    }
+}
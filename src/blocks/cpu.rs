@@ -0,0 +1,432 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::bsd;
+use crate::events::Events;
+use crate::format::{sparkline, Template};
+use crate::output::{BlockOutput, Threshold};
+use crate::system::Sys;
+
+pub static CPU_FALLBACK: &str = "💻 ???";
+/// How long the first sample is, so there's something to show straight away
+static CPU_FIRST_SAMPLE: u64 = 250;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    /// Where the kernel's CPU times are, for the "proc" source
+    pub path: PathBuf,
+    /// Where the kernel lists CPUs, with their frequencies
+    pub cpus: PathBuf,
+    /// Whether `{frequency}` is the average of the cores or the fastest one
+    pub frequency: Frequency,
+    /// How long each sample is, in seconds. Usage is averaged over it.
+    pub interval: u64,
+    pub icon: String,
+    /// Placeholders: icon, usage, cores (a bar for each core, like "▁▃▅█"),
+    /// frequency (in GHz)
+    pub format: Template,
+    /// The characters `{cores}` is drawn with, from idle to fully busy
+    pub core_chars: String,
+    /// Colours for when the usage reaches a percentage
+    pub colors: Vec<Threshold>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::default(),
+            path: PathBuf::from("/proc/stat"),
+            cpus: PathBuf::from("/sys/devices/system/cpu"),
+            frequency: Frequency::Average,
+            interval: 2,
+            icon: "💻".to_string(),
+            format: Template::parse("{icon} {usage}%").unwrap(),
+            core_chars: "▁▂▃▄▅▆▇█".to_string(),
+            colors: vec![
+                Threshold::new(70.0, "#ffaa00"),
+                Threshold::new(90.0, "#ff4444"),
+            ],
+        }
+    }
+}
+
+/// Where the CPU times come from
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// Linux's /proc/stat and cpufreq
+    Proc,
+    /// The BSDs' `kern.cp_time` sysctl. Only FreeBSD has the times for each
+    /// core, and the frequency is the first core's.
+    Sysctl,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        if cfg!(any(target_os = "freebsd", target_os = "openbsd")) {
+            Self::Sysctl
+        } else {
+            Self::Proc
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Average,
+    Max,
+}
+
+/// Time a CPU has spent working and in total since boot, in clock ticks
+#[derive(Clone, Copy)]
+struct Times {
+    busy: u64,
+    total: u64,
+}
+
+impl Times {
+    /// How busy the CPU was between `earlier` and now, as a percentage
+    fn usage_since(self, earlier: Times) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f64 / total as f64 * 100.0
+    }
+}
+
+/// Show how busy the CPU has been over the last `interval` seconds, from the
+/// difference between two readings of /proc/stat or sysctl
+pub async fn cpu_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval);
+    let mut last: Option<Vec<Times>> = None;
+    loop {
+        let read = match config.source {
+            Source::Proc => read_stat(&config.path).await,
+            Source::Sysctl => read_sysctl(&sys, config.format.uses("cores")).await,
+        };
+        let times = match read {
+            Ok(times) => times,
+            Err(err) => {
+                eprintln!("Couldn't read the CPU times. {err:#}");
+                tx.send(CPU_FALLBACK.into())?;
+                last = None;
+                events.wait(interval).await;
+                continue;
+            }
+        };
+        let Some(earlier) = last.replace(times.clone()) else {
+            events.wait(Duration::from_millis(CPU_FIRST_SAMPLE)).await;
+            continue;
+        };
+        // A CPU can be taken offline between readings, so only as many as
+        // both have are compared
+        let usages: Vec<f64> = times
+            .iter()
+            .zip(&earlier)
+            .map(|(now, earlier)| now.usage_since(*earlier))
+            .collect();
+        let usage = usages.first().copied().unwrap_or(0.0);
+        // Reading every core's frequency is only worth it if it's shown
+        let mut frequency = None;
+        if config.format.uses("frequency") {
+            frequency = match config.source {
+                Source::Proc => read_frequency(&config.cpus, config.frequency).await,
+                Source::Sysctl => read_sysctl_frequency(&sys).await,
+            };
+        }
+        let full_text = config.format.render(|name| match name {
+            "icon" => Some(config.icon.clone()),
+            "usage" => Some(format!("{usage:.0}")),
+            "cores" => {
+                let fractions = usages.iter().skip(1).map(|usage| usage / 100.0);
+                Some(sparkline(fractions, &config.core_chars))
+            }
+            "frequency" => frequency.map(|ghz| format!("{ghz:.1}")),
+            _ => None,
+        });
+        tx.send(BlockOutput {
+            full_text,
+            color: Threshold::color(&config.colors, usage),
+            ..BlockOutput::default()
+        })?;
+        events.wait(interval).await;
+    }
+}
+
+/// The times for all CPUs together, from the "cpu" line of /proc/stat,
+/// followed by each core's
+async fn read_stat(path: &Path) -> Result<Vec<Times>> {
+    let stat = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let times: Vec<Times> = stat
+        .lines()
+        .filter(|line| line.starts_with("cpu"))
+        .map(parse_times)
+        .collect();
+    if times.is_empty() {
+        bail!("{} has no CPU times", path.display());
+    }
+    Ok(times)
+}
+
+/// A line like "cpu0 4705 356 584 3699 23 23 0 0 0 0". Idle and I/O wait are
+/// the fourth and fifth numbers, and guest time is already counted in user
+/// time, so only the first eight make up the total.
+fn parse_times(line: &str) -> Times {
+    let numbers: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|n| n.parse().unwrap_or(0))
+        .collect();
+    let total = numbers.iter().sum();
+    let idle = numbers.iter().skip(3).take(2).sum::<u64>();
+    Times {
+        busy: total - idle,
+        total,
+    }
+}
+
+/// The times for all CPUs together from `kern.cp_time`, followed by each
+/// core's from FreeBSD's `kern.cp_times` if `cores` is shown
+async fn read_sysctl(sys: &Sys, cores: bool) -> Result<Vec<Times>> {
+    let [cp_time] = bsd::sysctl(sys, ["kern.cp_time"]).await?;
+    let ticks = parse_ticks(&cp_time)?;
+    let mut times = vec![sysctl_times(&ticks)];
+    // OpenBSD doesn't have it, so the cores are just left out
+    if cores {
+        if let Ok([cp_times]) = bsd::sysctl(sys, ["kern.cp_times"]).await {
+            let per_core = parse_ticks(&cp_times)?;
+            times.extend(per_core.chunks_exact(ticks.len()).map(sysctl_times));
+        }
+    }
+    Ok(times)
+}
+
+/// Clock ticks in each state, like "4705 356 584 23 3699" on FreeBSD or
+/// "4705,356,584,0,23,3699" on OpenBSD, which also counts spinning
+fn parse_ticks(value: &str) -> Result<Vec<u64>> {
+    let ticks = value
+        .split([' ', ','])
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().with_context(|| format!("Couldn't parse {value}")))
+        .collect::<Result<Vec<u64>>>()?;
+    if ticks.is_empty() {
+        bail!("sysctl has no CPU times");
+    }
+    Ok(ticks)
+}
+
+/// Idle is the last of the states on both BSDs
+fn sysctl_times(ticks: &[u64]) -> Times {
+    let total = ticks.iter().sum::<u64>();
+    Times {
+        busy: total - ticks.last().copied().unwrap_or(0),
+        total,
+    }
+}
+
+/// The first core's frequency on FreeBSD, or the CPU's on OpenBSD, in GHz
+async fn read_sysctl_frequency(sys: &Sys) -> Option<f64> {
+    for name in ["dev.cpu.0.freq", "hw.cpuspeed"] {
+        let Ok([mhz]) = bsd::sysctl(sys, [name]).await else {
+            continue;
+        };
+        if let Ok(mhz) = mhz.parse::<f64>() {
+            return Some(mhz / 1000.0);
+        }
+    }
+    None
+}
+
+/// The average or highest frequency of the cores in GHz, or None if the
+/// kernel doesn't report them
+async fn read_frequency(cpus: &Path, kind: Frequency) -> Option<f64> {
+    let mut entries = tokio::fs::read_dir(cpus).await.ok()?;
+    let mut frequencies = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        let Some(number) = name.to_str().and_then(|name| name.strip_prefix("cpu")) else {
+            continue;
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let path = entry.path().join("cpufreq/scaling_cur_freq");
+        let Ok(khz) = tokio::fs::read_to_string(path).await else {
+            continue;
+        };
+        if let Ok(khz) = khz.trim().parse::<f64>() {
+            frequencies.push(khz / 1_000_000.0);
+        }
+    }
+    if frequencies.is_empty() {
+        return None;
+    }
+    Some(match kind {
+        Frequency::Average => frequencies.iter().sum::<f64>() / frequencies.len() as f64,
+        Frequency::Max => frequencies.into_iter().fold(0.0, f64::max),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn cpu_usage_is_measured_between_samples() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let stat = |times: &str| {
+            let contents = format!("cpu  {times} 0 0 0 0 0 0\ncpu0 {times} 0 0 0 0 0 0\n");
+            proc.write("stat", contents);
+        };
+        stat("100 0 100 800");
+        let config = format!("[[block]]\nblock = \"cpu\"\npath = {:?}", proc.path("stat"));
+        let mut tasks = start(&config, &mock);
+        render(&mut tasks, &mock).await;
+        stat("250 0 150 1100");
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"💻 40% ","name":"cpu""#));
+        stat("700 0 250 1150");
+        sleep(Duration::from_secs(2)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r##"[{"full_text":"💻 92% ","color":"#ff4444","name":"cpu""##));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unreadable_cpu_times_are_retried() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let config = format!("[[block]]\nblock = \"cpu\"\npath = {:?}", proc.path("stat"));
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"💻 ??? ","name":"cpu""#));
+
+        proc.write("stat", "cpu  100 0 100 800 0 0 0 0 0 0\n");
+        sleep(Duration::from_secs(2)).await;
+        proc.write("stat", "cpu  250 0 150 1100 0 0 0 0 0 0\n");
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"💻 40% ","name":"cpu""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cpu_cores_are_drawn_as_a_sparkline() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let stat = |lines: [&str; 4]| {
+            let names = ["cpu ", "cpu0", "cpu1", "cpu2"];
+            let contents: String = names
+                .iter()
+                .zip(lines)
+                .map(|(name, times)| format!("{name} {times} 0 0 0 0 0 0\n"))
+                .collect();
+            proc.write("stat", contents);
+        };
+        stat(["0 0 0 0"; 4]);
+        let config = format!(
+            "[[block]]\nblock = \"cpu\"\npath = {:?}\nformat = \"{{usage}}% {{cores}}\"",
+            proc.path("stat")
+        );
+        let mut tasks = start(&config, &mock);
+        render(&mut tasks, &mock).await;
+        stat(["150 0 0 150", "0 0 0 100", "50 0 0 50", "100 0 0 0"]);
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"50% ▁▅█ ","name":"cpu""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cpu_frequency_is_averaged_over_the_cores() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        sys.attributes("cpu0/cpufreq", &[("scaling_cur_freq", "1200000")]);
+        sys.attributes("cpu1/cpufreq", &[("scaling_cur_freq", "3400000")]);
+        sys.attributes("cpufreq", &[("policy0", "")]);
+        sys.write("stat", "cpu  0 0 0 0 0 0 0 0 0 0\n");
+        let config = format!(
+            "[[block]]\nblock = \"cpu\"\npath = {:?}\ncpus = {:?}\nformat = \"{{frequency}} GHz\"",
+            sys.path("stat"),
+            sys.path("")
+        );
+        let mut tasks = start(&config, &mock);
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"2.3 GHz ","name":"cpu""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bsd_cpu_times_are_read_with_sysctl() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sysctl -n kern.cp_time", 0, "100 0 100 0 800\n");
+        mock.command(
+            "sysctl -n kern.cp_times",
+            0,
+            "50 0 50 0 400 50 0 50 0 400\n",
+        );
+        mock.command("sysctl -n dev.cpu.0.freq", 0, "2400\n");
+        let config = r#"
+[[block]]
+block = "cpu"
+source = "sysctl"
+format = "{usage}% {cores} {frequency} GHz"
+"#;
+        let mut tasks = start(config, &mock);
+        render(&mut tasks, &mock).await;
+        mock.command("sysctl -n kern.cp_time", 0, "250 0 150 0 1100\n");
+        mock.command(
+            "sysctl -n kern.cp_times",
+            0,
+            "150 0 50 0 400 50 0 100 0 700\n",
+        );
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"40% █▂ 2.4 GHz ","name":"cpu""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn openbsd_cpu_times_are_split_by_commas() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sysctl -n kern.cp_time", 0, "100,0,100,0,0,800\n");
+        mock.command("sysctl -n hw.cpuspeed", 0, "1800\n");
+        let config = r#"
+[[block]]
+block = "cpu"
+source = "sysctl"
+format = "{usage}%[ {cores}] {frequency} GHz"
+"#;
+        let mut tasks = start(config, &mock);
+        render(&mut tasks, &mock).await;
+        mock.command("sysctl -n kern.cp_time", 0, "250,0,150,0,0,1100\n");
+        sleep(Duration::from_millis(250)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"40% 1.8 GHz ","name":"cpu""#));
+    }
+}
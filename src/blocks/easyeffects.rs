@@ -0,0 +1,184 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static EASYEFFECTS_FALLBACK: &str = "🎚️ ???";
+pub static EASYEFFECTS_UPDATE_FREQUENCY: u64 = 2000;
+static EASYEFFECTS_MAX_RETRY: u64 = 60 * 1000;
+static EASYEFFECTS_SCHEMA: &str = "com.github.wwmm.easyeffects";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Which presets to show and cycle through
+    pub kind: Kind,
+    pub icon: String,
+    /// Placeholders: icon, preset
+    pub format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            kind: Kind::Output,
+            icon: "🎚️".to_string(),
+            format: Template::parse("{icon} {preset}").unwrap(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Output,
+    Input,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Output => "output",
+            Self::Input => "input",
+        }
+    }
+}
+
+pub async fn easyeffects_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(EASYEFFECTS_UPDATE_FREQUENCY),
+        Duration::from_millis(EASYEFFECTS_MAX_RETRY),
+    );
+    loop {
+        let preset = match current_preset(&sys, config.kind).await {
+            Ok(preset) => preset,
+            Err(err) => {
+                eprintln!("Couldn't get the EasyEffects preset. {err:#}");
+                tx.send(EASYEFFECTS_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        let status = config.format.render(|name| match name {
+            "icon" => Some(config.icon.clone()),
+            "preset" => Some(preset.clone()),
+            _ => None,
+        });
+        tx.send(status.into())?;
+
+        let click = events
+            .wait(Duration::from_millis(EASYEFFECTS_UPDATE_FREQUENCY))
+            .await;
+        let step = match click.map(|c| c.button) {
+            Some(BUTTON_LEFT) => 1,
+            Some(BUTTON_RIGHT) => -1,
+            _ => continue,
+        };
+        if let Err(err) = cycle_preset(&sys, config.kind, &preset, step).await {
+            eprintln!("Couldn't change the EasyEffects preset. {err:#}");
+        }
+    }
+}
+
+/// The preset that was loaded last. The settings key was renamed in
+/// EasyEffects 7, so try both.
+async fn current_preset(sys: &Sys, kind: Kind) -> Result<String> {
+    for key in ["last-loaded", "last-used"] {
+        let key = format!("{key}-{}-preset", kind.name());
+        let mut gsettings = Command::new("gsettings");
+        gsettings.arg("get").arg(EASYEFFECTS_SCHEMA).arg(&key);
+        let output = sys.output(&mut gsettings).await?;
+        if output.status.success() {
+            let value = String::from_utf8(output.stdout)?;
+            return Ok(value.trim().trim_matches('\'').to_string());
+        }
+    }
+    bail!("EasyEffects doesn't seem to be installed")
+}
+
+/// Load the preset `step` places after `current`
+async fn cycle_preset(sys: &Sys, kind: Kind, current: &str, step: isize) -> Result<()> {
+    let presets = list_presets(sys, kind).await?;
+    if presets.is_empty() {
+        return Ok(());
+    }
+    let next = match presets.iter().position(|p| p == current) {
+        Some(i) => (i as isize + step).rem_euclid(presets.len() as isize) as usize,
+        None => 0,
+    };
+    let output = sys
+        .output(Command::new("easyeffects").arg("-l").arg(&presets[next]))
+        .await?;
+    if !output.status.success() {
+        bail!("easyeffects failed with {}", output.status);
+    }
+    Ok(())
+}
+
+/// `easyeffects -p` prints a line like "Output Presets: Headphones,Speakers,"
+async fn list_presets(sys: &Sys, kind: Kind) -> Result<Vec<String>> {
+    let output = sys.output(Command::new("easyeffects").arg("-p")).await?;
+    if !output.status.success() {
+        bail!("easyeffects failed with {}", output.status);
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let prefix = format!("{} presets:", kind.name());
+    let line = stdout
+        .lines()
+        .find(|l| l.trim().to_lowercase().starts_with(&prefix))
+        .context("easyeffects didn't list any presets")?;
+    let (_, presets) = line.split_once(':').unwrap_or_default();
+    Ok(presets
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn easyeffects_cycles_presets() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let get = "gsettings get com.github.wwmm.easyeffects";
+        // EasyEffects 6 doesn't have the newer key
+        mock.command(&format!("{get} last-loaded-output-preset"), 1, "");
+        mock.command(&format!("{get} last-used-output-preset"), 0, "'Speakers'\n");
+        mock.command(
+            "easyeffects -p",
+            0,
+            "Output Presets: Headphones,Speakers,\nInput Presets: Mic,\n",
+        );
+        mock.command("easyeffects -l Headphones", 0, "");
+        let mut tasks = start("[[block]]\nblock = \"easyeffects\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎚️ Speakers ","name":"easyeffects""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        render(&mut tasks, &mock).await;
+        assert!(mock
+            .ran
+            .lock()
+            .unwrap()
+            .contains(&"easyeffects -l Headphones".to_string()));
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+use crate::events::Events;
+use crate::format::{truncate, Template};
+use crate::locale::tr;
+use crate::notify::notify;
+use crate::output::BlockOutput;
+use crate::secret::expand_home;
+use crate::system::Sys;
+
+pub static FORTUNE_FALLBACK: &str = "🥠 ???";
+static FORTUNE_MAX_RETRY: u64 = 60 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    /// Seconds each entry is shown for
+    pub interval: u64,
+    /// How many characters of `{text}` are shown
+    pub max_length: usize,
+    pub icon: String,
+    /// Placeholders: icon, text
+    pub format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::Command {
+                command: "fortune -s".to_string(),
+            },
+            interval: 60 * 60,
+            max_length: 60,
+            icon: "🥠".to_string(),
+            format: Template::parse("{icon} {text}").unwrap(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Source {
+    /// A shell command that prints something new each time it's run
+    Command { command: String },
+    /// A file of entries separated by lines with just a `%` on them, like
+    /// fortune's files, or one entry per line. The entries are shown in
+    /// turn, so with an interval of a day each is shown for the whole day.
+    File { path: PathBuf },
+}
+
+pub async fn fortune_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval.max(1));
+    let mut backoff = Backoff::new(interval, Duration::from_millis(FORTUNE_MAX_RETRY));
+    // How many entries have been skipped by clicking
+    let mut skipped = 0;
+    loop {
+        let entry = match next_entry(&sys, &config, skipped).await {
+            Ok(entry) => entry,
+            Err(err) => {
+                eprintln!("Couldn't get a fortune. {err:#}");
+                tx.send(FORTUNE_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        let text = entry.split_whitespace().collect::<Vec<_>>().join(" ");
+        let status = config.format.render(|name| match name {
+            "icon" => Some(config.icon.clone()),
+            "text" => Some(truncate(&text, config.max_length)),
+            _ => None,
+        });
+        tx.send(status.into())?;
+
+        // Left click shows the whole entry, right click skips to the next one
+        while let Some(click) = events.wait(interval).await {
+            match click.button {
+                BUTTON_LEFT => notify(&sys, &tr("fortune-title", &[]), entry.trim()).await,
+                BUTTON_RIGHT => {
+                    skipped += 1;
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+async fn next_entry(sys: &Sys, config: &Config, skipped: u64) -> Result<String> {
+    match &config.source {
+        Source::Command { command } => {
+            let output = sys
+                .output(Command::new("sh").arg("-c").arg(command))
+                .await?;
+            if !output.status.success() {
+                bail!("`{command}` failed with {}", output.status);
+            }
+            Ok(String::from_utf8(output.stdout)?)
+        }
+        Source::File { path } => {
+            let path = expand_home(path);
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("Couldn't read {}", path.display()))?;
+            let entries = entries(&contents);
+            if entries.is_empty() {
+                bail!("{} is empty", path.display());
+            }
+            // Which entry's turn it is, counting from the epoch
+            let turn = sys.now().timestamp().max(0) as u64 / config.interval.max(1) + skipped;
+            Ok(entries[(turn % entries.len() as u64) as usize].clone())
+        }
+    }
+}
+
+fn entries(contents: &str) -> Vec<String> {
+    let lines = contents.lines().map(str::trim_end);
+    let entries: Vec<String> = if lines.clone().any(|l| l == "%") {
+        let mut entries = vec![String::new()];
+        for line in lines {
+            if line == "%" {
+                entries.push(String::new());
+            } else {
+                let entry = entries.last_mut().unwrap();
+                entry.push_str(line);
+                entry.push('\n');
+            }
+        }
+        entries
+    } else {
+        lines.map(str::to_string).collect()
+    };
+    entries
+        .into_iter()
+        .filter(|e| !e.trim().is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn fortune_truncates_and_shows_the_rest_on_click() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "sh -c fortune -s",
+            0,
+            "You will have a long\nand happy life.\n",
+        );
+        mock.command(
+            "notify-send --app-name=subar Fortune You will have a long\nand happy life.",
+            0,
+            "",
+        );
+        let config = "[[block]]\nblock = \"fortune\"\nmax_length = 20";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🥠 You will have a long… ","name":"fortune""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        assert_eq!(
+            ran[1],
+            "notify-send --app-name=subar Fortune You will have a long\nand happy life."
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::Sys;
+
+pub static GITHUB_FALLBACK: &str = "🔔 ???";
+static GITHUB_MAX_RETRY: u64 = 30 * 60 * 1000;
+pub static GITHUB_API: &str = "https://api.github.com";
+/// The most notifications GitHub returns in one page
+static GITHUB_PAGE_SIZE: usize = 50;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// A personal access token with the notifications and repo scopes
+    pub token: Secret,
+    /// Seconds between checks
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    /// Placeholders: icon, notifications, reviews
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// Opened with xdg-open when the block is clicked
+    #[serde(default = "default_url")]
+    pub url: String,
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+fn default_icon() -> String {
+    "🔔".to_string()
+}
+
+fn default_format() -> Template {
+    Template::parse("{icon} {notifications} 👀 {reviews}").unwrap()
+}
+
+fn default_url() -> String {
+    "https://github.com/notifications".to_string()
+}
+
+pub async fn github_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(GITHUB_MAX_RETRY));
+    let mut token = None;
+    loop {
+        let result = async {
+            let token = match &token {
+                Some(token) => token,
+                None => token.insert(config.token.resolve(&sys).await?),
+            };
+            fetch_counts(&sys, token).await
+        };
+        let wait = match result.await {
+            Ok((0, 0)) => {
+                backoff.reset();
+                tx.send(BlockOutput::default())?;
+                interval
+            }
+            Ok((notifications, reviews)) => {
+                backoff.reset();
+                let notifications = if notifications >= GITHUB_PAGE_SIZE {
+                    format!("{notifications}+")
+                } else {
+                    notifications.to_string()
+                };
+                let status = config.format.render(|name| match name {
+                    "icon" => Some(config.icon.clone()),
+                    "notifications" => Some(notifications.clone()),
+                    "reviews" => Some(reviews.to_string()),
+                    _ => None,
+                });
+                tx.send(status.into())?;
+                interval
+            }
+            Err(err) => {
+                eprintln!("Couldn't get GitHub notifications. {err:#}");
+                // The token may have been revoked, so look it up again
+                token = None;
+                tx.send(GITHUB_FALLBACK.into())?;
+                backoff.fail()
+            }
+        };
+
+        if events
+            .wait(wait)
+            .await
+            .is_some_and(|c| c.button == BUTTON_LEFT)
+        {
+            match sys.output(Command::new("xdg-open").arg(&config.url)).await {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => eprintln!("xdg-open failed with {}", output.status),
+                Err(err) => eprintln!("Couldn't run xdg-open. {err}"),
+            }
+        }
+    }
+}
+
+/// The number of unread notifications and pull requests waiting for review
+async fn fetch_counts(sys: &Sys, token: &str) -> Result<(usize, u64)> {
+    let headers = api_headers(Some(token));
+    let url = format!("{GITHUB_API}/notifications?per_page={GITHUB_PAGE_SIZE}");
+    let body = sys.http_get(&url, headers.clone()).await?;
+    let notifications: Vec<Value> = serde_json::from_str(&body)?;
+
+    let url = format!("{GITHUB_API}/search/issues?q=is:open+is:pr+review-requested:@me&per_page=1");
+    let body = sys.http_get(&url, headers).await?;
+    let json: Value = serde_json::from_str(&body)?;
+    let reviews = json["total_count"]
+        .as_u64()
+        .context("Search response has no total_count")?;
+    Ok((notifications.len(), reviews))
+}
+
+/// Headers for the GitHub REST API. Public data can be read without a token,
+/// but with a much lower rate limit.
+pub fn api_headers(token: Option<&str>) -> Vec<(String, String)> {
+    let mut headers = vec![
+        (
+            "Accept".to_string(),
+            "application/vnd.github+json".to_string(),
+        ),
+        ("X-GitHub-Api-Version".to_string(), "2022-11-28".to_string()),
+    ];
+    if let Some(token) = token {
+        headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn github_counts_with_token_from_command() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c pass github", 0, "ghp_token\n");
+        mock.url(
+            "https://api.github.com/notifications?per_page=50",
+            r#"[{"id": "1"}, {"id": "2"}]"#,
+        );
+        mock.url(
+            "https://api.github.com/search/issues?q=is:open+is:pr+review-requested:@me&per_page=1",
+            r#"{"total_count": 3, "items": []}"#,
+        );
+        let config = r#"
+            [[block]]
+            block = "github"
+            token = { command = "pass github" }
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔔 2 👀 3 ","name":"github""#));
+    }
+}
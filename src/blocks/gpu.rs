@@ -0,0 +1,278 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::{BlockOutput, Threshold};
+use crate::system::Sys;
+
+pub static GPU_FALLBACK: &str = "🎮 ???";
+pub static GPU_UPDATE_FREQUENCY: u64 = 2000;
+static GPU_MAX_RETRY: u64 = 5 * 60 * 1000;
+static GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    /// Where the kernel lists graphics cards, for AMD GPUs
+    pub path: PathBuf,
+    /// Which GPU to show, counting from 0, when there's more than one
+    pub gpu: usize,
+    pub icon: String,
+    /// Placeholders: icon, usage, vram (percent used), vram_used and
+    /// vram_total (in GiB), temperature (in °C)
+    pub format: Template,
+    /// Colours for when the usage reaches a percentage
+    pub colors: Vec<Threshold>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::Auto,
+            path: PathBuf::from("/sys/class/drm"),
+            gpu: 0,
+            icon: "🎮".to_string(),
+            format: Template::parse("{icon} {usage}%").unwrap(),
+            colors: vec![
+                Threshold::new(70.0, "#ffaa00"),
+                Threshold::new(90.0, "#ff4444"),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// An AMD GPU if there is one, otherwise an NVIDIA one
+    Auto,
+    /// The amdgpu driver's files in sysfs
+    Amd,
+    /// `nvidia-smi`, which reads the driver through NVML
+    Nvidia,
+}
+
+/// What a GPU reports. Drivers don't all report everything.
+struct Gpu {
+    usage: Option<f64>,
+    /// Bytes used and in total
+    vram: Option<(f64, f64)>,
+    temperature: Option<f64>,
+}
+
+/// Show how busy a GPU is, with its VRAM and temperature. The block is
+/// hidden if there's no GPU it can read.
+pub async fn gpu_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_millis(GPU_UPDATE_FREQUENCY);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(GPU_MAX_RETRY));
+    loop {
+        let wait = match read(&config, &sys).await {
+            Ok(gpu) => {
+                backoff.reset();
+                tx.send(gpu.map(|gpu| render(&config, &gpu)).unwrap_or_default())?;
+                interval
+            }
+            Err(err) => {
+                eprintln!("Couldn't read the GPU. {err:#}");
+                tx.send(GPU_FALLBACK.into())?;
+                backoff.fail()
+            }
+        };
+        events.wait(wait).await;
+    }
+}
+
+fn render(config: &Config, gpu: &Gpu) -> BlockOutput {
+    let full_text = config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "usage" => gpu.usage.map(|usage| format!("{usage:.0}")),
+        "vram" => gpu
+            .vram
+            .map(|(used, total)| format!("{:.0}", used / total * 100.0)),
+        "vram_used" => gpu.vram.map(|(used, _)| format!("{:.1}", used / GIB)),
+        "vram_total" => gpu.vram.map(|(_, total)| format!("{:.1}", total / GIB)),
+        "temperature" => gpu.temperature.map(|celsius| format!("{celsius:.0}")),
+        _ => None,
+    });
+    BlockOutput {
+        full_text,
+        color: gpu
+            .usage
+            .and_then(|usage| Threshold::color(&config.colors, usage)),
+        ..BlockOutput::default()
+    }
+}
+
+async fn read(config: &Config, sys: &Sys) -> Result<Option<Gpu>> {
+    if config.source == Source::Nvidia {
+        return read_nvidia(config, sys).await;
+    }
+    let cards = amd_cards(&config.path).await;
+    if config.source == Source::Amd || !cards.is_empty() {
+        return match cards.get(config.gpu) {
+            Some(card) => Ok(Some(read_amd(card).await)),
+            None => Ok(None),
+        };
+    }
+    // Having no AMD GPU doesn't mean there's an NVIDIA one, so a missing
+    // nvidia-smi just hides the block
+    match read_nvidia(config, sys).await {
+        Err(err) if is_missing(&err) => Ok(None),
+        result => result,
+    }
+}
+
+fn is_missing(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
+}
+
+/// The cards like "card0" whose driver reports how busy they are, which only
+/// amdgpu does, sorted by number
+async fn amd_cards(path: &Path) -> Vec<PathBuf> {
+    let Ok(mut entries) = tokio::fs::read_dir(path).await else {
+        return Vec::new();
+    };
+    let mut cards = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name();
+        // Connectors like "card0-DP-1" are listed too
+        let Some(number) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("card"))
+            .and_then(|number| number.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let device = entry.path().join("device");
+        if tokio::fs::metadata(device.join("gpu_busy_percent"))
+            .await
+            .is_ok()
+        {
+            cards.push((number, device));
+        }
+    }
+    cards.sort();
+    cards.into_iter().map(|(_, device)| device).collect()
+}
+
+async fn read_amd(device: &Path) -> Gpu {
+    let used = number(&device.join("mem_info_vram_used")).await;
+    let total = number(&device.join("mem_info_vram_total")).await;
+    let mut temperature = None;
+    if let Ok(mut entries) = tokio::fs::read_dir(device.join("hwmon")).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Some(millidegrees) = number(&entry.path().join("temp1_input")).await {
+                temperature = Some(millidegrees / 1000.0);
+                break;
+            }
+        }
+    }
+    Gpu {
+        usage: number(&device.join("gpu_busy_percent")).await,
+        vram: used.zip(total.filter(|total| *total > 0.0)),
+        temperature,
+    }
+}
+
+async fn number(path: &Path) -> Option<f64> {
+    let text = tokio::fs::read_to_string(path).await.ok()?;
+    text.trim().parse().ok()
+}
+
+/// `nvidia-smi` prints a line like "37, 1234, 8192, 55" for each GPU, with
+/// memory in MiB, or "[N/A]" for anything the card doesn't report
+async fn read_nvidia(config: &Config, sys: &Sys) -> Result<Option<Gpu>> {
+    let mut nvidia_smi = Command::new("nvidia-smi");
+    nvidia_smi.args([
+        "--query-gpu=utilization.gpu,memory.used,memory.total,temperature.gpu",
+        "--format=csv,noheader,nounits",
+    ]);
+    let output = sys
+        .output(&mut nvidia_smi)
+        .await
+        .context("Couldn't run nvidia-smi")?;
+    if !output.status.success() {
+        bail!("nvidia-smi failed with {}", output.status);
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let Some(line) = stdout.lines().nth(config.gpu) else {
+        return Ok(None);
+    };
+    let fields: Vec<Option<f64>> = line
+        .split(',')
+        .map(|field| field.trim().parse().ok())
+        .collect();
+    let field = |i: usize| fields.get(i).copied().flatten();
+    let mib = 1024.0 * 1024.0;
+    Ok(Some(Gpu {
+        usage: field(0),
+        vram: field(1)
+            .zip(field(2).filter(|total| *total > 0.0))
+            .map(|(used, total)| (used * mib, total * mib)),
+        temperature: field(3),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn gpus_are_read_from_amdgpu_and_nvidia_smi() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        let gib: u64 = 1024 * 1024 * 1024;
+        sys.attributes(
+            "card1/device",
+            &[
+                ("gpu_busy_percent", "93"),
+                ("mem_info_vram_used", &(3 * gib / 2).to_string()),
+                ("mem_info_vram_total", &(8 * gib).to_string()),
+                ("hwmon/hwmon3/temp1_input", "61000"),
+            ],
+        );
+        sys.attributes("card1-DP-1", &[("status", "connected")]);
+        mock.command(
+            "nvidia-smi --query-gpu=utilization.gpu,memory.used,memory.total,temperature.gpu --format=csv,noheader,nounits",
+            0,
+            "12, 2048, 8192, [N/A]\n",
+        );
+        let config = format!(
+            r#"
+            [[block]]
+            block = "gpu"
+            source = "amd"
+            path = {:?}
+            format = "{{usage}}% {{vram_used}}/{{vram_total}}GiB {{temperature}}°C"
+
+            [[block]]
+            block = "gpu"
+            source = "nvidia"
+            format = "{{usage}}% {{vram}}%[ {{temperature}}°C]"
+            "#,
+            sys.path("")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock).await.starts_with(concat!(
+            r##"[{"full_text":"93% 1.5/8.0GiB 61°C ","color":"#ff4444","name":"gpu","instance":"0","##,
+            r#""separator":false,"separator_block_width":0},"#,
+            r#"{"full_text":"12% 25% ","name":"gpu""#
+        )));
+    }
+}
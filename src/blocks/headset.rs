@@ -0,0 +1,182 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static HEADSET_FALLBACK: &str = "🎧 ???";
+pub static HEADSET_UPDATE_FREQUENCY: u64 = 30 * 1000;
+static HEADSET_MAX_RETRY: u64 = 10 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Only show devices whose name contains one of these, ignoring case.
+    /// Without any, every connected device that reports its battery is shown.
+    pub devices: Vec<String>,
+    pub icon: String,
+    /// Placeholders: icon, name, battery
+    pub format: Template,
+    /// The block is urgent at or below this percent
+    pub low: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            devices: Vec::new(),
+            icon: "🎧".to_string(),
+            format: Template::parse("{icon} {battery}%").unwrap(),
+            low: 20,
+        }
+    }
+}
+
+/// A connected Bluetooth device and its battery
+struct Headset {
+    name: String,
+    battery: u8,
+}
+
+/// Show the battery of connected Bluetooth devices, from BlueZ's battery
+/// interface through `bluetoothctl`. The block is empty while none are
+/// connected.
+pub async fn headset_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(HEADSET_UPDATE_FREQUENCY),
+        Duration::from_millis(HEADSET_MAX_RETRY),
+    );
+    loop {
+        let wait = match headsets(&config, &sys).await {
+            Ok(headsets) => {
+                backoff.reset();
+                tx.send(render(&config, &headsets))?;
+                Duration::from_millis(HEADSET_UPDATE_FREQUENCY)
+            }
+            Err(err) => {
+                eprintln!("Couldn't read Bluetooth batteries. {err:#}");
+                tx.send(HEADSET_FALLBACK.into())?;
+                backoff.fail()
+            }
+        };
+        events.wait(wait).await;
+    }
+}
+
+fn render(config: &Config, headsets: &[Headset]) -> BlockOutput {
+    let Some(low) = headsets.iter().map(|h| h.battery).min() else {
+        return BlockOutput::default();
+    };
+    let shown: Vec<_> = headsets
+        .iter()
+        .map(|headset| {
+            config.format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                "name" => Some(headset.name.clone()),
+                "battery" => Some(headset.battery.to_string()),
+                _ => None,
+            })
+        })
+        .collect();
+    BlockOutput {
+        full_text: shown.join(" "),
+        urgent: low <= config.low,
+        ..BlockOutput::default()
+    }
+}
+
+/// `bluetoothctl devices Connected` prints lines like "Device
+/// 00:11:22:33:44:55 WH-1000XM4", and `bluetoothctl info` prints "Battery
+/// Percentage: 0x50 (80)" for devices that report it
+async fn headsets(config: &Config, sys: &Sys) -> Result<Vec<Headset>> {
+    let devices = bluetoothctl(sys, &["devices", "Connected"]).await?;
+    let mut headsets = Vec::new();
+    for line in devices.lines() {
+        let Some((address, name)) = line
+            .strip_prefix("Device ")
+            .and_then(|device| device.split_once(' '))
+        else {
+            continue;
+        };
+        let wanted = config.devices.is_empty()
+            || config
+                .devices
+                .iter()
+                .any(|d| name.to_lowercase().contains(&d.to_lowercase()));
+        if !wanted {
+            continue;
+        }
+        let info = bluetoothctl(sys, &["info", address]).await?;
+        let battery = info.lines().find_map(|line| {
+            let percentage = line.trim().strip_prefix("Battery Percentage: ")?;
+            let (_, decimal) = percentage.split_once('(')?;
+            decimal.strip_suffix(')')?.parse().ok()
+        });
+        if let Some(battery) = battery {
+            headsets.push(Headset {
+                name: name.to_string(),
+                battery,
+            });
+        }
+    }
+    Ok(headsets)
+}
+
+async fn bluetoothctl(sys: &Sys, args: &[&str]) -> Result<String> {
+    let mut bluetoothctl = Command::new("bluetoothctl");
+    bluetoothctl.args(args);
+    let output = sys.output(&mut bluetoothctl).await?;
+    if !output.status.success() {
+        bail!("bluetoothctl failed with {}", output.status);
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn headset_battery_shows_while_connected() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "bluetoothctl devices Connected",
+            0,
+            "Device 00:11:22:33:44:55 WH-1000XM4\nDevice AA:BB:CC:DD:EE:FF MX Keys\n",
+        );
+        mock.command(
+            "bluetoothctl info 00:11:22:33:44:55",
+            0,
+            "Device 00:11:22:33:44:55 (public)\n\tName: WH-1000XM4\n\tConnected: yes\n\tBattery Percentage: 0x12 (18)\n",
+        );
+        let config = r#"
+            [[block]]
+            block = "headset"
+            devices = ["wh-1000"]
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎧 18% ","urgent":true,"name":"headset""#));
+        assert!(!mock.ran.lock().unwrap().iter().any(|c| c.contains("AA:BB")));
+
+        mock.command("bluetoothctl devices Connected", 0, "");
+        sleep(Duration::from_millis(HEADSET_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(!line.contains("headset"));
+    }
+}
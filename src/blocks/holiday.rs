@@ -0,0 +1,193 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::cache::Cache;
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static HOLIDAY_FALLBACK: &str = "🎉 ???";
+static HOLIDAY_UPDATE_FREQUENCY: u64 = 60 * 1000;
+static HOLIDAY_RETRY_FREQUENCY: u64 = 10 * 60 * 1000;
+static HOLIDAY_CACHE_TTL: u64 = 7 * 24 * 60 * 60;
+static NAGER_URL: &str = "https://date.nager.at/api/v3/PublicHolidays";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// ISO 3166-1 country code used to look up public holidays, e.g. "AU"
+    pub country: Option<String>,
+    /// ISO 3166-2 region code, e.g. "AU-NSW". Holidays that only apply to
+    /// other regions are ignored.
+    pub region: Option<String>,
+    /// Use holiday names in the country's language rather than English
+    pub local_names: bool,
+    /// Extra days to show, such as name days or birthdays
+    #[serde(rename = "observance")]
+    pub observances: Vec<Observance>,
+    pub icon: String,
+    /// Placeholders: icon, holiday
+    pub format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            country: None,
+            region: None,
+            local_names: false,
+            observances: Vec::new(),
+            icon: "🎉".to_string(),
+            format: Template::parse("{icon} {holiday}").unwrap(),
+        }
+    }
+}
+
+/// Something to show on the same day every year
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Observance {
+    /// The month and day as MM-DD
+    pub date: String,
+    pub label: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Holiday {
+    date: String,
+    local_name: String,
+    name: String,
+    global: bool,
+    counties: Option<Vec<String>>,
+}
+
+pub async fn holiday_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut holidays: Option<(i32, Vec<Holiday>)> = None;
+    loop {
+        let today = sys.now().date_naive();
+        let mut wait = HOLIDAY_UPDATE_FREQUENCY;
+        if let Some(country) = &config.country {
+            if holidays
+                .as_ref()
+                .is_none_or(|(year, _)| *year != today.year())
+            {
+                match fetch_holidays(&sys, country, today.year()).await {
+                    Ok(fetched) => holidays = Some((today.year(), fetched)),
+                    Err(err) => {
+                        eprintln!("Couldn't get holidays for {country}. {err:#}");
+                        wait = HOLIDAY_RETRY_FREQUENCY;
+                    }
+                }
+            }
+        }
+
+        let fetched = holidays.as_ref().map(|(_, h)| h.as_slice()).unwrap_or(&[]);
+        let names = todays_holidays(&config, fetched, today);
+        let status = if names.is_empty() {
+            String::new()
+        } else {
+            let holiday = names.join(", ");
+            config.format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                "holiday" => Some(holiday.clone()),
+                _ => None,
+            })
+        };
+        tx.send(status.into())?;
+        events.wait(Duration::from_millis(wait)).await;
+    }
+}
+
+async fn fetch_holidays(sys: &Sys, country: &str, year: i32) -> Result<Vec<Holiday>> {
+    let ttl = Duration::from_secs(HOLIDAY_CACHE_TTL);
+    let cache = Cache::new(sys, &format!("holidays-{country}-{year}"), ttl);
+    if let Some(cache) = &cache {
+        if let Some(cached) = cache.load().await {
+            return Ok(cached.value);
+        }
+    }
+    let url = format!("{NAGER_URL}/{year}/{country}");
+    let body = sys.http_get(&url, Vec::new()).await?;
+    let holidays = serde_json::from_str(&body)?;
+    if let Some(cache) = &cache {
+        cache.store(&holidays).await;
+    }
+    Ok(holidays)
+}
+
+fn todays_holidays(config: &Config, holidays: &[Holiday], today: NaiveDate) -> Vec<String> {
+    let date = today.format("%Y-%m-%d").to_string();
+    let month_day = today.format("%m-%d").to_string();
+    let in_region = |h: &Holiday| match (&config.region, &h.counties) {
+        (Some(region), Some(counties)) => h.global || counties.contains(region),
+        _ => true,
+    };
+    let public = holidays
+        .iter()
+        .filter(|h| h.date == date && in_region(h))
+        .map(|h| {
+            if config.local_names {
+                h.local_name.clone()
+            } else {
+                h.name.clone()
+            }
+        });
+    let observed = config
+        .observances
+        .iter()
+        .filter(|o| o.date == month_day)
+        .map(|o| o.label.clone());
+    let mut names: Vec<String> = public.chain(observed).collect();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn holidays_for_the_region_and_observances() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://date.nager.at/api/v3/PublicHolidays/2026/AU",
+            r#"[
+                {"date": "2026-10-14", "localName": "Tag der Stadt", "name": "City Day", "global": false, "counties": ["AU-NSW"]},
+                {"date": "2026-10-14", "localName": "Tag des Landes", "name": "Country Day", "global": true, "counties": null},
+                {"date": "2026-10-15", "localName": "Morgen", "name": "Tomorrow", "global": true, "counties": null}
+            ]"#,
+        );
+        let config = r#"
+            [[block]]
+            block = "holiday"
+            country = "AU"
+            region = "AU-NSW"
+            [[block.observance]]
+            date = "10-14"
+            label = "Ada's birthday"
+
+            [[block]]
+            block = "holiday"
+            country = "AU"
+            region = "AU-VIC"
+            local_names = true
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(concat!(
+            r#"[{"full_text":"🎉 City Day, Country Day, Ada's birthday ","name":"holiday","instance":"0","separator":false,"separator_block_width":0},"#,
+            r#"{"full_text":"🎉 Tag des Landes ","name":"holiday","instance":"1""#,
+        )));
+    }
+}
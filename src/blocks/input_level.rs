@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+
+use crate::backoff::Backoff;
+use crate::click::BUTTON_LEFT;
+use crate::events::Events;
+use crate::format::{progress_bar, Template};
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static INPUT_LEVEL_FALLBACK: &str = "🎙️ ???";
+static INPUT_LEVEL_MAX_RETRY: u64 = 60 * 1000;
+/// Samples per second requested from the capture command
+static SAMPLE_RATE: usize = 8000;
+/// How many times a second the meter updates
+static METER_UPDATES: usize = 10;
+/// The quietest level shown, in dBFS
+static METER_FLOOR: f64 = -60.0;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Shell command that records the default source as mono signed 16 bit
+    /// little endian samples at 8 kHz and writes them to stdout
+    pub command: String,
+    /// Keep the meter running rather than only after clicking the block.
+    /// This keeps the microphone in use the whole time.
+    pub always_on: bool,
+    /// Seconds the meter runs for after clicking the block
+    pub duration: u64,
+    pub icon: String,
+    /// Shown while the meter is running. Placeholders: icon, meter, level
+    pub format: Template,
+    /// Shown while the meter isn't running. Placeholders: icon
+    pub idle_format: Template,
+    pub meter_width: usize,
+    /// The filled and empty characters of `{meter}`
+    pub meter_chars: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            command: "pw-record --raw --format s16 --rate 8000 --channels 1 -".to_string(),
+            always_on: false,
+            duration: 30,
+            icon: "🎙️".to_string(),
+            format: Template::parse("{icon} {meter}").unwrap(),
+            idle_format: Template::parse("{icon}").unwrap(),
+            meter_width: 5,
+            meter_chars: "▰▱".to_string(),
+        }
+    }
+}
+
+pub async fn input_level_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(INPUT_LEVEL_MAX_RETRY),
+    );
+    loop {
+        if !config.always_on {
+            let idle = config.idle_format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                _ => None,
+            });
+            tx.send(idle.into())?;
+            let click = events.wait(Duration::MAX).await;
+            if click.is_none_or(|c| c.button != BUTTON_LEFT) {
+                continue;
+            }
+        }
+        let until =
+            (!config.always_on).then(|| Instant::now() + Duration::from_secs(config.duration));
+        match meter(&config, &sys, &tx, &mut events, until).await {
+            Ok(()) => backoff.reset(),
+            Err(err) => {
+                eprintln!("Couldn't measure the input level. {err:#}");
+                tx.send(INPUT_LEVEL_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+            }
+        }
+    }
+}
+
+/// Show the input level until `until`, or until the block is clicked
+async fn meter(
+    config: &Config,
+    sys: &Sys,
+    tx: &watch::Sender<BlockOutput>,
+    events: &mut Events,
+    until: Option<Instant>,
+) -> Result<()> {
+    let mut reader = sys.stream(Command::new("sh").arg("-c").arg(&config.command))?;
+    let mut window = vec![0u8; SAMPLE_RATE / METER_UPDATES * 2];
+    let mut filled = 0;
+    loop {
+        let remaining = until.map_or(Duration::MAX, |u| {
+            u.saturating_duration_since(Instant::now())
+        });
+        tokio::select! {
+            read = reader.read(&mut window[filled..]) => {
+                let read = read?;
+                if read == 0 {
+                    bail!("`{}` stopped", config.command);
+                }
+                filled += read;
+                if filled < window.len() {
+                    continue;
+                }
+                filled = 0;
+                let level = peak_level(&window);
+                let fraction = (level - METER_FLOOR) / -METER_FLOOR;
+                let status = config.format.render(|name| match name {
+                    "icon" => Some(config.icon.clone()),
+                    "meter" => Some(progress_bar(fraction, config.meter_width, &config.meter_chars)),
+                    "level" => Some(format!("{level:.0} dB")),
+                    _ => None,
+                });
+                tx.send(status.into())?;
+            }
+            click = events.wait(remaining) => {
+                let clicked = click.is_some_and(|c| c.button == BUTTON_LEFT);
+                if clicked || until.is_some_and(|u| Instant::now() >= u) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// The loudest sample in dBFS
+fn peak_level(samples: &[u8]) -> f64 {
+    let peak = samples
+        .chunks_exact(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]).unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    if peak == 0 {
+        return METER_FLOOR;
+    }
+    (20.0 * (f64::from(peak) / 32768.0).log10()).max(METER_FLOOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn input_level_meters_after_click() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        // A tenth of a second at -20 dBFS
+        let samples = 3277i16.to_le_bytes().repeat(800);
+        let command = "sh -c pw-record --raw --format s16 --rate 8000 --channels 1 -";
+        mock.stream(command, samples);
+        let mut tasks = start("[[block]]\nblock = \"input_level\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎙️ ","name":"input_level""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎙️ ▰▰▰▱▱ ","name":"input_level""#));
+
+        sleep(Duration::from_secs(30)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎙️ ","name":"input_level""#));
+    }
+}
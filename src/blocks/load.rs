@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::{BlockOutput, Threshold};
+
+pub static LOAD_FALLBACK: &str = "📈 ???";
+pub static LOAD_UPDATE_FREQUENCY: u64 = 5000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the kernel reports the load averages
+    pub path: PathBuf,
+    /// Divide the averages by the number of cores, so 1.0 means they're all
+    /// busy
+    pub normalise: bool,
+    pub icon: String,
+    /// Placeholders: icon, one, five, fifteen (the 1, 5, and 15 minute
+    /// averages)
+    pub format: Template,
+    /// Colours for when the 1 minute average per core reaches a threshold,
+    /// whether or not it's normalised
+    pub colors: Vec<Threshold>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/proc/loadavg"),
+            normalise: false,
+            icon: "📈".to_string(),
+            format: Template::parse("{icon} {one}").unwrap(),
+            colors: vec![
+                Threshold::new(1.0, "#ffaa00"),
+                Threshold::new(2.0, "#ff4444"),
+            ],
+        }
+    }
+}
+
+/// Show the load averages from /proc/loadavg
+pub async fn load_task(
+    config: Config,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get()) as f64;
+    loop {
+        let [one, five, fifteen] = match read_loadavg(&config.path).await {
+            Ok(averages) => averages,
+            Err(err) => {
+                eprintln!("Couldn't read the load. {err:#}");
+                tx.send(LOAD_FALLBACK.into())?;
+                events
+                    .wait(Duration::from_millis(LOAD_UPDATE_FREQUENCY))
+                    .await;
+                continue;
+            }
+        };
+        let scale = if config.normalise { cores } else { 1.0 };
+        let shown = |average: f64| Some(format!("{:.2}", average / scale));
+        let full_text = config.format.render(|name| match name {
+            "icon" => Some(config.icon.clone()),
+            "one" => shown(one),
+            "five" => shown(five),
+            "fifteen" => shown(fifteen),
+            _ => None,
+        });
+        tx.send(BlockOutput {
+            full_text,
+            color: Threshold::color(&config.colors, one / cores),
+            ..BlockOutput::default()
+        })?;
+        events
+            .wait(Duration::from_millis(LOAD_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// The 1, 5, and 15 minute averages
+async fn read_loadavg(path: &Path) -> Result<[f64; 3]> {
+    let loadavg = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    // Like "0.52 0.58 0.59 1/1024 12345", with the running and total tasks
+    // and the newest process after the averages
+    let averages: Vec<f64> = loadavg
+        .split_whitespace()
+        .take(3)
+        .filter_map(|average| average.parse().ok())
+        .collect();
+    let [one, five, fifteen] = averages[..] else {
+        bail!("Couldn't parse {}", path.display());
+    };
+    Ok([one, five, fifteen])
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn load_can_be_normalised_by_the_cores() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let cores = std::thread::available_parallelism().unwrap().get() as f64;
+        let loadavg = format!("{} {} 0.00 2/1024 12345\n", cores * 1.5, cores / 4.0);
+        proc.write("loadavg", loadavg);
+        let config = format!(
+            "[[block]]\nblock = \"load\"\npath = {:?}\nnormalise = true\nformat = \"{{one}} {{five}} {{fifteen}}\"",
+            proc.path("loadavg")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r##"[{"full_text":"1.50 0.25 0.00 ","color":"#ffaa00","name":"load""##));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unparsable_load_shows_the_fallback() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        proc.write("loadavg", "0.52 0.58\n");
+        let config = format!(
+            "[[block]]\nblock = \"load\"\npath = {:?}",
+            proc.path("loadavg")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"📈 ??? ","name":"load""#));
+        proc.write("loadavg", "0.52 0.58 0.59 1/1024 12345\n");
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"📈 0.52 ","name":"load""#));
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::bsd;
+use crate::events::Events;
+use crate::format::{format_bytes, Template};
+use crate::output::{BlockOutput, Threshold};
+use crate::system::Sys;
+
+pub static MEMORY_FALLBACK: &str = "🧠 ???";
+pub static MEMORY_UPDATE_FREQUENCY: u64 = 5000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source: Source,
+    /// Where the kernel reports memory use, for the "proc" source
+    pub path: PathBuf,
+    pub icon: String,
+    /// Placeholders: icon, used, available, total (like "5.2G"), percent
+    /// (of the total that's used)
+    pub format: Template,
+    /// Colours for when the percentage used reaches a threshold
+    pub colors: Vec<Threshold>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::default(),
+            path: PathBuf::from("/proc/meminfo"),
+            icon: "🧠".to_string(),
+            format: Template::parse("{icon} {used}").unwrap(),
+            colors: vec![
+                Threshold::new(80.0, "#ffaa00"),
+                Threshold::new(90.0, "#ff4444"),
+            ],
+        }
+    }
+}
+
+/// Where the memory use comes from
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// Linux's /proc/meminfo
+    Proc,
+    /// FreeBSD's `vm.stats` sysctls
+    Sysctl,
+    /// OpenBSD's `vmstat -s`
+    Vmstat,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        if cfg!(target_os = "openbsd") {
+            Self::Vmstat
+        } else if cfg!(target_os = "freebsd") {
+            Self::Sysctl
+        } else {
+            Self::Proc
+        }
+    }
+}
+
+/// Show how much memory is used, from /proc/meminfo or the BSDs' page counts.
+/// Memory the kernel could free, like the page cache, counts as available.
+pub async fn memory_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    loop {
+        let read = match config.source {
+            Source::Proc => read_memory(&config.path).await,
+            Source::Sysctl => read_sysctl(&sys).await,
+            Source::Vmstat => read_vmstat(&sys).await,
+        };
+        let output = match read {
+            Ok((total, available)) => render(&config, total, available),
+            Err(err) => {
+                eprintln!("Couldn't read the memory use. {err:#}");
+                MEMORY_FALLBACK.into()
+            }
+        };
+        tx.send(output)?;
+        events
+            .wait(Duration::from_millis(MEMORY_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// The total and available memory, in bytes
+async fn read_memory(path: &Path) -> Result<(f64, f64)> {
+    let meminfo = read_meminfo(path).await?;
+    let field = |name: &str| {
+        meminfo
+            .get(name)
+            .copied()
+            .with_context(|| format!("{} has no {name}", path.display()))
+    };
+    Ok((field("MemTotal")?, field("MemAvailable")?))
+}
+
+/// FreeBSD's total and available memory, where free and inactive pages are
+/// available
+async fn read_sysctl(sys: &Sys) -> Result<(f64, f64)> {
+    let [total, page, free, inactive] = bsd::sysctl_numbers(
+        sys,
+        [
+            "hw.physmem",
+            "hw.pagesize",
+            "vm.stats.vm.v_free_count",
+            "vm.stats.vm.v_inactive_count",
+        ],
+    )
+    .await?;
+    Ok((total, (free + inactive) * page))
+}
+
+/// OpenBSD's total and available memory, from lines of `vmstat -s` like
+/// "  4096 bytes per page" and "  2045 pages free"
+async fn read_vmstat(sys: &Sys) -> Result<(f64, f64)> {
+    let vmstat = bsd::run(sys, Command::new("vmstat").arg("-s")).await?;
+    let field = |name: &str| {
+        vmstat
+            .lines()
+            .find_map(|line| {
+                line.trim()
+                    .split_once(' ')
+                    .filter(|(_, rest)| *rest == name)
+            })
+            .with_context(|| format!("vmstat -s has no {name}"))
+            .and_then(|(number, _)| bsd::number(name, number))
+    };
+    let page = field("bytes per page")?;
+    let available = field("pages free")? + field("pages inactive")?;
+    Ok((field("pages managed")? * page, available * page))
+}
+
+fn render(config: &Config, total: f64, available: f64) -> BlockOutput {
+    let used = total - available;
+    let percent = if total > 0.0 {
+        used / total * 100.0
+    } else {
+        0.0
+    };
+    let full_text = config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "used" => Some(format_bytes(used)),
+        "available" => Some(format_bytes(available)),
+        "total" => Some(format_bytes(total)),
+        "percent" => Some(format!("{percent:.0}")),
+        _ => None,
+    });
+    BlockOutput {
+        full_text,
+        color: Threshold::color(&config.colors, percent),
+        ..BlockOutput::default()
+    }
+}
+
+/// Each line of /proc/meminfo, like "MemTotal: 16318508 kB", in bytes
+pub async fn read_meminfo(path: &Path) -> Result<HashMap<String, f64>> {
+    let meminfo = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let fields = meminfo.lines().filter_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let mut value = value.split_whitespace();
+        let number: f64 = value.next()?.parse().ok()?;
+        let scale = match value.next() {
+            Some("kB") => 1024.0,
+            _ => 1.0,
+        };
+        Some((name.to_string(), number * scale))
+    });
+    Ok(fields.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn memory_counts_reclaimable_memory_as_available() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:          512000 kB\nMemAvailable:    2048000 kB\nHugePages_Total:       0\n";
+        proc.write("meminfo", meminfo);
+        let config = format!(
+            "[[block]]\nblock = \"memory\"\npath = {:?}\nformat = \"{{used}}/{{total}} {{percent}}% {{available}}\"",
+            proc.path("meminfo")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"14G/16G 88% 2.0G ","color":"#ffaa00","name":"memory""##
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unreadable_memory_shows_the_fallback_until_it_can_be_read() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        // Kernels before 3.14 don't have MemAvailable
+        proc.write("meminfo", "MemTotal:       16384000 kB\n");
+        let config = format!(
+            "[[block]]\nblock = \"memory\"\npath = {:?}",
+            proc.path("meminfo")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🧠 ??? ","name":"memory""#));
+
+        proc.write(
+            "meminfo",
+            "MemTotal:       16384000 kB\nMemAvailable:   12288000 kB\n",
+        );
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🧠 3.9G ","name":"memory""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn freebsd_memory_is_read_with_sysctl() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "sysctl -n hw.physmem hw.pagesize vm.stats.vm.v_free_count vm.stats.vm.v_inactive_count",
+            0,
+            "17179869184\n4096\n524288\n524288\n",
+        );
+        let config =
+            "[[block]]\nblock = \"memory\"\nsource = \"sysctl\"\nformat = \"{used}/{total}\"";
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"12G/16G ","name":"memory""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn openbsd_memory_is_read_with_vmstat() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let vmstat = "     4096 bytes per page\n  4194304 pages managed\n   786432 pages free\n   262144 pages inactive\n        0 pages being paged out\n";
+        mock.command("vmstat -s", 0, vmstat);
+        let config =
+            "[[block]]\nblock = \"memory\"\nsource = \"vmstat\"\nformat = \"{used}/{total}\"";
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"12G/16G ","name":"memory""#));
+    }
+}
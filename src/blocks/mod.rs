@@ -0,0 +1,50 @@
+#[cfg(feature = "weather")]
+pub mod aqi;
+pub mod battery;
+pub mod calendar;
+pub mod camera;
+#[cfg(feature = "http")]
+pub mod chat;
+#[cfg(feature = "http")]
+pub mod ci;
+pub mod clipboard;
+pub mod clock;
+pub mod cpu;
+#[cfg(feature = "audio")]
+pub mod easyeffects;
+pub mod fortune;
+#[cfg(feature = "http")]
+pub mod github;
+pub mod gpu;
+#[cfg(feature = "audio")]
+pub mod headset;
+#[cfg(feature = "http")]
+pub mod holiday;
+#[cfg(feature = "audio")]
+pub mod input_level;
+pub mod load;
+pub mod memory;
+pub mod moon;
+#[cfg(feature = "mpd")]
+pub mod mpd;
+#[cfg(feature = "dbus")]
+pub mod mpris;
+#[cfg(feature = "http")]
+pub mod parcel;
+pub mod pomodoro;
+#[cfg(feature = "dbus")]
+pub mod power_profile;
+pub mod schedule;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod stopwatch;
+pub mod sun;
+pub mod swap;
+pub mod temperature;
+pub mod timer;
+#[cfg(feature = "http")]
+pub mod transit;
+#[cfg(feature = "audio")]
+pub mod volume;
+#[cfg(feature = "weather")]
+pub mod weather;
@@ -0,0 +1,118 @@
+use std::f64::consts::PI;
+
+use anyhow::Result;
+use chrono::{DateTime, Days, Local};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::Template;
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static MOON_FALLBACK: &str = "🌙 ???";
+
+/// A new moon on the 6th of January 2000 at 18:14 UTC
+static MOON_NEW_EPOCH: i64 = 947182440;
+/// The average time from one new moon to the next, in days
+static MOON_SYNODIC_MONTH: f64 = 29.530588853;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Placeholders: icon, phase, illumination
+    pub format: Template,
+    /// Mirror the icons, since the moon waxes from the left south of the
+    /// equator
+    pub southern: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            format: Template::parse("{icon}").unwrap(),
+            southern: false,
+        }
+    }
+}
+
+/// Show the moon's phase, worked out from the date. It's updated each
+/// midnight.
+pub async fn moon_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    loop {
+        let now = sys.now();
+        tx.send(render(&config, now).into())?;
+        let midnight = now
+            .date_naive()
+            .checked_add_days(Days::new(1))
+            .and_then(|day| {
+                day.and_hms_opt(0, 0, 0)?
+                    .and_local_timezone(Local)
+                    .earliest()
+            });
+        let wait = midnight
+            .and_then(|midnight| (midnight - now).to_std().ok())
+            .unwrap_or(Duration::from_secs(60 * 60));
+        events.wait(wait).await;
+    }
+}
+
+fn render(config: &Config, now: DateTime<Local>) -> String {
+    let days = (now.timestamp() - MOON_NEW_EPOCH) as f64 / 86400.0;
+    // How far through the month it is, from 0 at a new moon to 1 at the next
+    let age = days.rem_euclid(MOON_SYNODIC_MONTH) / MOON_SYNODIC_MONTH;
+    let phase = (age * 8.0).round() as usize % 8;
+    let illumination = (1.0 - (2.0 * PI * age).cos()) / 2.0 * 100.0;
+    config.format.render(|name| match name {
+        "icon" => {
+            let icons = if config.southern {
+                ["🌑", "🌘", "🌗", "🌖", "🌕", "🌔", "🌓", "🌒"]
+            } else {
+                ["🌑", "🌒", "🌓", "🌔", "🌕", "🌖", "🌗", "🌘"]
+            };
+            Some(icons[phase].to_string())
+        }
+        "phase" => {
+            let names = [
+                "moon-new",
+                "moon-waxing-crescent",
+                "moon-first-quarter",
+                "moon-waxing-gibbous",
+                "moon-full",
+                "moon-waning-gibbous",
+                "moon-last-quarter",
+                "moon-waning-crescent",
+            ];
+            Some(tr(names[phase], &[]))
+        }
+        "illumination" => Some(format!("{illumination:.0}")),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn moon_shows_the_phase() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = r#"
+            [[block]]
+            block = "moon"
+            format = "{icon} {phase} {illumination}%"
+            southern = true
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🌘 waxing crescent 10% ","#));
+    }
+}
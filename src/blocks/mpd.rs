@@ -0,0 +1,1083 @@
+use std::env;
+use std::sync::Arc;
+
+use anyhow::Result;
+use mpd_client::client::{ConnectionEvent, ConnectionEvents, Subsystem};
+use mpd_client::commands::{self, Command, Seek, SeekMode, SingleMode, SongId};
+use mpd_client::responses::{PlayState, Song, SongInQueue, Status};
+use mpd_client::tag::Tag;
+use mpd_client::Client;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::backoff::Backoff;
+use crate::click::{ClickEvent, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_UP};
+use crate::events::Events;
+use crate::format::{
+    format_duration, marquee, progress_bar, truncate, truncate_song, Template, Truncation,
+};
+use crate::history::{self, MPD_LISTENED, MPD_SONGS};
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+static MPD_DEFAULT_HOST: &str = "/run/mpd/socket";
+static MPD_DEFAULT_PORT: u16 = 6600;
+pub static MPD_FALLBACK: &str = "🎵 ???";
+static MPD_ELAPSED_FREQUENCY: u64 = 1000;
+static MPD_IDLE_FREQUENCY: u64 = 60 * 1000;
+static MPD_MAX_RETRY: u64 = 30 * 1000;
+/// How often listening time is saved while playing, so little is lost if the
+/// bar stops
+static MPD_LISTENED_SAVE: u64 = 10 * 60;
+/// Put between the end of a scrolling song and its start
+static MARQUEE_GAP: &str = "   ";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The server's socket path, @abstract socket name, host, or host:port,
+    /// optionally with a password like `password@host`. Defaults to
+    /// `$MPD_HOST`, then localhost if a port is set, then /run/mpd/socket.
+    pub host: Option<String>,
+    /// Port for a host that doesn't include one. Defaults to `$MPD_PORT`, or
+    /// 6600.
+    pub port: Option<u16>,
+    /// Shown in `{label}`, to tell servers apart
+    pub label: String,
+    /// Hide the block while it isn't playing and another MPD block is
+    pub hide_while_others_play: bool,
+    /// Hide the block while MPD is stopped or has nothing queued
+    pub hide_when_idle: bool,
+    /// Placeholders: label, icon, state, song, artist, title, station, album,
+    /// track, disc, date, genre, time, elapsed, duration, progress, percent,
+    /// position, queue_length, queue, volume, repeat, random, single,
+    /// consume, modes, audio, sample_rate, bits, channels, bitrate, rating.
+    /// Tags the song doesn't have, and modes that are off, are empty.
+    pub format: Template,
+    /// How many characters wide `{progress}` is
+    pub progress_width: usize,
+    /// The filled and empty characters of `{progress}`
+    pub progress_chars: String,
+    pub playing_icon: String,
+    pub paused_icon: String,
+    pub stopped_icon: String,
+    /// Shown in `{repeat}`, `{random}`, `{single}`, and `{consume}` while
+    /// that mode is on, and together in `{modes}`
+    pub repeat_icon: String,
+    pub random_icon: String,
+    pub single_icon: String,
+    pub consume_icon: String,
+    /// Text colour while paused or stopped, e.g. "#888888" to dim the block
+    pub paused_color: Option<String>,
+    /// How many characters of `{song}` are shown
+    pub max_length: usize,
+    /// What's cut from songs longer than `max_length`: "end", or "artist" to
+    /// keep the title
+    pub truncate: Truncation,
+    /// Scroll songs longer than `max_length` instead of truncating them
+    pub marquee: bool,
+    /// Milliseconds between each step of the scroll
+    pub marquee_interval: u64,
+    /// Seconds to seek by when scrolling on the block
+    pub seek_step: u64,
+    /// The sticker `{rating}` is read from, which is only asked for if the
+    /// format shows it
+    pub rating_sticker: String,
+    /// The highest rating, which fills every star. Most clients rate out of
+    /// 10, so half a star is a point.
+    pub rating_max: f64,
+    /// How many stars `{rating}` has
+    pub rating_stars: usize,
+    /// The filled and empty stars of `{rating}`
+    pub rating_chars: String,
+    /// Seconds between asking MPD for its status while nothing changes. MPD
+    /// says when the song, state, or position changes, so in between the
+    /// elapsed time is counted locally.
+    pub poll_interval: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: None,
+            label: String::new(),
+            hide_while_others_play: false,
+            hide_when_idle: false,
+            format: Template::parse("{icon} {song} ({time})").unwrap(),
+            playing_icon: "▶".to_string(),
+            paused_icon: "⏸".to_string(),
+            stopped_icon: "⏹".to_string(),
+            repeat_icon: "🔁".to_string(),
+            random_icon: "🔀".to_string(),
+            single_icon: "1️⃣".to_string(),
+            consume_icon: "✂️".to_string(),
+            paused_color: None,
+            progress_width: 5,
+            progress_chars: "▰▱".to_string(),
+            max_length: 70,
+            truncate: Truncation::End,
+            marquee: false,
+            marquee_interval: 500,
+            seek_step: 5,
+            rating_sticker: "rating".to_string(),
+            rating_max: 10.0,
+            rating_stars: 5,
+            rating_chars: "★☆".to_string(),
+            poll_interval: 30,
+        }
+    }
+}
+
+/// Which of the bar's MPD blocks are playing, so they can make way for
+/// each other
+#[derive(Clone)]
+pub struct Players(Arc<watch::Sender<Vec<bool>>>);
+
+impl Default for Players {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(Vec::new()).0))
+    }
+}
+
+impl Players {
+    /// Add a block that isn't playing yet, returning its index
+    fn join(&self) -> usize {
+        let mut index = 0;
+        self.0.send_modify(|playing| {
+            index = playing.len();
+            playing.push(false);
+        });
+        index
+    }
+
+    fn set(&self, index: usize, is_playing: bool) {
+        self.0.send_if_modified(|playing| {
+            let changed = playing[index] != is_playing;
+            playing[index] = is_playing;
+            changed
+        });
+    }
+
+    fn others_playing(&self, index: usize) -> bool {
+        let playing = self.0.borrow();
+        playing.iter().enumerate().any(|(i, p)| *p && i != index)
+    }
+}
+
+pub async fn mpd_task(
+    config: Config,
+    players: Players,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let env_port = env::var("MPD_PORT").ok().and_then(|p| p.parse().ok());
+    let host = config.host.clone().or_else(|| env::var("MPD_HOST").ok());
+    let (host, password) = resolve_host(host, config.port.or(env_port));
+    let index = players.join();
+    let mut others = players.0.subscribe();
+    let mut backoff = Backoff::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(MPD_MAX_RETRY),
+    );
+    loop {
+        let (client, mut changes) = match sys.connect_mpd(&host, password.as_deref()).await {
+            Ok(ok) => ok,
+            Err(err) => {
+                eprintln!("Couldn't connect to {host}. {err}");
+                players.set(index, false);
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+
+        let poll_interval = Duration::from_secs(config.poll_interval.max(1));
+        let mut scroll = Scroll::default();
+        let mut listening = Listening::default();
+        'fetch: loop {
+            let Ok(now_playing) = NowPlaying::fetch(&client, &config).await else {
+                players.set(index, false);
+                tx.send(MPD_FALLBACK.into())?;
+                break;
+            };
+            // Only a server that answers counts as working, so one that hangs
+            // up straight after connecting isn't retried in a tight loop
+            backoff.reset();
+            loop {
+                let (output, playing) = match &now_playing {
+                    Some(now_playing) => now_playing.render(&config, &mut scroll),
+                    None => (MPD_FALLBACK.into(), false),
+                };
+                players.set(index, playing);
+                let song = now_playing.as_ref().filter(|_| playing);
+                listening.update(song.map(|n| n.current.id), &sys).await;
+                let idle = now_playing
+                    .as_ref()
+                    .is_none_or(|n| n.status.state == PlayState::Stopped);
+                let hidden = (config.hide_when_idle && idle)
+                    || (config.hide_while_others_play && !playing && players.others_playing(index));
+                if hidden {
+                    tx.send(BlockOutput::default())?;
+                } else {
+                    tx.send(output)?;
+                }
+                // Between changes only the elapsed time moves, and only while playing
+                let wait = Duration::from_millis(if playing && scroll.scrolling {
+                    config.marquee_interval.min(MPD_ELAPSED_FREQUENCY)
+                } else if playing {
+                    MPD_ELAPSED_FREQUENCY
+                } else {
+                    MPD_IDLE_FREQUENCY
+                });
+                let others = config.hide_while_others_play.then_some(&mut others);
+                match wait_for_change(&mut changes, &mut events, others, wait).await {
+                    Wake::Tick
+                        if now_playing
+                            .as_ref()
+                            .is_some_and(|n| n.fetched.elapsed() < poll_interval) => {}
+                    Wake::Tick | Wake::Update => continue 'fetch,
+                    Wake::Click(click) => {
+                        if let Err(err) = handle_click(&client, &config, click.button).await {
+                            eprintln!("Couldn't control MPD. {err}");
+                        }
+                        continue 'fetch;
+                    }
+                    Wake::Closed => {
+                        eprintln!("Lost connection to {host}");
+                        listening.update(None, &sys).await;
+                        players.set(index, false);
+                        tx.send(MPD_FALLBACK.into())?;
+                        break 'fetch;
+                    }
+                }
+            }
+        }
+        events.wait(backoff.fail()).await;
+    }
+}
+
+/// Where to connect, as a socket path, @abstract socket name, or host:port,
+/// and the password to send, if any. Like mpc, the password comes before
+/// the host with an @ between them.
+fn resolve_host(host: Option<String>, port: Option<u16>) -> (String, Option<String>) {
+    let Some(host) = host.filter(|h| !h.is_empty()) else {
+        let host = match port {
+            Some(port) => format!("localhost:{port}"),
+            None => MPD_DEFAULT_HOST.to_string(),
+        };
+        return (host, None);
+    };
+    // An @ at the start names an abstract socket rather than ending a password
+    let (password, host) = match host.split_once('@') {
+        Some((password, rest)) if !password.is_empty() => (Some(password.to_string()), rest),
+        _ => (None, host.as_str()),
+    };
+    if host.starts_with(['/', '@']) {
+        return (host.to_string(), password);
+    }
+    let port = port.unwrap_or(MPD_DEFAULT_PORT);
+    let host = match host.strip_prefix('[') {
+        // An IPv6 address with brackets, like [::1] or [::1]:6600
+        Some(address) if address.contains("]:") => host.to_string(),
+        Some(_) => format!("{host}:{port}"),
+        None => match host.matches(':').count() {
+            0 => format!("{host}:{port}"),
+            1 => host.to_string(),
+            // An IPv6 address without brackets can't have a port
+            _ => format!("[{host}]:{port}"),
+        },
+    };
+    (host, password)
+}
+
+enum Wake {
+    /// The timer ran out without anything changing
+    Tick,
+    Update,
+    Click(ClickEvent),
+    Closed,
+}
+
+/// Wait until something that affects the block changes, including another
+/// block starting or stopping if `others` is given, the timer runs out, or
+/// the block is clicked or refreshed
+async fn wait_for_change(
+    changes: &mut ConnectionEvents,
+    events: &mut Events,
+    mut others: Option<&mut watch::Receiver<Vec<bool>>>,
+    wait: Duration,
+) -> Wake {
+    let start = Instant::now();
+    let timer = events.wait(wait);
+    tokio::pin!(timer);
+    loop {
+        tokio::select! {
+            change = changes.next() => match change {
+                Some(ConnectionEvent::SubsystemChange(
+                    Subsystem::Player
+                    | Subsystem::Mixer
+                    | Subsystem::Options
+                    | Subsystem::Queue
+                    | Subsystem::Sticker,
+                )) => return Wake::Update,
+                Some(ConnectionEvent::SubsystemChange(_)) => {}
+                Some(ConnectionEvent::ConnectionClosed(_)) | None => return Wake::Closed,
+            },
+            click = &mut timer => match click {
+                Some(click) => return Wake::Click(click),
+                // Returning early without a click means the block was asked
+                // to refresh, or the bar was shown again
+                None if start.elapsed() < wait => return Wake::Update,
+                None => return Wake::Tick,
+            },
+            Some(Ok(())) = async { Some(others.as_mut()?.changed().await) } => {
+                return Wake::Update
+            }
+        }
+    }
+}
+
+/// Left click plays or pauses, right click skips to the next song, and
+/// scrolling seeks
+async fn handle_click(client: &Client, config: &Config, button: u8) -> Result<()> {
+    let step = Duration::from_secs(config.seek_step);
+    match button {
+        BUTTON_LEFT => match client.command(commands::Status).await?.state {
+            PlayState::Playing => client.command(commands::SetPause(true)).await?,
+            PlayState::Paused => client.command(commands::SetPause(false)).await?,
+            PlayState::Stopped => client.command(commands::Play::current()).await?,
+        },
+        BUTTON_RIGHT => client.command(commands::Next).await?,
+        BUTTON_SCROLL_UP => client.command(Seek(SeekMode::Forward(step))).await?,
+        BUTTON_SCROLL_DOWN => client.command(Seek(SeekMode::Backward(step))).await?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Counts time spent playing, and songs played, for `subar report`
+#[derive(Default)]
+struct Listening {
+    since: Option<Instant>,
+    song: Option<SongId>,
+}
+
+impl Listening {
+    /// Note the song that's playing, if any
+    async fn update(&mut self, playing: Option<SongId>, sys: &Sys) {
+        let save = Duration::from_secs(MPD_LISTENED_SAVE);
+        if let Some(since) = self.since {
+            if playing.is_none() || since.elapsed() >= save {
+                let listened = since.elapsed().as_secs_f64();
+                history::record(&MPD_LISTENED, listened, sys.now()).await;
+                self.since = None;
+            }
+        }
+        let Some(song) = playing else {
+            return;
+        };
+        self.since.get_or_insert_with(Instant::now);
+        // Pausing and carrying on is still the same song
+        if self.song != Some(song) {
+            history::record(&MPD_SONGS, 1.0, sys.now()).await;
+            self.song = Some(song);
+        }
+    }
+}
+
+/// How far a long song has scrolled
+#[derive(Default)]
+struct Scroll {
+    song: String,
+    offset: usize,
+    scrolling: bool,
+}
+
+impl Scroll {
+    /// The part of `song` to show, moving one step along each time the
+    /// same song is shown while playing
+    fn show(&mut self, song: String, config: &Config, playing: bool) -> String {
+        self.scrolling = config.marquee && song.graphemes(true).count() > config.max_length;
+        if !self.scrolling {
+            return truncate(&song, config.max_length);
+        }
+        if song != self.song {
+            self.song = song;
+            self.offset = 0;
+        } else if playing {
+            self.offset += 1;
+        }
+        marquee(&self.song, config.max_length, self.offset, MARQUEE_GAP)
+    }
+}
+
+/// The current song and MPD's status when they were last fetched
+struct NowPlaying {
+    current: SongInQueue,
+    status: Status,
+    audio: Option<AudioFormat>,
+    /// The song's rating sticker, if it has one and it's shown
+    rating: Option<f64>,
+    fetched: Instant,
+}
+
+impl NowPlaying {
+    /// None if there's no current song
+    async fn fetch(client: &Client, config: &Config) -> Result<Option<Self>> {
+        let Some(current) = client.command(commands::CurrentSong).await? else {
+            return Ok(None);
+        };
+        // The typed status leaves out the audio format
+        let frame = client.raw_command(commands::Status.command()).await?;
+        let audio = frame.find("audio").and_then(AudioFormat::parse);
+        let status = commands::Status.response(frame)?;
+        let mut rating = None;
+        if config.format.uses("rating") {
+            let sticker = commands::StickerGet::new(&current.song.url, &config.rating_sticker);
+            // MPD says there's no such sticker for songs without a rating
+            rating = match client.command(sticker).await {
+                Ok(sticker) => sticker.value.trim().parse().ok(),
+                Err(_) => None,
+            };
+        }
+        Ok(Some(Self {
+            current,
+            status,
+            audio,
+            rating,
+            fetched: Instant::now(),
+        }))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.status.duration.or(self.current.song.duration)
+    }
+
+    /// The elapsed time MPD reported, moved on by however long it's been
+    /// playing since
+    fn elapsed(&self) -> Option<Duration> {
+        let elapsed = self.status.elapsed?;
+        if self.status.state != PlayState::Playing {
+            return Some(elapsed);
+        }
+        let elapsed = elapsed + self.fetched.elapsed();
+        Some(match self.duration() {
+            Some(duration) if !duration.is_zero() => elapsed.min(duration),
+            _ => elapsed,
+        })
+    }
+
+    /// The block's output, and whether it's currently playing rather than
+    /// paused or stopped
+    fn render(&self, config: &Config, scroll: &mut Scroll) -> (BlockOutput, bool) {
+        let (current, status, audio) = (&self.current, &self.status, &self.audio);
+        let artists = current.song.artists();
+        let album_artist = current.song.album_artists();
+        let artists = if artists.is_empty() && !album_artist.is_empty() {
+            album_artist
+        } else {
+            artists
+        };
+        let file_name = file_name(&current.song.url);
+        let mut title = current.song.title().unwrap_or(file_name).to_string();
+        let mut artist = match artists.len() {
+            0 => "???".to_string(),
+            1 => artists[0].to_string(),
+            2 => artists.join(" & "),
+            _ => artists.join(", "),
+        };
+        // Radio streams are named after their station, and their title is
+        // what's on, which is often "Artist - Title"
+        let stream = current.song.url.contains("://");
+        let station = tag(&current.song, Tag::Name).filter(|_| stream);
+        let now_on = current.song.title().filter(|_| stream);
+        match now_on.and_then(|t| t.split_once(" - ")) {
+            Some((on_artist, on_title)) if artists.is_empty() => {
+                artist = on_artist.to_string();
+                title = on_title.to_string();
+            }
+            _ if stream && now_on.is_none() => {
+                title = station.clone().unwrap_or_else(|| current.song.url.clone());
+            }
+            _ => {}
+        }
+
+        let duration = self.duration();
+        let elapsed = self.elapsed();
+        let fraction = match (elapsed, duration) {
+            (Some(elapsed), Some(duration)) if !duration.is_zero() => {
+                elapsed.as_secs_f64() / duration.as_secs_f64()
+            }
+            _ => 0.0,
+        };
+        let elapsed = elapsed.map(format_duration);
+        let duration = duration.map(format_duration);
+        let playback_time = match (&elapsed, &duration) {
+            (Some(elapsed), Some(duration)) => format!("{elapsed}/{duration}"),
+            _ => "00:00".to_string(),
+        };
+
+        let position = status.current_song.map(|(position, _)| position.0 + 1);
+        let queue_length = status.playlist_length;
+
+        let single = status.single != SingleMode::Disabled;
+        let modes = [
+            (status.repeat, &config.repeat_icon),
+            (status.random, &config.random_icon),
+            (single, &config.single_icon),
+            (status.consume, &config.consume_icon),
+        ];
+        let mode = |on: bool, icon: &String| on.then(|| icon.clone());
+        let all_modes: String = modes
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, i)| i.as_str())
+            .collect();
+
+        let (icon, state) = match status.state {
+            PlayState::Playing => (&config.playing_icon, tr("state-playing", &[])),
+            PlayState::Paused => (&config.paused_icon, tr("state-paused", &[])),
+            PlayState::Stopped => (&config.stopped_icon, tr("state-stopped", &[])),
+        };
+        let is_playing = status.state == PlayState::Playing;
+        let song = if stream {
+            match (&station, now_on) {
+                (Some(station), Some(now_on)) => format!("{station}: {now_on}"),
+                (Some(station), None) => station.clone(),
+                (None, Some(now_on)) => now_on.to_string(),
+                (None, None) => current.song.url.clone(),
+            }
+        } else if artists.is_empty() && current.song.title().is_none() {
+            // Untagged files are shown by their name alone
+            title.clone()
+        } else if config.marquee {
+            format!("{artist} - {title}")
+        } else {
+            let unknown = ["???".to_string()];
+            let artists = if artists.is_empty() {
+                &unknown
+            } else {
+                artists
+            };
+            truncate_song(artists, &title, config.max_length, config.truncate)
+        };
+        let playing = scroll.show(song, config, is_playing);
+        let full_text = config.format.render(|name| match name {
+            "label" => Some(config.label.clone()).filter(|l| !l.is_empty()),
+            "icon" => Some(icon.clone()),
+            "state" => Some(state.clone()),
+            "song" => Some(playing.clone()),
+            "artist" => Some(artist.clone()),
+            "title" => Some(title.clone()),
+            "station" => station.clone(),
+            "rating" => self.rating.map(|rating| {
+                let fraction = rating / config.rating_max;
+                progress_bar(fraction, config.rating_stars, &config.rating_chars)
+            }),
+            "album" => current.song.album().map(str::to_string),
+            "track" => number_tag(&current.song, Tag::Track),
+            "disc" => number_tag(&current.song, Tag::Disc),
+            "date" => tag(&current.song, Tag::Date),
+            "genre" => tag(&current.song, Tag::Genre),
+            "time" => Some(playback_time.clone()),
+            "elapsed" => Some(elapsed.clone().unwrap_or_else(|| "00:00".to_string())),
+            "duration" => Some(duration.clone().unwrap_or_else(|| "00:00".to_string())),
+            "progress" => Some(progress_bar(
+                fraction,
+                config.progress_width,
+                &config.progress_chars,
+            )),
+            "percent" => Some(format!("{:.0}%", fraction * 100.0)),
+            "position" => position.map(|p| p.to_string()),
+            "queue_length" => Some(queue_length.to_string()),
+            "volume" => Some(status.volume.to_string()),
+            "repeat" => mode(status.repeat, &config.repeat_icon),
+            "random" => mode(status.random, &config.random_icon),
+            "single" => mode(single, &config.single_icon),
+            "consume" => mode(status.consume, &config.consume_icon),
+            "modes" => Some(all_modes.clone()),
+            "queue" => position.map(|p| format!("{p}/{queue_length}")),
+            "audio" => audio
+                .as_ref()
+                .map(|a| format!("{}/{}", a.sample_rate, a.bits)),
+            "sample_rate" => audio.as_ref().map(|a| a.sample_rate.clone()),
+            "bits" => audio.as_ref().map(|a| a.bits.clone()),
+            "channels" => audio.as_ref().map(|a| a.channels.clone()),
+            "bitrate" => status
+                .bitrate
+                .filter(|b| *b > 0)
+                .map(|b| format!("{b}kbps")),
+            _ => None,
+        });
+        let output = BlockOutput {
+            full_text,
+            color: config.paused_color.clone().filter(|_| !is_playing),
+            ..Default::default()
+        };
+        (output, is_playing)
+    }
+}
+
+/// What MPD is decoding, from its "audio" status field like "44100:16:2"
+struct AudioFormat {
+    /// e.g. "44.1kHz", or "dsd64" for DSD
+    sample_rate: String,
+    /// e.g. "16bit", or "float"
+    bits: String,
+    channels: String,
+}
+
+impl AudioFormat {
+    fn parse(audio: &str) -> Option<Self> {
+        let mut parts = audio.split(':');
+        let rate = parts.next()?;
+        let bits = parts.next()?;
+        let channels = parts.next()?.to_string();
+        let sample_rate = match rate.parse::<u32>() {
+            Ok(rate) => format!("{}kHz", f64::from(rate) / 1000.0),
+            Err(_) => rate.to_string(),
+        };
+        let bits = match bits {
+            "f" => "float".to_string(),
+            "dsd" => "1bit".to_string(),
+            bits => format!("{bits}bit"),
+        };
+        Some(Self {
+            sample_rate,
+            bits,
+            channels,
+        })
+    }
+}
+
+/// The last part of a song's path or URL, without its extension
+fn file_name(url: &str) -> &str {
+    let name = url.trim_end_matches('/').rsplit('/').next().unwrap_or(url);
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    }
+}
+
+fn tag(song: &Song, tag: Tag) -> Option<String> {
+    song.tags.get(&tag).map(|values| values.join(", "))
+}
+
+/// Track and disc numbers are sometimes tagged as e.g. "3/12"
+fn number_tag(song: &Song, tag: Tag) -> Option<String> {
+    let value = song.tags.get(&tag)?.first()?;
+    let number = value.split('/').next().unwrap_or(value).trim();
+    Some(number.trim_start_matches('0').to_string()).filter(|n| !n.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::{BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_UP};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_now_playing() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: song.flac\nArtist: Artist\nTitle: Title\nduration: 200.500\n",
+            ),
+            (
+                "status",
+                "volume: 45\nrepeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 65.200\nduration: 200.500\n",
+            ),
+        ]);
+        let config = r#"
+            [[block]]
+            block = "mpd"
+            format = "{icon} {song} ({time}) {progress} {percent} 🔈{volume}%"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"▶ Artist - Title (01:05/03:20) ▰▰▱▱▱ 33% 🔈45% ","name":"mpd""#
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_counts_elapsed_time_between_polls() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            ("currentsong", "file: song.flac\nduration: 200.500\n"),
+            (
+                "status",
+                "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 65.200\nduration: 200.500\n",
+            ),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{elapsed}\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"01:05 ","name":"mpd""#));
+
+        // MPD still says 65 seconds, but it's been playing for 10 more
+        sleep(Duration::from_secs(10)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"01:15 ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_marquee_scrolls_long_songs() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: song.flac\nArtist: Artist\nTitle: Title\nduration: 200.500\n",
+            ),
+            (
+                "status",
+                "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n",
+            ),
+        ]);
+        let config = r#"
+            [[block]]
+            block = "mpd"
+            format = "{song}"
+            max_length = 10
+            marquee = true
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Artist - T ","name":"mpd""#));
+
+        sleep(Duration::from_millis(500)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"rtist - Ti ","name":"mpd""#));
+
+        sleep(Duration::from_millis(4000)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Title   Ar ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_format_skips_missing_tags() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: song.flac\nTitle: Title\nAlbum: Album\nTrack: 03/12\nduration: 200.500\n",
+            ),
+            (
+                "status",
+                "repeat: 0\nrandom: 1\nsingle: 1\nconsume: 0\nplaylistlength: 27\nsong: 2\nsongid: 3\nstate: play\nelapsed: 1.000\nbitrate: 320\naudio: 44100:16:2\n",
+            ),
+        ]);
+        let config = r#"
+            [[block]]
+            block = "mpd"
+            format = "[{track}. ]{title}[ ({album})][ [[{date}]]] [[{queue}]][ {modes}][ {repeat}][ [[{audio}]]][ {bitrate}]"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"3. Title (Album) [3/27] 🔀1️⃣ [44.1kHz/16bit] 320kbps ","name":"mpd""#
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_names_untagged_songs_after_their_file() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: Downloads/rips/Live at the Hall.opus\nduration: 200.500\n",
+            ),
+            (
+                "status",
+                "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n",
+            ),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{song} | {title}\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line
+            .starts_with(r#"[{"full_text":"Live at the Hall | Live at the Hall ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_shows_radio_stations_and_what_is_on() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n";
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: https://radio.example/live.mp3\nName: Example FM\nTitle: Artist - Song\n",
+            ),
+            ("status", status),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{song} | {artist} | {title}\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"Example FM: Artist - Song | Artist | Song ","name":"mpd""#
+        ));
+
+        // Before the station says what's on
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: https://radio.example/live.mp3\nName: Example FM\n",
+            ),
+            ("status", status),
+        ]);
+        sleep(Duration::from_secs(5)).await;
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Example FM | ??? | Example FM ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_shows_rating_stickers_as_stars() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n";
+        mock.mpd(&[
+            ("currentsong", "file: song.flac\nTitle: Title\n"),
+            ("status", status),
+            ("sticker get song song.flac rating", "sticker: rating=7\n"),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{title}[ {rating}]\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Title ★★★★☆ ","name":"mpd""#));
+
+        // Unrated songs don't have the sticker
+        mock.mpd(&[
+            ("currentsong", "file: other.flac\nTitle: Other\n"),
+            ("status", status),
+        ]);
+        sleep(Duration::from_secs(5)).await;
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Other ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_can_shorten_artists_before_titles() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n";
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: song.flac\nArtist: Alpha Band\nArtist: Beta Crew\nArtist: Gamma\nTitle: Song Title\n",
+            ),
+            ("status", status),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{song}\"\nmax_length = 32\ntruncate = \"artist\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Alpha Band et al. - Song Title ","name":"mpd""#));
+
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: song.flac\nArtist: The Extraordinarily Long Artist Name\nTitle: Song Title\n",
+            ),
+            ("status", status),
+        ]);
+        sleep(Duration::from_secs(5)).await;
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(
+            line.starts_with(r#"[{"full_text":"The Extraordinaril… - Song Title ","name":"mpd""#)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_can_hide_while_idle() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = |state: &str| {
+            format!("repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: {state}\nelapsed: 1.000\n")
+        };
+        mock.mpd(&[("currentsong", ""), ("status", &status("stop"))]);
+        let config = "[[block]]\nblock = \"mpd\"\nformat = \"{title}\"\nhide_when_idle = true";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🗓️"#));
+
+        mock.mpd(&[
+            ("currentsong", "file: song.flac\nTitle: Title\n"),
+            ("status", &status("stop")),
+        ]);
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🗓️"#));
+
+        mock.mpd(&[
+            ("currentsong", "file: song.flac\nTitle: Title\n"),
+            ("status", &status("pause")),
+        ]);
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Title ","name":"mpd""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_clicks_control_playback() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd(&[
+            ("currentsong", "file: song.flac\nTitle: Title\n"),
+            (
+                "status",
+                "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\nelapsed: 1.000\n",
+            ),
+            ("pause 1", ""),
+            ("next", ""),
+            ("seekcur +5.000", ""),
+        ]);
+        let mut tasks = start("[[block]]\nblock = \"mpd\"", &mock);
+        render(&mut tasks, &mock).await;
+        for button in [BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_UP] {
+            click(&tasks[0], button, &[]).await;
+            render(&mut tasks, &mock).await;
+        }
+        let ran = mock.mpd_ran.lock().unwrap();
+        let controls: Vec<_> = ran
+            .iter()
+            .filter(|c| !c.contains("status") && *c != "currentsong")
+            .collect();
+        assert_eq!(controls, ["pause 1", "next", "seekcur +5.000"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_updates_on_idle_events() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: pause\nelapsed: 65.200\nduration: 200.500\n";
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: a.flac\nArtist: Artist\nTitle: First\n",
+            ),
+            ("status", status),
+        ]);
+        let config = "[[block]]\nblock = \"mpd\"\npaused_color = \"#888888\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r##"[{"full_text":"⏸ Artist - First (01:05/03:20) ","color":"#888888","##
+        ));
+
+        mock.mpd(&[
+            (
+                "currentsong",
+                "file: b.flac\nArtist: Artist\nTitle: Second\n",
+            ),
+            ("status", status),
+        ]);
+        sleep(Duration::from_secs(5)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.contains("First"), "paused songs only update on events");
+
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"⏸ Artist - Second (01:05/03:20) ","#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_backs_off_from_servers_that_hang_up() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.mpd_hang_up();
+        let mut tasks = start("[[block]]\nblock = \"mpd\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎵 ??? ","name":"mpd""#));
+        sleep(Duration::from_secs(10)).await;
+        // After 1, 2, and 4 seconds, give or take 20%
+        assert_eq!(mock.mpd_connections.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_hosts_are_read_like_mpc() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let status = "repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: play\n";
+        for (host, title) in [
+            ("media:6601", "One"),
+            ("[::1]:6600", "Two"),
+            ("@mpd", "Three"),
+        ] {
+            let song = format!("file: song.flac\nTitle: {title}\n");
+            let responses = [("currentsong", song.as_str()), ("status", status)];
+            let password = [("password secret", "")];
+            let responses: Vec<_> = match title {
+                "One" => responses.into_iter().chain(password).collect(),
+                _ => responses.to_vec(),
+            };
+            mock.mpd_at(host, &responses);
+        }
+        let config = r#"
+            [[block]]
+            block = "mpd"
+            host = "secret@media"
+            port = 6601
+            format = "{title}"
+            [[block]]
+            block = "mpd"
+            host = "::1"
+            format = "{title}"
+            [[block]]
+            block = "mpd"
+            host = "@mpd"
+            format = "{title}"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"One ","name":"mpd""#));
+        assert!(line.contains(r#"{"full_text":"Two ","name":"mpd""#));
+        assert!(line.contains(r#"{"full_text":"Three ","name":"mpd""#));
+        let ran = mock.mpd_ran.lock().unwrap();
+        assert!(ran.contains(&"password secret".to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mpd_servers_make_way_for_each_other() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let server = |state: &str| {
+            format!("repeat: 0\nrandom: 0\nsingle: 0\nconsume: 0\nstate: {state}\nelapsed: 1.000\n")
+        };
+        let song = "file: song.flac\nTitle: Title\nArtist: Artist\nduration: 200.000\n";
+        mock.mpd_at(
+            "/run/mpd/socket",
+            &[("currentsong", song), ("status", &server("pause"))],
+        );
+        mock.mpd_at(
+            "media:6600",
+            &[("currentsong", song), ("status", &server("play"))],
+        );
+        let config = r#"
+            [[block]]
+            block = "mpd"
+            label = "Home"
+            format = "{label}: {title}"
+            hide_while_others_play = true
+            [[block]]
+            block = "mpd"
+            host = "media:6600"
+            label = "Media"
+            format = "{label}: {title}"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Media: Title ","name":"mpd","instance":"1""#));
+
+        mock.mpd_at(
+            "media:6600",
+            &[("currentsong", song), ("status", &server("stop"))],
+        );
+        sleep(Duration::from_secs(5)).await;
+        mock.mpd_changed("player");
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Home: Title ","name":"mpd","instance":"0""#));
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+use zbus::fdo::DBusProxy;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedValue;
+use zbus::{proxy, Connection};
+
+use crate::backoff::Backoff;
+use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+use crate::events::Events;
+use crate::format::{truncate, truncate_song, Template, Truncation};
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static MPRIS_FALLBACK: &str = "🎧 ???";
+pub static MPRIS_UPDATE_FREQUENCY: u64 = 1000;
+static MPRIS_MAX_RETRY: u64 = 30 * 1000;
+static MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Players to prefer, by the end of their bus name, e.g. "spotify" or
+    /// "firefox". A playing player is always shown over a paused one, then
+    /// the first in this list, then any others.
+    pub players: Vec<String>,
+    /// Players never to show, named the same way
+    pub ignore: Vec<String>,
+    pub playing_icon: String,
+    pub paused_icon: String,
+    /// Placeholders: icon, state, player, song, artist, title, album
+    pub format: Template,
+    /// How many characters of `{song}` are shown
+    pub max_length: usize,
+    /// What's cut from songs longer than `max_length`: "end", or "artist" to
+    /// keep the title
+    pub truncate: Truncation,
+    /// Text colour while paused, e.g. "#888888" to dim the block
+    pub paused_color: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            players: Vec::new(),
+            ignore: Vec::new(),
+            playing_icon: "▶".to_string(),
+            paused_icon: "⏸".to_string(),
+            format: Template::parse("{icon} {song}").unwrap(),
+            max_length: 50,
+            truncate: Truncation::End,
+            paused_color: None,
+        }
+    }
+}
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    fn play_pause(&self) -> zbus::Result<()>;
+    fn next(&self) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+/// The player that's shown and what it's playing
+struct Active {
+    proxy: PlayerProxy<'static>,
+    /// The end of its bus name, e.g. "spotify"
+    player: String,
+    playing: bool,
+    metadata: HashMap<String, OwnedValue>,
+}
+
+pub async fn mpris_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_millis(MPRIS_UPDATE_FREQUENCY);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(MPRIS_MAX_RETRY));
+    loop {
+        let conn = match sys.session_bus().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                eprintln!("Couldn't connect to the session bus. {err:#}");
+                tx.send(MPRIS_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        loop {
+            let active = match active_player(&conn, &config).await {
+                Ok(active) => active,
+                Err(err) => {
+                    eprintln!("Couldn't get the MPRIS players. {err:#}");
+                    tx.send(MPRIS_FALLBACK.into())?;
+                    break;
+                }
+            };
+            backoff.reset();
+            let Some(active) = active else {
+                tx.send(BlockOutput::default())?;
+                events.wait(interval).await;
+                continue;
+            };
+            tx.send(render(&config, &active))?;
+
+            // Left click plays or pauses, right click skips
+            let click = events.wait(interval).await;
+            let result = match click.map(|c| c.button) {
+                Some(BUTTON_LEFT) => active.proxy.play_pause().await,
+                Some(BUTTON_RIGHT) => active.proxy.next().await,
+                _ => Ok(()),
+            };
+            if let Err(err) = result {
+                eprintln!("Couldn't control {}. {err}", active.player);
+            }
+        }
+        events.wait(backoff.fail()).await;
+    }
+}
+
+/// The best player to show, if any are playing or paused
+async fn active_player(conn: &Connection, config: &Config) -> Result<Option<Active>> {
+    let matches = |list: &[String], player: &str| {
+        list.iter()
+            .position(|p| player == p || player.starts_with(&format!("{p}.")))
+    };
+    let mut best: Option<((bool, usize), Active)> = None;
+    for name in DBusProxy::new(conn).await?.list_names().await? {
+        let Some(player) = name.as_str().strip_prefix(MPRIS_PREFIX) else {
+            continue;
+        };
+        if matches(&config.ignore, player).is_some() {
+            continue;
+        }
+        let proxy = PlayerProxy::builder(conn)
+            .destination(name.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        // Players can disappear at any time, so skip any that don't answer
+        let Ok(status) = proxy.playback_status().await else {
+            continue;
+        };
+        let playing = match status.as_str() {
+            "Playing" => true,
+            "Paused" => false,
+            _ => continue,
+        };
+        // Lower sorts first
+        let rank = (
+            !playing,
+            matches(&config.players, player).unwrap_or(config.players.len()),
+        );
+        if best.as_ref().is_some_and(|(best, _)| *best <= rank) {
+            continue;
+        }
+        let metadata = proxy.metadata().await.unwrap_or_default();
+        let active = Active {
+            proxy,
+            player: player.to_string(),
+            playing,
+            metadata,
+        };
+        best = Some((rank, active));
+    }
+    Ok(best.map(|(_, active)| active))
+}
+
+fn render(config: &Config, active: &Active) -> BlockOutput {
+    let values = |key: &str| {
+        let Some(value) = active.metadata.get(key) else {
+            return Vec::new();
+        };
+        let values = match value.downcast_ref::<&str>() {
+            Ok(text) => vec![text.to_string()],
+            // Artists are a list
+            Err(_) => value
+                .try_clone()
+                .ok()
+                .and_then(|value| Vec::<String>::try_from(value).ok())
+                .unwrap_or_default(),
+        };
+        values
+            .into_iter()
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>()
+    };
+    let text = |key: &str| Some(values(key).join(", ")).filter(|t| !t.is_empty());
+    let title = text("xesam:title");
+    let artists = values("xesam:artist");
+    let artist = Some(artists.join(", ")).filter(|a| !a.is_empty());
+    let song = match (&artist, &title) {
+        (Some(_), Some(title)) if config.truncate == Truncation::Artist => {
+            truncate_song(&artists, title, config.max_length, config.truncate)
+        }
+        (Some(artist), Some(title)) => format!("{artist} - {title}"),
+        (None, Some(title)) => title.clone(),
+        (Some(artist), None) => artist.clone(),
+        (None, None) => active.player.clone(),
+    };
+    let (icon, state) = if active.playing {
+        (&config.playing_icon, tr("state-playing", &[]))
+    } else {
+        (&config.paused_icon, tr("state-paused", &[]))
+    };
+    let full_text = config.format.render(|name| match name {
+        "icon" => Some(icon.clone()),
+        "state" => Some(state.clone()),
+        "player" => Some(active.player.clone()),
+        "song" => Some(truncate(&song, config.max_length)),
+        "artist" => artist.clone(),
+        "title" => title.clone(),
+        "album" => text("xesam:album"),
+        _ => None,
+    });
+    BlockOutput {
+        full_text,
+        color: config.paused_color.clone().filter(|_| !active.playing),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zbus::interface;
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+    use crate::system::mock::Mock;
+
+    /// Just enough of the bus daemon to list who's on the bus
+    struct Bus(Vec<&'static str>);
+
+    #[interface(name = "org.freedesktop.DBus")]
+    impl Bus {
+        fn list_names(&self) -> Vec<String> {
+            self.0.iter().map(|name| name.to_string()).collect()
+        }
+    }
+
+    struct Player {
+        playing: bool,
+        track: usize,
+    }
+
+    #[interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl Player {
+        fn play_pause(&mut self) {
+            self.playing = !self.playing;
+        }
+
+        fn next(&mut self) {
+            self.track += 1;
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            let status = if self.playing { "Playing" } else { "Paused" };
+            status.to_string()
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let artists = vec!["Alice".to_string(), "Bob".to_string()];
+            let title = format!("Song {}", self.track);
+            HashMap::from([
+                ("xesam:artist".to_string(), Value::from(artists)),
+                ("xesam:title".to_string(), Value::from(title)),
+            ])
+            .into_iter()
+            .map(|(key, value)| (key, value.try_into().unwrap()))
+            .collect()
+        }
+    }
+
+    /// Real time, since paused time would skip ahead while zbus waits on
+    /// the socket
+    #[tokio::test]
+    async fn players_are_shown_and_controlled() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let bus = mock.serve_session_bus().await;
+        let names = vec![
+            "org.freedesktop.DBus",
+            "org.mpris.MediaPlayer2.firefox.instance_1",
+            "org.mpris.MediaPlayer2.spotify",
+        ];
+        let server = bus.object_server();
+        server
+            .at("/org/freedesktop/DBus", Bus(names))
+            .await
+            .unwrap();
+        let player = Player {
+            playing: true,
+            track: 1,
+        };
+        server.at("/org/mpris/MediaPlayer2", player).await.unwrap();
+        let config = r##"
+            [[block]]
+            block = "mpris"
+            ignore = ["firefox"]
+            format = "{icon} {player}: {song}"
+            paused_color = "#888888"
+        "##;
+        let mut tasks = start(config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"▶ spotify: Alice, Bob - Song 1 ","name":"mpris""#));
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"⏸ spotify: Alice, Bob - Song 1 ","color":"#888888","name":"mpris""##
+        ));
+
+        click(&tasks[0], BUTTON_RIGHT, &[]).await;
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"⏸ spotify: Alice, Bob - Song 2 ","color":"#888888","name":"mpris""##
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_players_hides_the_block() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let bus = mock.serve_session_bus().await;
+        let server = bus.object_server();
+        server
+            .at("/org/freedesktop/DBus", Bus(vec!["org.freedesktop.DBus"]))
+            .await
+            .unwrap();
+        let mut tasks = start("[[block]]\nblock = \"mpris\"", &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🗓️"#));
+    }
+}
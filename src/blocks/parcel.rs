@@ -0,0 +1,287 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::Sys;
+
+pub static PARCEL_FALLBACK: &str = "📦 ???";
+static PARCEL_MAX_RETRY: u64 = 60 * 60 * 1000;
+static AFTERSHIP_URL: &str = "https://api.aftership.com/v4/trackings";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub provider: Provider,
+    #[serde(rename = "parcel", default)]
+    pub parcels: Vec<Parcel>,
+    /// Seconds between checks
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    /// Placeholders: icon, parcels
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// How each parcel is shown. Placeholders: label, status, message
+    #[serde(default = "default_parcel_format")]
+    pub parcel_format: Template,
+    /// Put between parcels
+    #[serde(default = "default_separator")]
+    pub separator: String,
+}
+
+fn default_interval() -> u64 {
+    30 * 60
+}
+
+fn default_icon() -> String {
+    "📦".to_string()
+}
+
+fn default_format() -> Template {
+    Template::parse("{icon} {parcels}").unwrap()
+}
+
+fn default_parcel_format() -> Template {
+    Template::parse("{label} {status}").unwrap()
+}
+
+fn default_separator() -> String {
+    ", ".to_string()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Parcel {
+    pub tracking_number: String,
+    /// The courier's identifier, e.g. "auspost" for AfterShip
+    #[serde(default)]
+    pub courier: String,
+    /// Shown instead of the tracking number
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Provider {
+    Aftership {
+        api_key: Secret,
+    },
+    /// A shell command run with the tracking number and courier as $1 and
+    /// $2. It should print an AfterShip style status such as InTransit or
+    /// Delivered, optionally followed by a line with more detail.
+    Command {
+        command: String,
+    },
+}
+
+struct Tracking {
+    tag: String,
+    message: String,
+}
+
+impl Tracking {
+    fn in_transit(&self) -> bool {
+        !matches!(self.tag.as_str(), "Delivered" | "Expired")
+    }
+
+    /// Something has gone wrong and needs attention
+    fn needs_attention(&self) -> bool {
+        matches!(self.tag.as_str(), "AttemptFail" | "Exception")
+    }
+
+    fn status(&self) -> String {
+        let id = match self.tag.as_str() {
+            "Pending" => "parcel-pending",
+            "InfoReceived" => "parcel-info-received",
+            "InTransit" => "parcel-in-transit",
+            "OutForDelivery" => "parcel-out-for-delivery",
+            "AttemptFail" => "parcel-attempt-fail",
+            "AvailableForPickup" => "parcel-available-for-pickup",
+            "Exception" => "parcel-exception",
+            tag => return tag.to_string(),
+        };
+        tr(id, &[])
+    }
+}
+
+pub async fn parcel_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_secs(config.interval);
+    let mut backoff = Backoff::new(interval, Duration::from_millis(PARCEL_MAX_RETRY));
+    loop {
+        let mut statuses = Vec::new();
+        let mut failed = false;
+        let mut urgent = false;
+        for parcel in &config.parcels {
+            match track(&sys, &config.provider, parcel).await {
+                Ok(tracking) if tracking.in_transit() => {
+                    urgent |= tracking.needs_attention();
+                    let label = parcel.label.as_ref().unwrap_or(&parcel.tracking_number);
+                    statuses.push(config.parcel_format.render(|name| match name {
+                        "label" => Some(label.clone()),
+                        "status" => Some(tracking.status()),
+                        "message" => Some(tracking.message.clone()),
+                        _ => None,
+                    }));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!("Couldn't track {}. {err:#}", parcel.tracking_number);
+                    failed = true;
+                }
+            }
+        }
+
+        let output = if failed && statuses.is_empty() {
+            PARCEL_FALLBACK.into()
+        } else if statuses.is_empty() {
+            BlockOutput::default()
+        } else {
+            let parcels = statuses.join(&config.separator);
+            let full_text = config.format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                "parcels" => Some(parcels.clone()),
+                _ => None,
+            });
+            BlockOutput {
+                full_text,
+                urgent,
+                ..Default::default()
+            }
+        };
+        tx.send(output)?;
+        let wait = if failed {
+            backoff.fail()
+        } else {
+            backoff.reset();
+            interval
+        };
+        events.wait(wait).await;
+    }
+}
+
+async fn track(sys: &Sys, provider: &Provider, parcel: &Parcel) -> Result<Tracking> {
+    match provider {
+        Provider::Aftership { api_key } => {
+            let url = format!(
+                "{AFTERSHIP_URL}/{}/{}",
+                parcel.courier, parcel.tracking_number
+            );
+            let api_key = api_key.resolve(sys).await?;
+            let headers = vec![("aftership-api-key".to_string(), api_key)];
+            let body = sys.http_get(&url, headers).await?;
+            let json: Value = serde_json::from_str(&body)?;
+            let tracking = json
+                .pointer("/data/tracking")
+                .context("Response has no tracking data")?;
+            let tag = tracking["tag"].as_str().unwrap_or("Pending").to_string();
+            let latest = tracking["checkpoints"]
+                .as_array()
+                .and_then(|c| c.last())
+                .and_then(|c| c["message"].as_str());
+            let message = latest
+                .or(tracking["subtag_message"].as_str())
+                .unwrap_or_default()
+                .to_string();
+            Ok(Tracking { tag, message })
+        }
+        Provider::Command { command } => {
+            let mut sh = Command::new("sh");
+            sh.arg("-c")
+                .arg(command)
+                .arg("sh")
+                .arg(&parcel.tracking_number)
+                .arg(&parcel.courier);
+            let output = sys.output(&mut sh).await?;
+            if !output.status.success() {
+                bail!("`{command}` failed with {}", output.status);
+            }
+            let stdout = String::from_utf8(output.stdout)?;
+            let mut lines = stdout.lines().map(str::trim);
+            let tag = lines.next().unwrap_or_default().to_string();
+            let message = lines.next().unwrap_or_default().to_string();
+            Ok(Tracking { tag, message })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn aftership_parcels_in_transit() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://api.aftership.com/v4/trackings/auspost/AP123",
+            r#"{"data": {"tracking": {"tag": "InTransit", "checkpoints": [
+                {"message": "Picked up"}, {"message": "Arrived at facility"}
+            ]}}}"#,
+        );
+        mock.url(
+            "https://api.aftership.com/v4/trackings/auspost/AP456",
+            r#"{"data": {"tracking": {"tag": "Delivered", "checkpoints": []}}}"#,
+        );
+        let config = r#"
+            [[block]]
+            block = "parcel"
+            provider = { type = "aftership", api_key = "key" }
+            parcel_format = "{label} {status}: {message}"
+            [[block.parcel]]
+            tracking_number = "AP123"
+            courier = "auspost"
+            label = "Books"
+            [[block.parcel]]
+            tracking_number = "AP456"
+            courier = "auspost"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(
+            r#"[{"full_text":"📦 Books in transit: Arrived at facility ","name":"parcel""#
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn command_parcels_needing_attention_are_urgent() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c track sh 123 ", 0, "AttemptFail\nNobody home\n");
+        mock.command("sh -c track sh 456 ", 0, "Delivered\n");
+        let config = r#"
+            [[block]]
+            block = "parcel"
+            provider = { type = "command", command = "track" }
+            [[block.parcel]]
+            tracking_number = "123"
+            [[block.parcel]]
+            tracking_number = "456"
+
+            [[block]]
+            block = "parcel"
+            provider = { type = "command", command = "track" }
+            [[block.parcel]]
+            tracking_number = "789"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(concat!(
+            r#"[{"full_text":"📦 123 delivery failed ","urgent":true,"name":"parcel","instance":"0","separator":false,"separator_block_width":0},"#,
+            r#"{"full_text":"📦 ??? ","name":"parcel","instance":"1""#,
+        )));
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::click::{self, BUTTON_LEFT, BUTTON_MIDDLE, BUTTON_RIGHT};
+use crate::events::{BlockEvent, Events};
+use crate::format::{format_duration, Template};
+use crate::history::{self, POMODOROS};
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static POMODORO_FALLBACK: &str = "🍅 ???";
+/// How long it waits while nothing is counting down
+static POMODORO_IDLE_WAIT: u64 = 60 * 60;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How long each phase lasts, in minutes
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    /// How many work phases come before a long break
+    pub long_break_every: u32,
+    /// Start the next phase as soon as one ends, rather than on a click
+    pub auto_start: bool,
+    pub work_icon: String,
+    pub break_icon: String,
+    pub paused_icon: String,
+    /// Placeholders: icon, phase, remaining, count (work phases finished)
+    pub format: Template,
+    /// Shown before it's started. Placeholders: icon, count
+    pub idle_format: Template,
+    /// A shell command run when a phase ends, like
+    /// `notify-send "{phase} is over"`. Placeholders: phase, next
+    pub on_phase_end: Option<Template>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            long_break_every: 4,
+            auto_start: false,
+            work_icon: "🍅".to_string(),
+            break_icon: "☕".to_string(),
+            paused_icon: "⏸️".to_string(),
+            format: Template::parse("{icon} {phase} {remaining}").unwrap(),
+            idle_format: Template::parse("{icon}").unwrap(),
+            on_phase_end: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    fn name(self) -> String {
+        match self {
+            Phase::Work => tr("pomodoro-work", &[]),
+            Phase::ShortBreak => tr("pomodoro-short-break", &[]),
+            Phase::LongBreak => tr("pomodoro-long-break", &[]),
+        }
+    }
+
+    fn length(self, config: &Config) -> Duration {
+        let minutes = match self {
+            Phase::Work => config.work,
+            Phase::ShortBreak => config.short_break,
+            Phase::LongBreak => config.long_break,
+        };
+        Duration::from_secs(minutes * 60)
+    }
+}
+
+#[derive(Default)]
+struct Pomodoro {
+    /// None until it's first started
+    phase: Option<Phase>,
+    /// When the phase ends, while it's running
+    ends: Option<DateTime<Local>>,
+    /// What's left of the phase while it's paused
+    left: Duration,
+    /// Work phases finished since it was started
+    count: u32,
+    /// A phase has ended and the next is waiting to be started
+    waiting: bool,
+}
+
+impl Pomodoro {
+    fn start(&mut self, now: DateTime<Local>, config: &Config) {
+        if self.phase.is_none() {
+            self.phase = Some(Phase::Work);
+            self.left = Phase::Work.length(config);
+        }
+        if self.ends.is_none() {
+            self.ends = Some(
+                now + chrono::Duration::from_std(self.left).unwrap_or(chrono::Duration::zero()),
+            );
+            self.waiting = false;
+        }
+    }
+
+    fn pause(&mut self, now: DateTime<Local>) {
+        if let Some(ends) = self.ends.take() {
+            self.left = (ends - now).to_std().unwrap_or_default();
+        }
+    }
+
+    /// Go on to the phase after this one, which starts straight away if
+    /// `running`
+    fn next(&mut self, running: bool, now: DateTime<Local>, config: &Config) -> Phase {
+        let next = match self.phase {
+            Some(Phase::Work)
+                if self.count > 0 && self.count.is_multiple_of(config.long_break_every) =>
+            {
+                Phase::LongBreak
+            }
+            Some(Phase::Work) => Phase::ShortBreak,
+            _ => Phase::Work,
+        };
+        self.phase = Some(next);
+        self.left = next.length(config);
+        self.ends = None;
+        if running {
+            self.start(now, config);
+        }
+        next
+    }
+}
+
+/// Count down work and break phases. A left click starts or pauses it, a
+/// right click skips to the next phase, and a middle click stops it. The
+/// same can be done with `subar ctl pomodoro toggle`, `skip`, or `stop`.
+pub async fn pomodoro_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut pomodoro = Pomodoro::default();
+    loop {
+        let now = sys.now();
+        if let (Some(phase), Some(ends)) = (pomodoro.phase, pomodoro.ends) {
+            if ends <= now {
+                if phase == Phase::Work {
+                    pomodoro.count += 1;
+                    history::record(&POMODOROS, 1.0, now).await;
+                }
+                let next = pomodoro.next(config.auto_start, ends, &config);
+                pomodoro.waiting = !config.auto_start;
+                if let Some(command) = &config.on_phase_end {
+                    let command = command.render(|name| match name {
+                        "phase" => Some(phase.name()),
+                        "next" => Some(next.name()),
+                        _ => None,
+                    });
+                    tokio::spawn(click::run(sys.clone(), command));
+                }
+                continue;
+            }
+        }
+        let count = pomodoro.count.to_string();
+        let (output, wait) = match pomodoro.phase {
+            None => {
+                let full_text = config.idle_format.render(|name| match name {
+                    "icon" => Some(config.work_icon.clone()),
+                    "count" => Some(count.clone()),
+                    _ => None,
+                });
+                (full_text.into(), Duration::from_secs(POMODORO_IDLE_WAIT))
+            }
+            Some(phase) => {
+                let left = match pomodoro.ends {
+                    Some(ends) => (ends - now).to_std().unwrap_or_default(),
+                    None => pomodoro.left,
+                };
+                let icon = match (pomodoro.ends, phase) {
+                    (None, _) => &config.paused_icon,
+                    (Some(_), Phase::Work) => &config.work_icon,
+                    (Some(_), _) => &config.break_icon,
+                };
+                // Rounded up, so it shows zero just as the phase ends
+                let shown = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+                let full_text = config.format.render(|name| match name {
+                    "icon" => Some(icon.clone()),
+                    "phase" => Some(phase.name()),
+                    "remaining" => Some(format_duration(Duration::from_secs(shown))),
+                    "count" => Some(count.clone()),
+                    _ => None,
+                });
+                let wait = match (pomodoro.ends, left.subsec_nanos()) {
+                    (None, _) => Duration::from_secs(POMODORO_IDLE_WAIT),
+                    (Some(_), 0) => Duration::from_secs(1),
+                    (Some(_), nanos) => Duration::from_nanos(nanos.into()),
+                };
+                let output = BlockOutput {
+                    full_text,
+                    urgent: pomodoro.waiting,
+                    ..BlockOutput::default()
+                };
+                (output, wait)
+            }
+        };
+        tx.send(output)?;
+        let action = match events.next(wait).await {
+            Some(BlockEvent::Click(click)) => match click.button {
+                BUTTON_LEFT => "toggle",
+                BUTTON_RIGHT => "skip",
+                BUTTON_MIDDLE => "stop",
+                _ => continue,
+            }
+            .to_string(),
+            Some(BlockEvent::Command(command)) => command.trim().to_string(),
+            _ => continue,
+        };
+        let now = sys.now();
+        match action.as_str() {
+            "toggle" if pomodoro.ends.is_some() => pomodoro.pause(now),
+            "toggle" | "start" => pomodoro.start(now, &config),
+            "pause" => pomodoro.pause(now),
+            "skip" => {
+                let running = pomodoro.ends.is_some() || pomodoro.phase.is_none();
+                pomodoro.next(running, now, &config);
+                pomodoro.waiting = false;
+            }
+            "stop" => pomodoro = Pomodoro::default(),
+            action => eprintln!("The pomodoro block can't {action:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn pomodoro_goes_from_work_to_a_break() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c notify-send 'work is over'", 0, "");
+        let config = r#"
+            [[block]]
+            block = "pomodoro"
+            work = 1
+            on_phase_end = "notify-send '{phase} is over'"
+        "#;
+        let mut tasks = start(config, &mock);
+        let text = |line: String| line.split('"').nth(3).unwrap().to_string();
+        assert_eq!(text(render(&mut tasks, &mock).await), "🍅 ");
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "🍅 work 01:00 ");
+        sleep(Duration::from_secs(60)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"⏸️ short break 05:00 ","urgent":true"#));
+        assert!(mock
+            .ran
+            .lock()
+            .unwrap()
+            .contains(&"sh -c notify-send 'work is over'".to_string()));
+        click(&tasks[0], BUTTON_RIGHT, &[]).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "⏸️ work 01:00 ");
+    }
+}
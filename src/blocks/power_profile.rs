@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::pin::Pin;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+use zbus::export::futures_core::Stream;
+use zbus::proxy;
+use zbus::zvariant::OwnedValue;
+
+use crate::backoff::Backoff;
+use crate::click::{BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_UP};
+use crate::events::{BlockEvent, Events};
+use crate::format::Template;
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static POWER_PROFILE_FALLBACK: &str = "⚖️ ???";
+/// How often it checks the profile, in case a change notification is missed
+pub static POWER_PROFILE_UPDATE_FREQUENCY: u64 = 60 * 1000;
+static POWER_PROFILE_MAX_RETRY: u64 = 5 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Icons for each profile by its name, like "power-saver"
+    pub icons: HashMap<String, String>,
+    /// Placeholders: icon, profile
+    pub format: Template,
+    /// Which profiles clicking cycles through, in order. Every profile the
+    /// daemon offers is used if this is empty.
+    pub profiles: Vec<String>,
+    /// Colours for each profile by its name
+    pub colors: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let icons = [
+            ("power-saver", "🍃"),
+            ("balanced", "⚖️"),
+            ("performance", "🚀"),
+        ];
+        Self {
+            icons: icons
+                .into_iter()
+                .map(|(profile, icon)| (profile.to_string(), icon.to_string()))
+                .collect(),
+            format: Template::parse("{icon}").unwrap(),
+            profiles: Vec::new(),
+            colors: HashMap::new(),
+        }
+    }
+}
+
+#[proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+/// Show the active power-profiles-daemon profile. A left click or scrolling
+/// up switches to the next profile, and a right click or scrolling down to the
+/// previous one. `subar ctl power_profile <profile>` switches to a profile by
+/// name, or `next` and `previous` cycle like clicking.
+pub async fn power_profile_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let interval = Duration::from_millis(POWER_PROFILE_UPDATE_FREQUENCY);
+    let mut backoff = Backoff::new(
+        Duration::from_secs(1),
+        Duration::from_millis(POWER_PROFILE_MAX_RETRY),
+    );
+    loop {
+        let proxy = match connect(&sys).await {
+            Ok(proxy) => proxy,
+            Err(err) => {
+                eprintln!("Couldn't connect to power-profiles-daemon. {err}");
+                tx.send(POWER_PROFILE_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        let mut changes = proxy.receive_active_profile_changed().await;
+        loop {
+            let (active, profiles) = match read(&proxy, &config).await {
+                Ok(read) => read,
+                Err(err) => {
+                    eprintln!("Couldn't get the power profile. {err}");
+                    tx.send(POWER_PROFILE_FALLBACK.into())?;
+                    break;
+                }
+            };
+            backoff.reset();
+            tx.send(render(&config, &active))?;
+            let changed = poll_fn(|cx| Pin::new(&mut changes).poll_next(cx));
+            let event = tokio::select! {
+                event = events.next(interval) => event,
+                _ = changed => continue,
+            };
+            let action = match event {
+                Some(BlockEvent::Click(click)) => match click.button {
+                    BUTTON_LEFT | BUTTON_SCROLL_UP => "next",
+                    BUTTON_RIGHT | BUTTON_SCROLL_DOWN => "previous",
+                    _ => continue,
+                }
+                .to_string(),
+                Some(BlockEvent::Command(command)) => command.trim().to_string(),
+                _ => continue,
+            };
+            let current = profiles.iter().position(|p| *p == active);
+            let target = match (action.as_str(), current) {
+                ("next" | "previous", _) if profiles.is_empty() => continue,
+                ("next", Some(i)) => &profiles[(i + 1) % profiles.len()],
+                ("previous", Some(i)) => &profiles[(i + profiles.len() - 1) % profiles.len()],
+                ("next" | "previous", None) => &profiles[0],
+                (profile, _) => profile,
+            };
+            if let Err(err) = proxy.set_active_profile(target).await {
+                eprintln!("Couldn't switch to the {target} power profile. {err}");
+            }
+        }
+        events.wait(backoff.fail()).await;
+    }
+}
+
+async fn connect(sys: &Sys) -> Result<PowerProfilesProxy<'static>> {
+    let conn = sys.system_bus().await?;
+    Ok(PowerProfilesProxy::new(&conn).await?)
+}
+
+/// The active profile and the ones to cycle through
+async fn read(proxy: &PowerProfilesProxy<'_>, config: &Config) -> Result<(String, Vec<String>)> {
+    let active = proxy.active_profile().await?;
+    let profiles = if config.profiles.is_empty() {
+        proxy
+            .profiles()
+            .await?
+            .iter()
+            .filter_map(|profile| profile.get("Profile")?.downcast_ref::<String>().ok())
+            .collect()
+    } else {
+        config.profiles.clone()
+    };
+    Ok((active, profiles))
+}
+
+fn render(config: &Config, active: &str) -> BlockOutput {
+    let name = match active {
+        "power-saver" => tr("power-profile-power-saver", &[]),
+        "balanced" => tr("power-profile-balanced", &[]),
+        "performance" => tr("power-profile-performance", &[]),
+        other => other.to_string(),
+    };
+    let full_text = config.format.render(|placeholder| match placeholder {
+        "icon" => config.icons.get(active).cloned(),
+        "profile" => Some(name.clone()),
+        _ => None,
+    });
+    BlockOutput {
+        full_text,
+        color: config.colors.get(active).cloned(),
+        ..BlockOutput::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zbus::interface;
+    use zbus::zvariant::{OwnedValue, Str};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+    use crate::events::BlockEvent;
+    use crate::system::mock::Mock;
+
+    struct Daemon {
+        active: String,
+    }
+
+    #[interface(name = "net.hadess.PowerProfiles")]
+    impl Daemon {
+        #[zbus(property)]
+        fn active_profile(&self) -> String {
+            self.active.clone()
+        }
+
+        #[zbus(property)]
+        fn set_active_profile(&mut self, profile: String) {
+            self.active = profile;
+        }
+
+        #[zbus(property)]
+        fn profiles(&self) -> Vec<HashMap<String, OwnedValue>> {
+            ["power-saver", "balanced", "performance"]
+                .into_iter()
+                .map(|profile| {
+                    let profile = OwnedValue::from(Str::from(profile));
+                    HashMap::from([("Profile".to_string(), profile)])
+                })
+                .collect()
+        }
+    }
+
+    /// Real time, since paused time would skip ahead while zbus waits on
+    /// the socket
+    #[tokio::test]
+    async fn profiles_are_shown_and_switched() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let bus = mock.serve_system_bus().await;
+        let server = bus.object_server();
+        let daemon = Daemon {
+            active: "balanced".to_string(),
+        };
+        server
+            .at("/net/hadess/PowerProfiles", daemon)
+            .await
+            .unwrap();
+        let config = r##"
+            [[block]]
+            block = "power_profile"
+            format = "{icon} {profile}"
+            colors = { performance = "#ff8800" }
+        "##;
+        let mut tasks = start(config, &mock);
+        let text = |line: String| line.split('"').nth(3).unwrap().to_string();
+        assert_eq!(text(render(&mut tasks, &mock).await), "⚖️ balanced ");
+
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"🚀 performance ","color":"#ff8800","name":"power_profile""##
+        ));
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "🍃 power saver ");
+        click(&tasks[0], BUTTON_RIGHT, &[]).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "🚀 performance ");
+        let command = BlockEvent::Command("balanced".to_string());
+        tasks[0].events.send(command).await.unwrap();
+        assert_eq!(text(render(&mut tasks, &mock).await), "⚖️ balanced ");
+
+        // Switching it elsewhere is shown straight away
+        let daemon = server
+            .interface::<_, Daemon>("/net/hadess/PowerProfiles")
+            .await
+            .unwrap();
+        let mut profiles = daemon.get_mut().await;
+        profiles.active = "power-saver".to_string();
+        let emitter = daemon.signal_emitter();
+        profiles.active_profile_changed(emitter).await.unwrap();
+        drop(profiles);
+        assert_eq!(text(render(&mut tasks, &mock).await), "🍃 power saver ");
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use chrono::{DateTime, Days, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Deserializer};
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::{format_countdown, Template};
+use crate::notify::notify;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static SCHEDULE_FALLBACK: &str = "⏰ ???";
+pub static SCHEDULE_UPDATE_FREQUENCY: u64 = 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "event")]
+    pub events: Vec<Event>,
+    /// Send a notification when an event arrives
+    pub notify: bool,
+    pub icon: String,
+    /// Placeholders: icon, label, time, countdown
+    pub format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            notify: true,
+            icon: "⏰".to_string(),
+            format: Template::parse("{icon} {label} in {countdown}").unwrap(),
+        }
+    }
+}
+
+/// Something that happens at the same time every day
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: NaiveTime,
+    pub label: String,
+}
+
+fn deserialize_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(&s, "%H:%M:%S"))
+        .map_err(|_| serde::de::Error::custom(format!("Invalid time {s:?}, expected HH:MM")))
+}
+
+pub async fn schedule_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut upcoming = next_event(&config.events, sys.now());
+    loop {
+        let now = sys.now();
+        if let Some((at, event)) = upcoming {
+            if now >= at {
+                if config.notify {
+                    notify(&sys, &event.label, &at.format("%H:%M").to_string()).await;
+                }
+                upcoming = next_event(&config.events, now);
+            }
+        }
+
+        let status = match upcoming {
+            Some((at, event)) => {
+                let remaining = (at - now).to_std().unwrap_or_default();
+                config.format.render(|name| match name {
+                    "icon" => Some(config.icon.clone()),
+                    "label" => Some(event.label.clone()),
+                    "time" => Some(event.time.format("%H:%M").to_string()),
+                    "countdown" => Some(format_countdown(remaining)),
+                    _ => None,
+                })
+            }
+            None => String::new(),
+        };
+        tx.send(status.into())?;
+        events
+            .wait(Duration::from_millis(SCHEDULE_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// The next event to happen after `now`, and when it'll happen
+fn next_event(events: &[Event], now: DateTime<Local>) -> Option<(DateTime<Local>, &Event)> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let today = now.date_naive();
+            let tomorrow = today.checked_add_days(Days::new(1))?;
+            [today, tomorrow]
+                .into_iter()
+                .filter_map(|date| {
+                    Local
+                        .from_local_datetime(&date.and_time(event.time))
+                        .earliest()
+                })
+                .find(|at| *at > now)
+                .map(|at| (at, event))
+        })
+        .min_by_key(|(at, _)| *at)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn schedule_counts_down_and_notifies() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("notify-send --app-name=subar Standup 10:00", 0, "");
+        let config = r#"
+            [[block]]
+            block = "schedule"
+            [[block.event]]
+            time = "10:00"
+            label = "Standup"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"⏰ Standup in 30m ","#));
+
+        sleep(Duration::from_secs(30 * 60)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"⏰ Standup in 24h 00m ","#));
+        let ran = mock.ran.lock().unwrap();
+        assert!(ran.contains(&"notify-send --app-name=subar Standup 10:00".to_string()));
+    }
+}
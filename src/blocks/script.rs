@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::task::spawn_blocking;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::format_duration;
+#[cfg(feature = "http")]
+use crate::http;
+use crate::output::BlockOutput;
+
+pub static SCRIPT_FALLBACK: &str = "📜 ???";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// A Rhai script whose final expression is the block's text
+    pub path: PathBuf,
+    /// Milliseconds between runs of the script
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    1000
+}
+
+pub async fn script_task(
+    config: Config,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let engine = Arc::new(engine());
+    let interval = Duration::from_millis(config.interval);
+    let ast = loop {
+        match engine.compile_file(config.path.clone()) {
+            Ok(ast) => break Arc::new(ast),
+            Err(err) => {
+                eprintln!("Couldn't compile {}. {err}", config.path.display());
+                events.wait(interval).await;
+            }
+        }
+    };
+
+    // The scope is kept between runs so scripts can hold on to state
+    let mut scope = Scope::new();
+    loop {
+        let (engine, ast) = (engine.clone(), ast.clone());
+        let (result, returned_scope) = spawn_blocking(move || {
+            let result = run(&engine, &ast, &mut scope);
+            (result, scope)
+        })
+        .await?;
+        scope = returned_scope;
+        match result {
+            Ok(text) => tx.send(text.into())?,
+            Err(err) => {
+                eprintln!("Error running {}. {err}", config.path.display());
+                tx.send(SCRIPT_FALLBACK.into())?;
+            }
+        }
+        events.wait(interval).await;
+    }
+}
+
+fn run(engine: &Engine, ast: &AST, scope: &mut Scope<'static>) -> Result<String> {
+    let output: Dynamic = engine.eval_ast_with_scope(scope, ast)?;
+    if output.is_unit() {
+        Ok(String::new())
+    } else {
+        Ok(output.to_string())
+    }
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_fn(
+            "read_file",
+            |path: &str| -> Result<String, Box<EvalAltResult>> {
+                fs::read_to_string(path)
+                    .map_err(|err| format!("Couldn't read {path}. {err}").into())
+            },
+        )
+        .register_fn("format_duration", |secs: i64| {
+            format_duration(Duration::from_secs(secs.max(0) as u64))
+        })
+        .register_fn("format_duration", |secs: f64| {
+            format_duration(Duration::from_secs_f64(secs.max(0.0)))
+        });
+    #[cfg(feature = "http")]
+    engine.register_fn(
+        "http_get",
+        |url: &str| -> Result<String, Box<EvalAltResult>> {
+            http::get(url).map_err(|err| format!("{err:#}").into())
+        },
+    );
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::events::BlockEvent;
+    use crate::system::mock::Mock;
+
+    /// Real time, since paused time would skip ahead while the script runs
+    /// on a blocking thread
+    #[tokio::test]
+    async fn scripts_keep_their_variables_between_runs() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let dir = Fixture::new();
+        dir.write("word", "hello");
+        let script = format!(
+            r#"
+            let runs = if is_def_var("runs") {{ runs + 1 }} else {{ 1 }};
+            let word = read_file({:?});
+            if word == "hide" {{
+            }} else if word == "fail" {{
+                throw "failed";
+            }} else {{
+                `${{word}} ${{format_duration(90)}} #${{runs}}`
+            }}
+            "#,
+            dir.path("word")
+        );
+        dir.write("block.rhai", script);
+        let config = format!(
+            "[[block]]\nblock = \"script\"\npath = {:?}\ninterval = 3600000",
+            dir.path("block.rhai")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"hello 01:30 #1 ","name":"script""#));
+
+        for (word, line) in [
+            (
+                "again",
+                r#"[{"full_text":"again 01:30 #2 ","name":"script""#,
+            ),
+            ("fail", r#"[{"full_text":"📜 ??? ","name":"script""#),
+            ("hide", r#"[{"full_text":"🗓️"#),
+            ("back", r#"[{"full_text":"back 01:30 #5 ","name":"script""#),
+        ] {
+            dir.write("word", word);
+            tasks[0].events.send(BlockEvent::Refresh).await.unwrap();
+            let rendered = render(&mut tasks, &mock).await;
+            assert!(rendered.starts_with(line), "{word}: {rendered}");
+        }
+    }
+}
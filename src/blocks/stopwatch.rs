@@ -0,0 +1,130 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::click::{BUTTON_LEFT, BUTTON_RIGHT};
+use crate::events::{BlockEvent, Events};
+use crate::format::{format_hms, Template};
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static STOPWATCH_FALLBACK: &str = "⏱️ ???";
+/// How long it waits while it isn't running
+static STOPWATCH_IDLE_WAIT: u64 = 60 * 60;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub icon: String,
+    pub paused_icon: String,
+    /// Placeholders: icon, elapsed
+    pub format: Template,
+    /// Shown before it's started or after it's reset. Placeholders: icon
+    pub idle_format: Template,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            icon: "⏱️".to_string(),
+            paused_icon: "⏸️".to_string(),
+            format: Template::parse("{icon} {elapsed}").unwrap(),
+            idle_format: Template::parse("{icon}").unwrap(),
+        }
+    }
+}
+
+/// Count up from a left click or `subar ctl stopwatch toggle`, which also
+/// pause it. A right click or `subar ctl stopwatch reset` sets it back to
+/// zero.
+pub async fn stopwatch_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    // The time counted before it was last paused
+    let mut counted = Duration::ZERO;
+    let mut started: Option<DateTime<Local>> = None;
+    loop {
+        let now = sys.now();
+        let elapsed = counted
+            + started
+                .and_then(|started| (now - started).to_std().ok())
+                .unwrap_or_default();
+        let icon = match started {
+            Some(_) => &config.icon,
+            None => &config.paused_icon,
+        };
+        let full_text = if started.is_none() && counted.is_zero() {
+            config.idle_format.render(|name| match name {
+                "icon" => Some(config.icon.clone()),
+                _ => None,
+            })
+        } else {
+            config.format.render(|name| match name {
+                "icon" => Some(icon.clone()),
+                "elapsed" => Some(format_hms(elapsed)),
+                _ => None,
+            })
+        };
+        tx.send(full_text.into())?;
+        let wait = match started {
+            Some(_) => Duration::from_secs(1) - Duration::from_nanos(elapsed.subsec_nanos().into()),
+            None => Duration::from_secs(STOPWATCH_IDLE_WAIT),
+        };
+        let action = match events.next(wait).await {
+            Some(BlockEvent::Click(click)) => match click.button {
+                BUTTON_LEFT => "toggle",
+                BUTTON_RIGHT => "reset",
+                _ => continue,
+            }
+            .to_string(),
+            Some(BlockEvent::Command(command)) => command.trim().to_string(),
+            _ => continue,
+        };
+        let now = sys.now();
+        match (action.as_str(), started) {
+            ("toggle" | "pause", Some(from)) => {
+                counted += (now - from).to_std().unwrap_or_default();
+                started = None;
+            }
+            ("toggle" | "start", None) => started = Some(now),
+            ("start" | "pause", _) => {}
+            ("reset", _) => {
+                counted = Duration::ZERO;
+                started = None;
+            }
+            (action, _) => eprintln!("The stopwatch can't {action:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::events::BlockEvent;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn stopwatch_can_be_paused_and_reset() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let mut tasks = start("[[block]]\nblock = \"stopwatch\"", &mock);
+        let text = |line: String| line.split('"').nth(3).unwrap().to_string();
+        assert_eq!(text(render(&mut tasks, &mock).await), "⏱️ ");
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        sleep(Duration::from_secs(3723)).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "⏱️ 01:02:03 ");
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        sleep(Duration::from_secs(60)).await;
+        assert_eq!(text(render(&mut tasks, &mock).await), "⏸️ 01:02:03 ");
+        let reset = BlockEvent::Command("reset".to_string());
+        tasks[0].events.send(reset).await.unwrap();
+        assert_eq!(text(render(&mut tasks, &mock).await), "⏱️ ");
+    }
+}
@@ -0,0 +1,226 @@
+use std::f64::consts::PI;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Days, Local, NaiveDate, TimeZone};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::{format_countdown, Template};
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static SUN_FALLBACK: &str = "🌅 ???";
+pub static SUN_UPDATE_FREQUENCY: u64 = 60 * 1000;
+
+/// How far below the horizon the sun's centre is at sunrise and sunset, from
+/// refraction and the size of its disc
+static SUNRISE_ELEVATION: f64 = -0.833;
+/// The evening golden hour starts when the sun drops below this elevation
+static GOLDEN_HOUR_ELEVATION: f64 = 6.0;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Placeholders: sunrise, sunset, golden_hour, day_length
+    pub format: Template,
+    /// How many minutes before sunrise, golden hour, or sunset to switch to
+    /// `countdown_format`. Never with 0.
+    pub countdown: u64,
+    /// Placeholders: icon, event, time, countdown
+    pub countdown_format: Template,
+    /// A chrono format string for the times
+    pub time_format: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            latitude: None,
+            longitude: None,
+            format: Template::parse("🌅 {sunrise} 🌇 {sunset}").unwrap(),
+            countdown: 0,
+            countdown_format: Template::parse("{icon} {event} in {countdown}").unwrap(),
+            time_format: "%H:%M".to_string(),
+        }
+    }
+}
+
+/// When things happen on one day. They're missing when the sun doesn't rise
+/// or set, like in a polar summer.
+struct Day {
+    sunrise: Option<DateTime<Local>>,
+    golden_hour: Option<DateTime<Local>>,
+    sunset: Option<DateTime<Local>>,
+}
+
+#[derive(Clone, Copy)]
+enum Event {
+    Sunrise,
+    GoldenHour,
+    Sunset,
+}
+
+impl Event {
+    fn icon(self) -> &'static str {
+        match self {
+            Event::Sunrise => "🌅",
+            Event::GoldenHour => "✨",
+            Event::Sunset => "🌇",
+        }
+    }
+
+    fn name(self) -> String {
+        let id = match self {
+            Event::Sunrise => "sun-sunrise",
+            Event::GoldenHour => "sun-golden-hour",
+            Event::Sunset => "sun-sunset",
+        };
+        tr(id, &[])
+    }
+}
+
+/// Show today's sunrise and sunset, worked out from the location so nothing
+/// has to be fetched
+pub async fn sun_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let (Some(latitude), Some(longitude)) = (config.latitude, config.longitude) else {
+        bail!("The sun block needs a latitude and longitude");
+    };
+    loop {
+        let now = sys.now();
+        let today = now.date_naive();
+        let day = Day::new(today, latitude, longitude);
+        let upcoming = next_event(&day, now).or_else(|| {
+            let tomorrow = today.checked_add_days(Days::new(1))?;
+            let sunrise = Day::new(tomorrow, latitude, longitude).sunrise?;
+            Some((sunrise, Event::Sunrise))
+        });
+        tx.send(render(&config, &day, upcoming, now).into())?;
+        events
+            .wait(Duration::from_millis(SUN_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+fn render(
+    config: &Config,
+    day: &Day,
+    upcoming: Option<(DateTime<Local>, Event)>,
+    now: DateTime<Local>,
+) -> String {
+    let time = |at: Option<DateTime<Local>>| Some(at?.format(&config.time_format).to_string());
+    if let Some((at, event)) = upcoming {
+        let remaining = (at - now).to_std().unwrap_or_default();
+        if remaining <= Duration::from_secs(config.countdown * 60) {
+            return config.countdown_format.render(|name| match name {
+                "icon" => Some(event.icon().to_string()),
+                "event" => Some(event.name()),
+                "time" => time(Some(at)),
+                "countdown" => Some(format_countdown(remaining)),
+                _ => None,
+            });
+        }
+    }
+    config.format.render(|name| match name {
+        "sunrise" => time(day.sunrise),
+        "sunset" => time(day.sunset),
+        "golden_hour" => time(day.golden_hour),
+        "day_length" => {
+            let length = (day.sunset? - day.sunrise?).to_std().ok()?;
+            Some(format_countdown(length))
+        }
+        _ => None,
+    })
+}
+
+/// The first of today's events still to come
+fn next_event(day: &Day, now: DateTime<Local>) -> Option<(DateTime<Local>, Event)> {
+    [
+        (day.sunrise, Event::Sunrise),
+        (day.golden_hour, Event::GoldenHour),
+        (day.sunset, Event::Sunset),
+    ]
+    .into_iter()
+    .filter_map(|(at, event)| Some((at?, event)))
+    .find(|(at, _)| *at > now)
+}
+
+impl Day {
+    /// From the sunrise equation, which is good to about a minute away from
+    /// the poles
+    fn new(date: NaiveDate, latitude: f64, longitude: f64) -> Self {
+        let radians = PI / 180.0;
+        // Days since noon on the 1st of January 2000, for noon on `date`
+        let noon = date.and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp();
+        let days = (noon as f64 / 86400.0 + 2440587.5 - 2451545.0).round() + 0.0008;
+        let mean_noon = days - longitude / 360.0;
+        let anomaly = (357.5291 + 0.98560028 * mean_noon).rem_euclid(360.0) * radians;
+        let centre =
+            1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+        let ecliptic =
+            ((anomaly / radians + centre + 180.0 + 102.9372).rem_euclid(360.0)) * radians;
+        let transit =
+            2451545.0 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+        let declination = (ecliptic.sin() * (23.4397 * radians).sin()).asin();
+        let latitude = latitude * radians;
+
+        // When the sun is at `elevation` before and after noon
+        let crossing = |elevation: f64| {
+            let cos_hour_angle = ((elevation * radians).sin() - latitude.sin() * declination.sin())
+                / (latitude.cos() * declination.cos());
+            if !(-1.0..=1.0).contains(&cos_hour_angle) {
+                return (None, None);
+            }
+            let offset = cos_hour_angle.acos() / radians / 360.0;
+            (julian_time(transit - offset), julian_time(transit + offset))
+        };
+        let (sunrise, sunset) = crossing(SUNRISE_ELEVATION);
+        let (_, golden_hour) = crossing(GOLDEN_HOUR_ELEVATION);
+        Self {
+            sunrise,
+            golden_hour,
+            sunset,
+        }
+    }
+}
+
+fn julian_time(julian: f64) -> Option<DateTime<Local>> {
+    let seconds = ((julian - 2440587.5) * 86400.0).round() as i64;
+    Local.timestamp_opt(seconds, 0).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn sun_counts_down_to_golden_hour() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = r#"
+            [[block]]
+            block = "sun"
+            latitude = 51.48
+            longitude = 0.0
+            countdown = 60
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🌅 06:22 🌇 17:11 ","#));
+
+        sleep(Duration::from_secs((6 * 60 + 23) * 60)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"✨ golden hour in 32m ","#));
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::blocks::memory::read_meminfo;
+use crate::events::Events;
+use crate::format::{format_bytes, Template};
+use crate::output::{BlockOutput, Threshold};
+
+pub static SWAP_FALLBACK: &str = "💾 ???";
+pub static SWAP_UPDATE_FREQUENCY: u64 = 5000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the kernel reports memory use
+    pub path: PathBuf,
+    /// Only show the block once more than this percent of swap is used
+    pub above: f64,
+    pub icon: String,
+    /// Placeholders: icon, used, free, total (like "5.2G"), percent (of the
+    /// total that's used)
+    pub format: Template,
+    /// Colours for when the percentage used reaches a threshold
+    pub colors: Vec<Threshold>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/proc/meminfo"),
+            above: 0.0,
+            icon: "💾".to_string(),
+            format: Template::parse("{icon} {used}").unwrap(),
+            colors: vec![
+                Threshold::new(50.0, "#ffaa00"),
+                Threshold::new(80.0, "#ff4444"),
+            ],
+        }
+    }
+}
+
+/// Show how much swap is used, from /proc/meminfo. The block is hidden until
+/// it's used past `above` percent, since that's when it's worth knowing.
+pub async fn swap_task(
+    config: Config,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    loop {
+        let output = match read_swap(&config.path).await {
+            Ok((total, free)) => render(&config, total, free),
+            Err(err) => {
+                eprintln!("Couldn't read the swap use. {err:#}");
+                SWAP_FALLBACK.into()
+            }
+        };
+        tx.send(output)?;
+        events
+            .wait(Duration::from_millis(SWAP_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// The total and free swap, in bytes
+async fn read_swap(path: &Path) -> Result<(f64, f64)> {
+    let meminfo = read_meminfo(path).await?;
+    let field = |name: &str| {
+        meminfo
+            .get(name)
+            .copied()
+            .with_context(|| format!("{} has no {name}", path.display()))
+    };
+    Ok((field("SwapTotal")?, field("SwapFree")?))
+}
+
+fn render(config: &Config, total: f64, free: f64) -> BlockOutput {
+    let used = total - free;
+    let percent = if total > 0.0 {
+        used / total * 100.0
+    } else {
+        0.0
+    };
+    if total <= 0.0 || used <= 0.0 || percent <= config.above {
+        return BlockOutput::default();
+    }
+    let full_text = config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "used" => Some(format_bytes(used)),
+        "free" => Some(format_bytes(free)),
+        "total" => Some(format_bytes(total)),
+        "percent" => Some(format!("{percent:.0}")),
+        _ => None,
+    });
+    BlockOutput {
+        full_text,
+        color: Threshold::color(&config.colors, percent),
+        ..BlockOutput::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn swap_is_hidden_until_it_passes_the_threshold() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let meminfo = |free: &str| {
+            let contents = format!("SwapTotal:       8192000 kB\nSwapFree:        {free} kB\n");
+            proc.write("meminfo", contents);
+        };
+        meminfo("8000000");
+        let config = format!(
+            "[[block]]\nblock = \"swap\"\npath = {:?}\nabove = 10\nformat = \"{{used}} {{percent}}%\"",
+            proc.path("meminfo")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"🗓️"#));
+        meminfo("4096000");
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r##"[{"full_text":"3.9G 50% ","color":"#ffaa00","name":"swap""##));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unreadable_swap_shows_the_fallback() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let proc = Fixture::new();
+        let config = format!(
+            "[[block]]\nblock = \"swap\"\npath = {:?}",
+            proc.path("meminfo")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"💾 ??? ","name":"swap""#));
+        proc.write("meminfo", "SwapTotal: 8192000 kB\nSwapFree: 4096000 kB\n");
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r##"[{"full_text":"💾 3.9G ","color":"#ffaa00","name":"swap""##));
+    }
+}
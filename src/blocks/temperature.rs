@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::events::Events;
+use crate::format::Template;
+use crate::output::{BlockOutput, Threshold};
+
+pub static TEMPERATURE_FALLBACK: &str = "🌡️ ???";
+pub static TEMPERATURE_UPDATE_FREQUENCY: u64 = 5000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the kernel lists hardware monitoring chips
+    pub hwmon: PathBuf,
+    /// Where the kernel lists thermal zones
+    pub thermal: PathBuf,
+    /// Which sensors to show the hottest of, by their chip like "coretemp",
+    /// the chip and label like "coretemp/Package id 0", or a thermal zone's
+    /// type like "x86_pkg_temp". Every sensor is used if this is empty.
+    pub sensors: Vec<String>,
+    pub icon: String,
+    /// Placeholders: icon, temperature (in °C), sensor
+    pub format: Template,
+    /// Colours for when the temperature reaches some degrees
+    pub colors: Vec<Threshold>,
+    /// Turn urgent at this many degrees
+    pub urgent: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            hwmon: PathBuf::from("/sys/class/hwmon"),
+            thermal: PathBuf::from("/sys/class/thermal"),
+            sensors: Vec::new(),
+            icon: "🌡️".to_string(),
+            format: Template::parse("{icon} {temperature}°C").unwrap(),
+            colors: vec![
+                Threshold::new(70.0, "#ffaa00"),
+                Threshold::new(85.0, "#ff4444"),
+            ],
+            urgent: 95.0,
+        }
+    }
+}
+
+struct Sensor {
+    /// Like "coretemp/Package id 0", or "coretemp" for a sensor without a
+    /// label
+    name: String,
+    /// The chip or thermal zone type
+    chip: String,
+    celsius: f64,
+}
+
+/// Show the hottest temperature from hwmon and thermal zone sensors. The
+/// block is hidden if there aren't any.
+pub async fn temperature_task(
+    config: Config,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    loop {
+        let mut sensors = read_hwmon(&config.hwmon).await;
+        sensors.extend(read_thermal(&config.thermal).await);
+        if !config.sensors.is_empty() {
+            sensors.retain(|s| config.sensors.iter().any(|c| *c == s.name || *c == s.chip));
+        }
+        let hottest = sensors
+            .iter()
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius));
+        let output = match hottest {
+            None => BlockOutput::default(),
+            Some(sensor) => BlockOutput {
+                full_text: config.format.render(|name| match name {
+                    "icon" => Some(config.icon.clone()),
+                    "temperature" => Some(format!("{:.0}", sensor.celsius)),
+                    "sensor" => Some(sensor.name.clone()),
+                    _ => None,
+                }),
+                color: Threshold::color(&config.colors, sensor.celsius),
+                urgent: sensor.celsius >= config.urgent,
+                ..BlockOutput::default()
+            },
+        };
+        tx.send(output)?;
+        events
+            .wait(Duration::from_millis(TEMPERATURE_UPDATE_FREQUENCY))
+            .await;
+    }
+}
+
+/// Every `tempN_input` of every chip. Chips come and go with devices, so
+/// anything that can't be read is skipped.
+async fn read_hwmon(path: &Path) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+    for dir in subdirs(path).await {
+        let Some(chip) = text(&dir.join("name")).await else {
+            continue;
+        };
+        for file in files(&dir).await {
+            let Some(number) = file
+                .strip_prefix("temp")
+                .and_then(|f| f.strip_suffix("_input"))
+            else {
+                continue;
+            };
+            let Some(celsius) = millidegrees(&dir.join(&file)).await else {
+                continue;
+            };
+            let name = match text(&dir.join(format!("temp{number}_label"))).await {
+                Some(label) => format!("{chip}/{label}"),
+                None => chip.clone(),
+            };
+            sensors.push(Sensor {
+                name,
+                chip: chip.clone(),
+                celsius,
+            });
+        }
+    }
+    sensors
+}
+
+async fn read_thermal(path: &Path) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+    for dir in subdirs(path).await {
+        let is_zone = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("thermal_zone"));
+        if !is_zone {
+            continue;
+        }
+        let (Some(kind), Some(celsius)) = (
+            text(&dir.join("type")).await,
+            millidegrees(&dir.join("temp")).await,
+        ) else {
+            continue;
+        };
+        sensors.push(Sensor {
+            name: kind.clone(),
+            chip: kind,
+            celsius,
+        });
+    }
+    sensors
+}
+
+/// The directories in `path`, sorted so sensors keep their order
+async fn subdirs(path: &Path) -> Vec<PathBuf> {
+    let Ok(mut entries) = tokio::fs::read_dir(path).await else {
+        return Vec::new();
+    };
+    let mut dirs = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        dirs.push(entry.path());
+    }
+    dirs.sort();
+    dirs
+}
+
+async fn files(dir: &Path) -> Vec<String> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Some(name) = entry.file_name().to_str() {
+            files.push(name.to_string());
+        }
+    }
+    files.sort();
+    files
+}
+
+async fn text(path: &Path) -> Option<String> {
+    let text = tokio::fs::read_to_string(path).await.ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+async fn millidegrees(path: &Path) -> Option<f64> {
+    Some(text(path).await?.parse::<f64>().ok()? / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start, Fixture};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn temperature_shows_the_hottest_chosen_sensor() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sys = Fixture::new();
+        sys.attributes(
+            "hwmon/hwmon0",
+            &[
+                ("name", "coretemp"),
+                ("temp1_label", "Package id 0"),
+                ("temp1_input", "45000"),
+                ("temp2_input", "52000"),
+            ],
+        );
+        sys.attributes(
+            "hwmon/hwmon1",
+            &[("name", "nvme"), ("temp1_input", "88000")],
+        );
+        sys.attributes(
+            "thermal/thermal_zone0",
+            &[("type", "x86_pkg_temp"), ("temp", "48000")],
+        );
+        sys.attributes("thermal/cooling_device0", &[("type", "Processor")]);
+        let config = format!(
+            "[[block]]\nblock = \"temperature\"\nhwmon = {:?}\nthermal = {:?}\nsensors = [\"coretemp\", \"x86_pkg_temp\"]\nformat = \"{{temperature}}°C {{sensor}}\"",
+            sys.path("hwmon"),
+            sys.path("thermal")
+        );
+        let mut tasks = start(&config, &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"52°C coretemp ","name":"temperature""#));
+        sys.attributes("hwmon/hwmon0", &[("temp1_input", "96000")]);
+        sleep(Duration::from_secs(5)).await;
+        assert!(render(&mut tasks, &mock).await.starts_with(
+            r##"[{"full_text":"96°C coretemp/Package id 0 ","color":"#ff4444","urgent":true"##
+        ));
+    }
+}
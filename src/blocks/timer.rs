@@ -0,0 +1,246 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::click::{BUTTON_LEFT, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_UP};
+use crate::events::{BlockEvent, Events};
+use crate::format::{format_duration, Template};
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static TIMER_FALLBACK: &str = "⏲️ ???";
+/// How long it waits while nothing is counting down
+static TIMER_IDLE_WAIT: u64 = 60 * 60;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub icon: String,
+    /// How long a left click sets the timer for
+    pub minutes: u64,
+    /// Placeholders: icon, remaining
+    pub format: Template,
+    /// Shown while the timer isn't running. Placeholders: icon
+    pub idle_format: Template,
+    /// The colour once it's reached zero
+    pub done_color: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            icon: "⏲️".to_string(),
+            minutes: 25,
+            format: Template::parse("{icon} {remaining}").unwrap(),
+            idle_format: Template::parse("{icon}").unwrap(),
+            done_color: "#ff4444".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Idle,
+    Running(DateTime<Local>),
+    Done,
+}
+
+/// Count down from a click or `subar ctl timer 25m`, and turn urgent at
+/// zero. Scrolling adds or takes off a minute, and another left click, or
+/// `subar ctl timer stop`, stops it.
+pub async fn timer_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let minute = chrono::Duration::minutes(1);
+    let mut state = State::Idle;
+    loop {
+        let now = sys.now();
+        if matches!(state, State::Running(ends) if ends <= now) {
+            state = State::Done;
+        }
+        let icon = |name: &str| (name == "icon").then(|| config.icon.clone());
+        let (output, wait) = match state {
+            State::Idle => (
+                config.idle_format.render(icon).into(),
+                Duration::from_secs(TIMER_IDLE_WAIT),
+            ),
+            State::Running(ends) => {
+                let left = (ends - now).to_std().unwrap_or_default();
+                // Rounded up, so it shows zero just as it finishes
+                let shown =
+                    Duration::from_secs(left.as_secs() + u64::from(left.subsec_nanos() > 0));
+                let full_text = config.format.render(|name| match name {
+                    "remaining" => Some(format_duration(shown)),
+                    name => icon(name),
+                });
+                let tick = match left.subsec_nanos() {
+                    0 => Duration::from_secs(1),
+                    nanos => Duration::from_nanos(nanos.into()),
+                };
+                (full_text.into(), tick)
+            }
+            State::Done => {
+                let full_text = config.format.render(|name| match name {
+                    "remaining" => Some(format_duration(Duration::ZERO)),
+                    name => icon(name),
+                });
+                let output = BlockOutput {
+                    full_text,
+                    color: Some(config.done_color.clone()),
+                    urgent: true,
+                    ..BlockOutput::default()
+                };
+                (output, Duration::from_secs(TIMER_IDLE_WAIT))
+            }
+        };
+        tx.send(output)?;
+        match events.next(wait).await {
+            Some(BlockEvent::Click(click)) => {
+                state = match (click.button, state) {
+                    (BUTTON_LEFT, State::Idle) => {
+                        match minutes(config.minutes).and_then(|l| running(sys.now(), l)) {
+                            Ok(state) => state,
+                            Err(err) => {
+                                eprintln!("Couldn't start the timer. {err:#}");
+                                State::Idle
+                            }
+                        }
+                    }
+                    (BUTTON_LEFT, _) => State::Idle,
+                    (BUTTON_SCROLL_UP, State::Running(ends)) => {
+                        running(ends, minute).unwrap_or(state)
+                    }
+                    (BUTTON_SCROLL_DOWN, State::Running(ends)) => {
+                        running(ends, -minute).unwrap_or(state)
+                    }
+                    (_, state) => state,
+                }
+            }
+            Some(BlockEvent::Command(command)) => match command.trim() {
+                "stop" => state = State::Idle,
+                length => match parse_duration(length).and_then(|l| running(sys.now(), l)) {
+                    Ok(running) => state = running,
+                    Err(err) => eprintln!("Couldn't start the timer. {err:#}"),
+                },
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Counting down to `length` after `start`
+fn running(start: DateTime<Local>, length: chrono::Duration) -> Result<State> {
+    let ends = start
+        .checked_add_signed(length)
+        .context("That's too long for the timer")?;
+    Ok(State::Running(ends))
+}
+
+fn minutes(minutes: u64) -> Result<chrono::Duration> {
+    seconds(minutes.checked_mul(60))
+}
+
+/// The length, unless it overflowed or is too long for chrono
+fn seconds(seconds: Option<u64>) -> Result<chrono::Duration> {
+    let seconds = seconds.context("That's too long for the timer")?;
+    chrono::Duration::from_std(Duration::from_secs(seconds))
+        .context("That's too long for the timer")
+}
+
+/// A length like "25m", "90s", or "1h30m". Plain numbers are minutes.
+fn parse_duration(text: &str) -> Result<chrono::Duration> {
+    if let Ok(number) = text.parse::<u64>() {
+        return minutes(number);
+    }
+    let mut total = Some(0u64);
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(digits);
+        let scale = match after.chars().next() {
+            Some('h') => 3600,
+            Some('m') => 60,
+            Some('s') => 1,
+            _ => bail!("{text:?} isn't a length like 25m or 1h30m"),
+        };
+        let number: u64 = number
+            .parse()
+            .with_context(|| format!("{text:?} isn't a length like 25m or 1h30m"))?;
+        total = number
+            .checked_mul(scale)
+            .and_then(|n| total?.checked_add(n));
+        rest = after[1..].trim_start();
+    }
+    if total == Some(0) {
+        bail!("The timer needs a length, like 25m");
+    }
+    seconds(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::testing::{click, render, start};
+    use crate::click::BUTTON_LEFT;
+    use crate::events::BlockEvent;
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn timer_counts_down_to_urgent() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let mut tasks = start("[[block]]\nblock = \"timer\"", &mock);
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"⏲️ ","name":"timer""#));
+        let command = BlockEvent::Command("1m30s".to_string());
+        tasks[0].events.send(command).await.unwrap();
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"⏲️ 01:30 ","name""#));
+        sleep(Duration::from_secs(90)).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r##"[{"full_text":"⏲️ 00:00 ","color":"#ff4444","urgent":true,"name""##));
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        assert!(render(&mut tasks, &mock)
+            .await
+            .starts_with(r#"[{"full_text":"⏲️ ","name""#));
+    }
+
+    #[test]
+    fn lengths_are_parsed() {
+        let seconds = |text| parse_duration(text).map(|d| d.num_seconds()).ok();
+        assert_eq!(seconds("1h30m"), Some(5400));
+        assert_eq!(seconds("1h 30m"), Some(5400));
+        assert_eq!(seconds("90s"), Some(90));
+        assert_eq!(seconds("25"), Some(1500));
+        for text in ["", "m", "0m", "5x", "-5m", "1.5h"] {
+            assert_eq!(seconds(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn overflowing_lengths_are_errors() {
+        for text in [
+            "99999999999999999999s",
+            "18446744073709551615h",
+            "18446744073709551615s1s",
+            "9223372036854775807s",
+            "18446744073709551615",
+        ] {
+            assert!(parse_duration(text).is_err(), "{text:?}");
+        }
+        assert!(minutes(u64::MAX).is_err());
+        let far_off = Local::now() + chrono::Duration::days(365 * 200_000);
+        assert!(running(far_off, chrono::Duration::days(365 * 100_000)).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::events::Events;
+use crate::format::Template;
+use crate::locale::tr;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+pub static TRANSIT_FALLBACK: &str = "🚌 ???";
+static TRANSIT_IDLE_FREQUENCY: u64 = 60 * 1000;
+static TRANSIT_MAX_RETRY: u64 = 10 * 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub provider: Provider,
+    /// Only show departures during these times, e.g. "07:30-09:00". The
+    /// block is always shown if none are given.
+    #[serde(default)]
+    pub hours: Vec<TimeRange>,
+    /// How many departures to show
+    #[serde(default = "default_count")]
+    pub count: usize,
+    /// Seconds between fetches
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default = "default_icon")]
+    pub icon: String,
+    /// Placeholders: icon, departures
+    #[serde(default = "default_format")]
+    pub format: Template,
+}
+
+fn default_count() -> usize {
+    2
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+fn default_icon() -> String {
+    "🚌".to_string()
+}
+
+fn default_format() -> Template {
+    Template::parse("{icon} {departures}").unwrap()
+}
+
+/// Where departures come from. Times can be Unix timestamps, RFC 3339
+/// dates, HH:MM, or a small number of minutes from now.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Provider {
+    /// A shell command that prints one departure per line
+    Command { command: String },
+    /// An HTTP API that returns JSON
+    Json {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        /// JSON pointer to the array of departures, e.g. "/departures"
+        #[serde(default)]
+        departures: String,
+        /// JSON pointer to the departure time within each departure
+        #[serde(default)]
+        time: String,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct TimeRange {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeRange {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            // The range wraps around midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let invalid = || serde::de::Error::custom(format!("Invalid time range {s:?}"));
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M");
+        Ok(Self {
+            start: parse(start).map_err(|_| invalid())?,
+            end: parse(end).map_err(|_| invalid())?,
+        })
+    }
+}
+
+pub async fn transit_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_secs(config.interval),
+        Duration::from_millis(TRANSIT_MAX_RETRY),
+    );
+    loop {
+        let now = sys.now();
+        let active = config.hours.is_empty() || config.hours.iter().any(|h| h.contains(now.time()));
+        if !active {
+            tx.send(BlockOutput::default())?;
+            events
+                .wait(Duration::from_millis(TRANSIT_IDLE_FREQUENCY))
+                .await;
+            continue;
+        }
+
+        match fetch_departures(&sys, &config.provider).await {
+            Ok(departures) => {
+                backoff.reset();
+                tx.send(render(&config, &departures, sys.now()).into())?;
+                events.wait(Duration::from_secs(config.interval)).await;
+            }
+            Err(err) => {
+                eprintln!("Couldn't get departures. {err:#}");
+                tx.send(TRANSIT_FALLBACK.into())?;
+                events.wait(backoff.fail()).await;
+            }
+        }
+    }
+}
+
+fn render(config: &Config, departures: &[DateTime<Local>], now: DateTime<Local>) -> String {
+    let mut minutes: Vec<i64> = departures
+        .iter()
+        .map(|d| (*d - now).num_seconds())
+        .filter(|secs| *secs >= 0)
+        .map(|secs| (secs + 30) / 60)
+        .collect();
+    minutes.sort_unstable();
+    if minutes.is_empty() {
+        return String::new();
+    }
+    let departures = minutes
+        .iter()
+        .take(config.count)
+        .map(|m| {
+            if *m == 0 {
+                tr("transit-now", &[])
+            } else {
+                tr("duration-minutes", &[("minutes", &m.to_string())])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    config.format.render(|name| match name {
+        "icon" => Some(config.icon.clone()),
+        "departures" => Some(departures.clone()),
+        _ => None,
+    })
+}
+
+async fn fetch_departures(sys: &Sys, provider: &Provider) -> Result<Vec<DateTime<Local>>> {
+    let now = sys.now();
+    match provider {
+        Provider::Command { command } => {
+            let output = sys
+                .output(Command::new("sh").arg("-c").arg(command))
+                .await?;
+            if !output.status.success() {
+                bail!("`{command}` failed with {}", output.status);
+            }
+            let stdout = String::from_utf8(output.stdout)?;
+            Ok(stdout
+                .lines()
+                .filter_map(|line| parse_str(line.trim(), now))
+                .collect())
+        }
+        Provider::Json {
+            url,
+            headers,
+            departures,
+            time,
+        } => {
+            let headers = headers.clone().into_iter().collect();
+            let body = sys.http_get(url, headers).await?;
+            let json: Value = serde_json::from_str(&body)?;
+            let list = json
+                .pointer(departures)
+                .and_then(Value::as_array)
+                .with_context(|| format!("No array of departures at {departures:?}"))?;
+            Ok(list
+                .iter()
+                .filter_map(|d| parse_value(d.pointer(time)?, now))
+                .collect())
+        }
+    }
+}
+
+fn parse_value(value: &Value, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    match value {
+        Value::Number(n) => parse_number(n.as_f64()?, now),
+        Value::String(s) => parse_str(s, now),
+        _ => None,
+    }
+}
+
+fn parse_str(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if let Ok(n) = s.parse::<f64>() {
+        return parse_number(n, now);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Local));
+    }
+    let time = NaiveTime::parse_from_str(s, "%H:%M").ok()?;
+    Local
+        .from_local_datetime(&now.date_naive().and_time(time))
+        .earliest()
+}
+
+fn parse_number(n: f64, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if n > 1e12 {
+        Local.timestamp_millis_opt(n as i64).single()
+    } else if n > 1e9 {
+        Local.timestamp_opt(n as i64, 0).single()
+    } else {
+        Some(now + chrono::Duration::seconds((n * 60.0) as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn transit_departures_from_json() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://transit.example/stop",
+            r#"{"departures": [{"due": "09:42"}, {"due": 3}, {"due": 25}]}"#,
+        );
+        let config = r#"
+            [[block]]
+            block = "transit"
+            provider = { type = "json", url = "https://transit.example/stop", departures = "/departures", time = "/due" }
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🚌 3m, 12m ","name":"transit""#));
+    }
+}
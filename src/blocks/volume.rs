@@ -0,0 +1,1521 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::click::{ClickEvent, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_UP};
+use crate::events::{while_shown, Events};
+use crate::format::Template;
+use crate::locale::tr;
+use crate::notify::notify;
+use crate::output::BlockOutput;
+use crate::pipewire;
+use crate::sway;
+use crate::system::{Reader, Sys};
+
+pub static VOL_FALLBACK: &str = "🔊 ???";
+pub static MIC_FALLBACK: &str = "🎙️ ???";
+pub static VOL_UPDATE_FREQUENCY: u64 = 323;
+static VOL_MAX_RETRY: u64 = 30 * 1000;
+/// How long to wait between changes when the backend says when there are
+/// any, in case one is missed
+static VOL_IDLE_FREQUENCY: u64 = 60 * 1000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Whether to show the default output or the default input
+    pub device: Device,
+    /// Which devices to show, each with `format`: "default", "running" to
+    /// add any others that are in use, or "all"
+    pub show: Show,
+    /// Show the default device and how many others there are, like "🔉 45%
+    /// +1", rather than each of them
+    pub compact: bool,
+    /// Show the sink or source whose name or description contains this,
+    /// ignoring case, like "USB DAC", instead of the default one. Only the
+    /// wireplumber, pipewire, and pulseaudio backends can pick one.
+    pub target: Option<String>,
+    /// Where the volume comes from. Defaults to whichever works on Linux,
+    /// sndio on OpenBSD, and mixer on FreeBSD.
+    pub backend: Backend,
+    /// `{icon}` for each level of volume, quietest first. Defaults to 🔈🔉🔊
+    /// for sinks and 🎙️ for sources.
+    pub icons: Option<Vec<String>>,
+    /// The volumes at which each icon after the first starts being shown
+    pub thresholds: Vec<u32>,
+    /// `{icon}` while muted. Defaults to 🔇 for sinks and 🤐 for sources.
+    pub muted_icon: Option<String>,
+    /// Placeholders: icon, volume, name (the device's description), port
+    /// (the description of the port it's using, like "Headphones", or its
+    /// name if it has no ports). Only the wireplumber, pipewire, and
+    /// pulseaudio backends know the name and port.
+    pub format: Template,
+    /// Hold this while scrolling to change the focused application's volume
+    pub app_modifier: String,
+    /// Percent to change the volume by each scroll, 2 by default
+    pub step: u32,
+    /// The sound card the alsa backend uses, by number or name like "PCH".
+    /// Defaults to ALSA's default device.
+    pub card: Option<String>,
+    /// The mixer control the alsa backend reads. Defaults to Master for
+    /// sinks and Capture for sources.
+    pub control: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            device: Device::default(),
+            show: Show::default(),
+            compact: false,
+            target: None,
+            backend: Backend::default(),
+            icons: None,
+            thresholds: vec![34, 67],
+            muted_icon: None,
+            format: Template::parse("{icon} {volume}%").unwrap(),
+            app_modifier: "Shift".to_string(),
+            step: 2,
+            card: None,
+            control: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    /// The default output, like speakers or headphones
+    #[default]
+    Sink,
+    /// The default input, usually a microphone
+    Source,
+}
+
+impl Device {
+    pub fn fallback(self) -> &'static str {
+        match self {
+            Self::Sink => VOL_FALLBACK,
+            Self::Source => MIC_FALLBACK,
+        }
+    }
+
+    /// The icons for each level of volume, and for when it's muted
+    fn icons(self) -> (&'static [&'static str], &'static str) {
+        match self {
+            Self::Sink => (&["🔈", "🔉", "🔊"], "🔇"),
+            Self::Source => (&["🎙️"], "🤐"),
+        }
+    }
+
+    /// What pactl calls it, as in `get-sink-volume`
+    fn pulseaudio(self) -> &'static str {
+        match self {
+            Self::Sink => "sink",
+            Self::Source => "source",
+        }
+    }
+}
+
+/// Which devices the block shows. Only the wireplumber, pipewire, and
+/// pulseaudio backends can show more than the default, and only without a
+/// `target`.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Show {
+    /// Only the default device
+    #[default]
+    Default,
+    /// The default device, and others while something is playing to or
+    /// recording from them
+    Running,
+    /// Every device
+    All,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// The first of WirePlumber, PulseAudio, ALSA, sndio, and mixer that can
+    /// read the volume when the bar starts
+    Auto,
+    /// `wpctl`, for PipeWire
+    Wireplumber,
+    /// PipeWire's `pw-dump --monitor` tool, which says when the volume
+    /// changes rather than being asked, and works without WirePlumber's
+    /// tools. This runs the command rather than using the pipewire crate's
+    /// bindings, so building subar doesn't need PipeWire's headers or
+    /// libclang.
+    Pipewire,
+    /// `pactl`, for PulseAudio or pipewire-pulse. This runs the command
+    /// rather than linking to libpulse, like the pipewire backend.
+    Pulseaudio,
+    /// `amixer`, which reads an ALSA mixer control without a sound server
+    Alsa,
+    /// `sndioctl`, for OpenBSD's sndiod
+    Sndio,
+    /// FreeBSD's `mixer`
+    Mixer,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        if cfg!(target_os = "openbsd") {
+            Self::Sndio
+        } else if cfg!(target_os = "freebsd") {
+            Self::Mixer
+        } else {
+            Self::Auto
+        }
+    }
+}
+
+impl Backend {
+    /// Whether the volume is always read on a timer, rather than when it
+    /// changes
+    pub fn polls(self) -> bool {
+        self == Self::Mixer
+    }
+}
+
+/// The device's volume
+struct Level {
+    /// Percent, which can be over 100
+    volume: u32,
+    muted: bool,
+    /// Its description, like "USB DAC Analog Stereo", if it's shown
+    device: String,
+    /// The port it's using, like "Headphones", if it's shown and it has one
+    port: Option<String>,
+    /// What the backend's commands call it, if they need to be told
+    id: String,
+}
+
+/// The device `target` picked, kept until reading it fails so it isn't
+/// looked for on every poll
+#[derive(Clone)]
+struct Picked {
+    /// What the backend's commands call it
+    id: String,
+    description: String,
+    port: Option<String>,
+}
+
+pub async fn volume_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let mut backoff = Backoff::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(VOL_MAX_RETRY),
+    );
+    let (backend, first) = match config.backend {
+        Backend::Auto => loop {
+            if let Some((backend, level)) = detect(&config, &sys).await {
+                break (backend, Some(level));
+            }
+            tx.send(config.device.fallback().into())?;
+            events.wait(backoff.fail()).await;
+        },
+        backend => (backend, None),
+    };
+    match backend {
+        Backend::Pipewire => watch_pipewire(config, sys, tx, events, backoff).await,
+        backend => follow(config, backend, sys, tx, events, backoff, first).await,
+    }
+}
+
+/// The first backend that can read the volume, and what it read
+async fn detect(config: &Config, sys: &Sys) -> Option<(Backend, Level)> {
+    let backends = [
+        Backend::Wireplumber,
+        Backend::Pulseaudio,
+        Backend::Alsa,
+        Backend::Sndio,
+        Backend::Mixer,
+    ];
+    for backend in backends {
+        if let Ok(level) = read_level(backend, config, &mut None, sys).await {
+            return Some((backend, level));
+        }
+    }
+    eprintln!("Couldn't find a way to read the volume");
+    None
+}
+
+async fn read_level(
+    backend: Backend,
+    config: &Config,
+    picked: &mut Option<Picked>,
+    sys: &Sys,
+) -> Result<Level> {
+    match backend {
+        Backend::Wireplumber => wireplumber_level(config, picked, sys).await,
+        Backend::Pulseaudio => pulseaudio_level(config, sys).await,
+        Backend::Alsa => alsa_level(config, sys).await,
+        Backend::Sndio => sndio_level(config.device, sys).await,
+        Backend::Mixer => mixer_level(config.device, sys).await,
+        Backend::Auto | Backend::Pipewire => bail!("The volume isn't read this way"),
+    }
+}
+
+/// Read the volume whenever the backend's monitor says something changed, or
+/// every VOL_UPDATE_FREQUENCY without one, starting with `first` if the
+/// backend was just found by reading it
+async fn follow(
+    config: Config,
+    backend: Backend,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+    mut backoff: Backoff,
+    mut first: Option<Level>,
+) -> Result<()> {
+    let mut picked = None;
+    loop {
+        // Started before reading the volume so no changes are missed
+        let mut changes = monitor(backend, &config, &sys);
+        loop {
+            let level = match first.take() {
+                Some(level) => Ok(level),
+                None => read_level(backend, &config, &mut picked, &sys).await,
+            };
+            let Ok(level) = level else {
+                break;
+            };
+            backoff.reset();
+            let others = match read_others(backend, &config, &sys).await {
+                Ok(others) => others,
+                Err(err) => {
+                    eprintln!("Couldn't list the other devices. {err:#}");
+                    Vec::new()
+                }
+            };
+            tx.send(render(&config, &level, &others))?;
+            let click = match &mut changes {
+                Some(lines) => wait_for_change(backend, &config, lines, &mut events).await,
+                None => Ok(events
+                    .wait(Duration::from_millis(VOL_UPDATE_FREQUENCY))
+                    .await),
+            };
+            match click {
+                Ok(Some(click)) => on_click(&config, backend, &level, &sys, &click).await,
+                Ok(None) => {}
+                Err(()) => changes = None,
+            }
+        }
+        tx.send(config.device.fallback().into())?;
+        events.wait(backoff.fail()).await;
+    }
+}
+
+type Changes = Lines<BufReader<Reader>>;
+
+/// Start the backend's command that prints a line when the volume might have
+/// changed, so it's only read when it needs to be. This is `pactl subscribe`
+/// for WirePlumber too, since pipewire-pulse provides it and wpctl has
+/// nothing like it.
+fn monitor(backend: Backend, config: &Config, sys: &Sys) -> Option<Changes> {
+    let mut command = match backend {
+        Backend::Wireplumber | Backend::Pulseaudio => {
+            let mut subscribe = pactl();
+            subscribe.arg("subscribe");
+            subscribe
+        }
+        Backend::Alsa => {
+            let mut alsactl = Command::new("alsactl");
+            alsactl.arg("monitor");
+            if let Some(card) = &config.card {
+                alsactl.arg(card);
+            }
+            alsactl
+        }
+        Backend::Sndio => {
+            let mut sndioctl = Command::new("sndioctl");
+            sndioctl.arg("-m");
+            sndioctl
+        }
+        Backend::Mixer | Backend::Auto | Backend::Pipewire => return None,
+    };
+    match sys.stream(&mut command) {
+        Ok(reader) => Some(BufReader::new(reader).lines()),
+        Err(err) => {
+            // Polling still works, just not as quickly
+            let program = command.as_std().get_program().to_string_lossy();
+            eprintln!("Couldn't watch the volume with {program}. {err}");
+            None
+        }
+    }
+}
+
+/// Wait until the monitor prints a change that matters, or the block is
+/// clicked or refreshed, which read the volume again too. Err means the
+/// monitor stopped.
+async fn wait_for_change(
+    backend: Backend,
+    config: &Config,
+    lines: &mut Changes,
+    events: &mut Events,
+) -> Result<Option<ClickEvent>, ()> {
+    let kind = format!(" on {} #", config.device.pulseaudio());
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                // pactl prints every event, like "Event 'change' on sink
+                // #54", and the server changes when the default device does
+                Ok(Some(line)) => match backend {
+                    Backend::Wireplumber | Backend::Pulseaudio
+                        if !line.contains(&kind) && !line.contains(" on server") => {}
+                    _ => return Ok(None),
+                },
+                Ok(None) | Err(_) => {
+                    eprintln!("The volume monitor stopped, so it's polled instead");
+                    return Err(());
+                }
+            },
+            click = events.wait(Duration::from_millis(VOL_IDLE_FREQUENCY)) => return Ok(click),
+        }
+    }
+}
+
+/// Follow the device's volume as pw-dump reports changes to it. Its output
+/// is left unread while the bar is paused.
+async fn watch_pipewire(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+    mut backoff: Backoff,
+) -> Result<()> {
+    loop {
+        let mut monitor = match pipewire::Monitor::start(&sys) {
+            Ok(monitor) => monitor,
+            Err(err) => {
+                eprintln!("Couldn't run pw-dump. {err}");
+                tx.send(config.device.fallback().into())?;
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        let mut graph = pipewire::Graph::default();
+        let mut level = None;
+        let mut paused = events.paused();
+        loop {
+            tokio::select! {
+                changes = while_shown(&mut paused, monitor.next()) => match changes {
+                    Ok(Some(changes)) => {
+                        backoff.reset();
+                        graph.update(changes);
+                        let source = config.device == Device::Source;
+                        let default = graph.default_node(source);
+                        let node = pick(graph.nodes(source), default, &config);
+                        level = node.map(Level::from);
+                        let others = others(&config, graph.nodes(source), default);
+                        let output = match &level {
+                            Some(level) => render(&config, level, &others),
+                            None => config.device.fallback().into(),
+                        };
+                        tx.send_if_modified(|current| {
+                            let changed = *current != output;
+                            *current = output;
+                            changed
+                        });
+                    }
+                    Ok(None) => {
+                        eprintln!("pw-dump stopped");
+                        break;
+                    }
+                    Err(err) => {
+                        eprintln!("Couldn't read pw-dump. {err:#}");
+                        break;
+                    }
+                },
+                Some(click) = events.wait(Duration::from_millis(VOL_IDLE_FREQUENCY)) => {
+                    if let Some(level) = &level {
+                        on_click(&config, Backend::Pipewire, level, &sys, &click).await;
+                    }
+                }
+            }
+        }
+        tx.send(config.device.fallback().into())?;
+        events.wait(backoff.fail()).await;
+    }
+}
+
+/// Show `level`, then `others` as `show` and `compact` say
+fn render(config: &Config, level: &Level, others: &[Level]) -> BlockOutput {
+    let text = |level: &Level| {
+        config.format.render(|name| match name {
+            "icon" => Some(icon(config, level)),
+            "volume" => Some(level.volume.to_string()),
+            "name" => Some(level.device.clone()),
+            "port" => Some(level.port.clone().unwrap_or_else(|| level.device.clone())),
+            _ => None,
+        })
+    };
+    let mut status = text(level);
+    if config.compact && !others.is_empty() {
+        status.push_str(&format!(" +{}", others.len()));
+    } else if !config.compact {
+        for other in others {
+            status.push(' ');
+            status.push_str(&text(other));
+        }
+    }
+    status.into()
+}
+
+/// The devices besides the default one that `show` adds
+async fn read_others(backend: Backend, config: &Config, sys: &Sys) -> Result<Vec<Level>> {
+    if config.show == Show::Default || config.target.is_some() {
+        return Ok(Vec::new());
+    }
+    let source = config.device == Device::Source;
+    match backend {
+        Backend::Wireplumber => {
+            let graph = pipewire::dump(sys).await?;
+            Ok(others(
+                config,
+                graph.nodes(source),
+                graph.default_node(source),
+            ))
+        }
+        Backend::Pulseaudio => {
+            let default = pulseaudio_default(config, sys).await?;
+            let devices = pulseaudio_devices(config, sys).await?;
+            let levels = devices
+                .into_iter()
+                .filter(|d| d.name != default && (config.show == Show::All || d.running))
+                .filter_map(|d| {
+                    Some(Level {
+                        volume: d.volume?,
+                        muted: d.muted,
+                        device: d.description,
+                        port: d.port,
+                        id: d.name,
+                    })
+                })
+                .collect();
+            Ok(levels)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn others(config: &Config, nodes: Vec<pipewire::Node>, default: Option<&str>) -> Vec<Level> {
+    if config.show == Show::Default || config.target.is_some() {
+        return Vec::new();
+    }
+    nodes
+        .into_iter()
+        .filter(|n| Some(n.name.as_str()) != default)
+        .filter(|n| config.show == Show::All || n.running)
+        .map(Level::from)
+        .collect()
+}
+
+fn icon(config: &Config, level: &Level) -> String {
+    let (defaults, muted) = config.device.icons();
+    if level.muted {
+        return config.muted_icon.as_deref().unwrap_or(muted).to_string();
+    }
+    let icons: Vec<&str> = match &config.icons {
+        Some(icons) => icons.iter().map(String::as_str).collect(),
+        None => defaults.to_vec(),
+    };
+    let passed = config
+        .thresholds
+        .iter()
+        .filter(|t| level.volume >= **t)
+        .count();
+    let index = passed.min(icons.len().saturating_sub(1));
+    icons.get(index).unwrap_or(&"").to_string()
+}
+
+async fn on_click(config: &Config, backend: Backend, level: &Level, sys: &Sys, click: &ClickEvent) {
+    if let Err(err) = handle_click(config, backend, level, sys, click).await {
+        eprintln!("Couldn't change the volume. {err:#}");
+    }
+}
+
+/// Run a command and return its stdout if it succeeded
+async fn run(sys: &Sys, command: &mut Command) -> Result<String> {
+    let output = sys.output(command).await?;
+    if !output.status.success() {
+        let program = command.as_std().get_program().to_string_lossy();
+        bail!("{program} failed with {}", output.status);
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+impl From<pipewire::Node> for Level {
+    fn from(node: pipewire::Node) -> Self {
+        Self {
+            volume: node.volume,
+            muted: node.muted,
+            device: node.description,
+            port: node.port,
+            id: node.id.to_string(),
+        }
+    }
+}
+
+/// The device `config.target` picks from `nodes`, or the default one
+fn pick(
+    nodes: Vec<pipewire::Node>,
+    default: Option<&str>,
+    config: &Config,
+) -> Option<pipewire::Node> {
+    nodes.into_iter().find(|node| match &config.target {
+        Some(target) => matches(target, &node.name, &node.description),
+        None => Some(node.name.as_str()) == default,
+    })
+}
+
+/// Whether `target` is part of a device's name or description, ignoring case
+fn matches(target: &str, name: &str, description: &str) -> bool {
+    let target = target.to_lowercase();
+    [name, description]
+        .iter()
+        .any(|s| s.to_lowercase().contains(&target))
+}
+
+/// wpctl only takes ids, so `target` is looked for in `pw-dump`
+async fn wireplumber_level(
+    config: &Config,
+    picked: &mut Option<Picked>,
+    sys: &Sys,
+) -> Result<Level> {
+    let source = config.device == Device::Source;
+    let described = config.format.uses("name") || config.format.uses("port");
+    let node = match &config.target {
+        Some(target) => {
+            if picked.is_none() {
+                let nodes = pipewire::dump(sys).await?.nodes(source);
+                let node = pick(nodes, None, config)
+                    .with_context(|| format!("Couldn't find a device matching {target}"))?;
+                *picked = Some(node.into());
+            }
+            picked.clone()
+        }
+        // The default can change at any time, so it's looked up every time
+        None if described => {
+            let graph = pipewire::dump(sys).await?;
+            let node = pick(graph.nodes(source), graph.default_node(source), config)
+                .context("Couldn't find the default device")?;
+            Some(node.into())
+        }
+        None => None,
+    };
+    let (id, description, port) = match node {
+        Some(node) => (node.id, node.description, node.port),
+        None if source => ("@DEFAULT_AUDIO_SOURCE@".to_string(), String::new(), None),
+        None => ("@DEFAULT_AUDIO_SINK@".to_string(), String::new(), None),
+    };
+    let mut wpctl = Command::new("wpctl");
+    wpctl.arg("get-volume").arg(&id);
+    let output = match run(sys, &mut wpctl).await {
+        Ok(output) => output,
+        Err(err) => {
+            // It may have been unplugged and come back with another id
+            *picked = None;
+            return Err(err);
+        }
+    };
+    let (volume, muted) = parse_wireplumber(&output)?;
+    Ok(Level {
+        volume,
+        muted,
+        device: description,
+        port,
+        id,
+    })
+}
+
+/// `wpctl get-volume` prints "Volume: 0.45", with " [MUTED]" after it
+/// while muted, and volumes over 100% like "Volume: 1.32"
+fn parse_wireplumber(output: &str) -> Result<(u32, bool)> {
+    let mut words = output
+        .trim()
+        .strip_prefix("Volume:")
+        .with_context(|| format!("Unexpected output from wpctl: {output:?}"))?
+        .split_whitespace();
+    let volume: f64 = words
+        .next()
+        .and_then(|v| v.parse().ok())
+        .with_context(|| format!("wpctl didn't print a volume: {output:?}"))?;
+    let muted = words.any(|w| w == "[MUTED]");
+    Ok(((volume * 100.0).round() as u32, muted))
+}
+
+impl From<pipewire::Node> for Picked {
+    fn from(node: pipewire::Node) -> Self {
+        Self {
+            id: node.id.to_string(),
+            description: node.description,
+            port: node.port,
+        }
+    }
+}
+
+/// pactl, with its output in English so it can be parsed
+fn pactl() -> Command {
+    let mut pactl = Command::new("pactl");
+    pactl.env("LC_ALL", "C");
+    pactl
+}
+
+/// `pactl get-sink-volume` prints each channel like "front-left: 29491 /
+/// 45% / -20.81 dB", and `pactl get-sink-mute` prints "Mute: yes" or "Mute: no"
+async fn pulseaudio_level(config: &Config, sys: &Sys) -> Result<Level> {
+    let kind = config.device.pulseaudio();
+    let mut found = PulseDevice {
+        name: format!("@DEFAULT_{}@", kind.to_uppercase()),
+        ..PulseDevice::default()
+    };
+    let described = config.format.uses("name") || config.format.uses("port");
+    if config.target.is_some() || described {
+        let devices = pulseaudio_devices(config, sys).await?;
+        found = match &config.target {
+            Some(target) => devices
+                .into_iter()
+                .find(|d| matches(target, &d.name, &d.description))
+                .with_context(|| format!("Couldn't find a device matching {target}"))?,
+            None => {
+                let default = pulseaudio_default(config, sys).await?;
+                match devices.into_iter().find(|d| d.name == default) {
+                    Some(device) => device,
+                    None => PulseDevice {
+                        name: default,
+                        ..PulseDevice::default()
+                    },
+                }
+            }
+        };
+    }
+    let name = found.name;
+    let mut get_volume = pactl();
+    get_volume.arg(format!("get-{kind}-volume")).arg(&name);
+    let output = run(sys, &mut get_volume).await?;
+    let volume = pactl_volume(&output).context("pactl didn't print a volume")?;
+    let mut get_mute = pactl();
+    get_mute.arg(format!("get-{kind}-mute")).arg(&name);
+    let mute = run(sys, &mut get_mute).await?;
+    Ok(Level {
+        volume,
+        muted: mute.trim() == "Mute: yes",
+        device: found.description,
+        port: found.port,
+        id: name,
+    })
+}
+
+/// The average of each channel's volume, like "front-left: 29491 / 45% /
+/// -20.81 dB"
+fn pactl_volume(channels: &str) -> Option<u32> {
+    let channels: Vec<u32> = channels
+        .split('/')
+        .filter_map(|part| part.trim().strip_suffix('%')?.parse().ok())
+        .collect();
+    if channels.is_empty() {
+        return None;
+    }
+    Some(channels.iter().sum::<u32>() / channels.len() as u32)
+}
+
+async fn pulseaudio_default(config: &Config, sys: &Sys) -> Result<String> {
+    let mut get_default = pactl();
+    get_default.arg(format!("get-default-{}", config.device.pulseaudio()));
+    Ok(run(sys, &mut get_default).await?.trim().to_string())
+}
+
+#[derive(Default)]
+struct PulseDevice {
+    name: String,
+    description: String,
+    /// The description of its active port
+    port: Option<String>,
+    volume: Option<u32>,
+    muted: bool,
+    running: bool,
+}
+
+/// Each sink or source from `pactl list`, which prints "Sink #54" and then
+/// indented lines like "Name:
+/// alsa_output.usb", "Description: USB DAC", "State: RUNNING", "Mute: no",
+/// and "Volume: front-left: 29491 / 45% / -20.81 dB", then its ports like
+/// "analog-output-headphones: Headphones (type: Headphones, ...)", then
+/// "Active Port: analog-output-headphones"
+async fn pulseaudio_devices(config: &Config, sys: &Sys) -> Result<Vec<PulseDevice>> {
+    let mut list = pactl();
+    list.arg("list")
+        .arg(format!("{}s", config.device.pulseaudio()));
+    let output = run(sys, &mut list).await?;
+    let mut devices: Vec<PulseDevice> = Vec::new();
+    let mut ports: Option<HashMap<String, String>> = None;
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        // Each starts with an unindented line like "Sink #54"
+        if !line.starts_with(char::is_whitespace) {
+            devices.push(PulseDevice::default());
+            continue;
+        }
+        let line = line.trim();
+        let Some(device) = devices.last_mut() else {
+            continue;
+        };
+        if let Some(name) = line.strip_prefix("Name: ") {
+            device.name = name.to_string();
+        } else if let Some(description) = line.strip_prefix("Description: ") {
+            device.description = description.to_string();
+        } else if let Some(state) = line.strip_prefix("State: ") {
+            device.running = state == "RUNNING";
+        } else if let Some(channels) = line.strip_prefix("Volume: ") {
+            device.volume = pactl_volume(channels);
+        } else if let Some(mute) = line.strip_prefix("Mute: ") {
+            device.muted = mute == "yes";
+        } else if line == "Ports:" {
+            ports = Some(HashMap::new());
+        } else if let Some(active) = line.strip_prefix("Active Port: ") {
+            device.port = ports.take().and_then(|mut ports| ports.remove(active));
+        } else if let Some(ports) = &mut ports {
+            if let Some((port, description)) = line.split_once(": ") {
+                let description = description.split(" (").next().unwrap_or(description);
+                ports.insert(port.to_string(), description.to_string());
+            }
+        }
+    }
+    Ok(devices)
+}
+
+/// `amixer sget Master` prints each channel like "Front Left: Playback 39
+/// [45%] [-36.00dB] [on]", where "[off]" means it's muted, and capture
+/// controls say "Capture" instead
+async fn alsa_level(config: &Config, sys: &Sys) -> Result<Level> {
+    let direction = match config.device {
+        Device::Sink => ": Playback ",
+        Device::Source => ": Capture ",
+    };
+    let control = alsa_control(config);
+    let mut amixer = Command::new("amixer");
+    amixer.env("LC_ALL", "C");
+    if let Some(card) = &config.card {
+        amixer.arg("-c").arg(card);
+    }
+    amixer.arg("sget").arg(control);
+    let output = run(sys, &mut amixer).await?;
+    let channels: Vec<_> = output
+        .lines()
+        .filter_map(|line| line.split_once(direction).map(|(_, fields)| fields))
+        .filter_map(|fields| {
+            let percent = fields.split_once('[')?.1.split_once("%]")?.0;
+            Some((percent.parse::<u32>().ok()?, fields.contains("[off]")))
+        })
+        .collect();
+    if channels.is_empty() {
+        bail!("amixer didn't print a volume for {control}");
+    }
+    let volume = channels.iter().map(|(v, _)| v).sum::<u32>() / channels.len() as u32;
+    Ok(Level {
+        volume,
+        muted: channels.iter().all(|(_, off)| *off),
+        device: String::new(),
+        port: None,
+        id: String::new(),
+    })
+}
+
+/// `sndioctl -n output.level output.mute`, or input for sources, prints a level from 0 to 1 and
+/// then 0 or 1 on separate lines
+async fn sndio_level(device: Device, sys: &Sys) -> Result<Level> {
+    let prefix = sndio_prefix(device);
+    let mut sndioctl = Command::new("sndioctl");
+    sndioctl
+        .arg("-n")
+        .arg(format!("{prefix}.level"))
+        .arg(format!("{prefix}.mute"));
+    let output = run(sys, &mut sndioctl).await?;
+    let mut lines = output.lines().map(str::trim);
+    let level: f64 = lines
+        .next()
+        .and_then(|l| l.parse().ok())
+        .context("sndioctl didn't print a level")?;
+    Ok(Level {
+        volume: (level * 100.0).round() as u32,
+        muted: lines.next() == Some("1"),
+        device: String::new(),
+        port: None,
+        id: String::new(),
+    })
+}
+
+/// `mixer -o vol.volume vol.mute` prints lines like "vol.volume=0.45:0.45"
+/// and "vol.mute=0", and sources are the "mic" control
+async fn mixer_level(device: Device, sys: &Sys) -> Result<Level> {
+    let control = mixer_control(device);
+    let volume = format!("{control}.volume");
+    let mute = format!("{control}.mute");
+    let mut mixer = Command::new("mixer");
+    mixer.arg("-o").arg(&volume).arg(&mute);
+    let output = run(sys, &mut mixer).await?;
+    let value = |control: &str| {
+        output
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(control)?.strip_prefix('='))
+    };
+    let level: f64 = value(&volume)
+        .and_then(|v| v.split(':').next()?.parse().ok())
+        .context("mixer didn't print a volume")?;
+    Ok(Level {
+        volume: (level * 100.0).round() as u32,
+        muted: value(&mute) == Some("1"),
+        device: String::new(),
+        port: None,
+        id: String::new(),
+    })
+}
+
+/// Left click mutes, scrolling changes the volume, and on PipeWire right
+/// click lists application volumes and scrolling while holding
+/// `app_modifier` changes the focused one's
+async fn handle_click(
+    config: &Config,
+    backend: Backend,
+    level: &Level,
+    sys: &Sys,
+    click: &ClickEvent,
+) -> Result<()> {
+    // Application volumes come from PipeWire
+    let apps = matches!(backend, Backend::Wireplumber | Backend::Pipewire)
+        && config.device == Device::Sink;
+    let app_scroll = apps && click.modifiers.contains(&config.app_modifier);
+    let mut command = match click.button {
+        BUTTON_RIGHT if apps => return show_streams(sys).await,
+        BUTTON_SCROLL_UP if app_scroll => return change_focused_app(config, sys, '+').await,
+        BUTTON_SCROLL_DOWN if app_scroll => return change_focused_app(config, sys, '-').await,
+        BUTTON_LEFT => toggle_mute(backend, config, level),
+        BUTTON_SCROLL_UP => change_volume(backend, config, level, '+'),
+        BUTTON_SCROLL_DOWN => change_volume(backend, config, level, '-'),
+        _ => return Ok(()),
+    };
+    run(sys, &mut command).await?;
+    Ok(())
+}
+
+/// `amixer sset` for the control the alsa backend reads
+fn amixer_set(config: &Config) -> Command {
+    let mut amixer = Command::new("amixer");
+    amixer.arg("-q");
+    if let Some(card) = &config.card {
+        amixer.arg("-c").arg(card);
+    }
+    amixer.arg("sset").arg(alsa_control(config));
+    amixer
+}
+
+fn alsa_control(config: &Config) -> &str {
+    let default = match config.device {
+        Device::Sink => "Master",
+        Device::Source => "Capture",
+    };
+    config.control.as_deref().unwrap_or(default)
+}
+
+fn sndio_prefix(device: Device) -> &'static str {
+    match device {
+        Device::Sink => "output",
+        Device::Source => "input",
+    }
+}
+
+fn mixer_control(device: Device) -> &'static str {
+    match device {
+        Device::Sink => "vol",
+        Device::Source => "mic",
+    }
+}
+
+fn toggle_mute(backend: Backend, config: &Config, level: &Level) -> Command {
+    let kind = config.device.pulseaudio();
+    match backend {
+        Backend::Pulseaudio => {
+            let mut pactl = pactl();
+            pactl
+                .arg(format!("set-{kind}-mute"))
+                .arg(&level.id)
+                .arg("toggle");
+            pactl
+        }
+        Backend::Alsa => {
+            let mut amixer = amixer_set(config);
+            amixer.arg("toggle");
+            amixer
+        }
+        Backend::Sndio => {
+            let mut sndioctl = Command::new("sndioctl");
+            sndioctl
+                .arg("-q")
+                .arg(format!("{}.mute=!", sndio_prefix(config.device)));
+            sndioctl
+        }
+        Backend::Mixer => {
+            let mut mixer = Command::new("mixer");
+            mixer.arg(format!("{}.mute=^", mixer_control(config.device)));
+            mixer
+        }
+        Backend::Wireplumber | Backend::Pipewire | Backend::Auto => {
+            let mut wpctl = Command::new("wpctl");
+            wpctl.arg("set-mute").arg(&level.id).arg("toggle");
+            wpctl
+        }
+    }
+}
+
+/// Change the volume by `step` percent, up to 100%. ALSA, sndio, and mixer
+/// stop there themselves, and wpctl is told to.
+fn change_volume(backend: Backend, config: &Config, level: &Level, direction: char) -> Command {
+    let step = config.step;
+    let fraction = f64::from(step) / 100.0;
+    match backend {
+        Backend::Pulseaudio => {
+            // pactl goes past 100% when asked, so the last step up only goes
+            // as far as 100%, and a volume already past it is left alone
+            let change = if direction == '+' && level.volume + step > 100 {
+                format!("{}%", level.volume.max(100))
+            } else {
+                format!("{direction}{step}%")
+            };
+            let mut pactl = pactl();
+            pactl
+                .arg(format!("set-{}-volume", config.device.pulseaudio()))
+                .arg(&level.id)
+                .arg(change);
+            pactl
+        }
+        Backend::Alsa => {
+            let mut amixer = amixer_set(config);
+            amixer.arg(format!("{step}%{direction}"));
+            amixer
+        }
+        Backend::Sndio => {
+            let mut sndioctl = Command::new("sndioctl");
+            let prefix = sndio_prefix(config.device);
+            sndioctl
+                .arg("-q")
+                .arg(format!("{prefix}.level={direction}{fraction}"));
+            sndioctl
+        }
+        Backend::Mixer => {
+            let mut mixer = Command::new("mixer");
+            let control = mixer_control(config.device);
+            mixer.arg(format!("{control}.volume={direction}{fraction}"));
+            mixer
+        }
+        Backend::Wireplumber | Backend::Pipewire | Backend::Auto => {
+            let mut wpctl = Command::new("wpctl");
+            wpctl
+                .arg("set-volume")
+                .arg("--limit")
+                .arg("1.0")
+                .arg(&level.id)
+                .arg(format!("{step}%{direction}"));
+            wpctl
+        }
+    }
+}
+
+/// Send a notification listing each application's volume
+async fn show_streams(sys: &Sys) -> Result<()> {
+    let streams = pipewire::streams(sys).await?;
+    let body = if streams.is_empty() {
+        tr("volume-nothing-playing", &[])
+    } else {
+        let lines: Vec<_> = streams
+            .iter()
+            .map(|s| {
+                let muted = if s.muted {
+                    format!(" {}", tr("volume-muted", &[]))
+                } else {
+                    String::new()
+                };
+                format!("{} {}%{muted}", s.app, s.volume)
+            })
+            .collect();
+        lines.join("\n")
+    };
+    notify(sys, &tr("volume-title", &[]), &body).await;
+    Ok(())
+}
+
+async fn change_focused_app(config: &Config, sys: &Sys, direction: char) -> Result<()> {
+    let Some(pid) = sway::focused_pid(sys).await? else {
+        return Ok(());
+    };
+    let change = format!("{}%{direction}", config.step);
+    for stream in pipewire::streams(sys).await? {
+        if stream.pid != Some(pid) {
+            continue;
+        }
+        let mut wpctl = Command::new("wpctl");
+        wpctl
+            .arg("set-volume")
+            .arg("--limit")
+            .arg("1.0")
+            .arg(stream.id.to_string())
+            .arg(&change);
+        let output = sys.output(&mut wpctl).await?;
+        if !output.status.success() {
+            bail!("wpctl failed with {}", output.status);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::testing::{click, render, start, start_paused};
+    use crate::click::{BUTTON_LEFT, BUTTON_RIGHT, BUTTON_SCROLL_UP};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_clicks_change_the_device_and_apps() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("wpctl get-volume @DEFAULT_AUDIO_SINK@", 0, "Volume: 0.45\n");
+        mock.command(
+            "pw-dump",
+            0,
+            r#"[
+                {"id": 30, "type": "PipeWire:Interface:Node", "info": {"props": {"media.class": "Audio/Sink"}}},
+                {"id": 57, "info": {
+                    "props": {"media.class": "Stream/Output/Audio", "application.name": "mpv", "application.process.id": 4242},
+                    "params": {"Props": [{"mute": false, "channelVolumes": [0.125, 0.125]}]}
+                }},
+                {"id": 61, "info": {
+                    "props": {"media.class": "Stream/Output/Audio", "application.name": "Firefox", "application.process.id": "1000"},
+                    "params": {"Props": [{"mute": true, "channelVolumes": [1.0]}]}
+                }}
+            ]"#,
+        );
+        mock.command(
+            "swaymsg -t get_tree",
+            0,
+            r#"{"focused": false, "nodes": [{"focused": false, "nodes": [], "floating_nodes": [{"focused": true, "pid": 4242}]}]}"#,
+        );
+        mock.command(
+            "notify-send --app-name=subar Volume mpv 50%\nFirefox 100% (muted)",
+            0,
+            "",
+        );
+        mock.command("wpctl set-volume --limit 1.0 57 2%+", 0, "");
+        mock.command(
+            "wpctl set-volume --limit 1.0 @DEFAULT_AUDIO_SINK@ 2%+",
+            0,
+            "",
+        );
+        mock.command("wpctl set-mute @DEFAULT_AUDIO_SINK@ toggle", 0, "");
+        let mut tasks = start("[[block]]\nblock = \"volume\"", &mock);
+        render(&mut tasks, &mock).await;
+
+        click(&tasks[0], BUTTON_RIGHT, &[]).await;
+        click(&tasks[0], BUTTON_SCROLL_UP, &[]).await;
+        click(&tasks[0], BUTTON_SCROLL_UP, &["Shift"]).await;
+        click(&tasks[0], BUTTON_LEFT, &[]).await;
+        sleep(Duration::from_secs(2)).await;
+        let ran = mock.ran.lock().unwrap();
+        let ran: Vec<_> = ran
+            .iter()
+            .filter(|c| !c.starts_with("wpctl get-volume") && *c != "pactl subscribe")
+            .collect();
+        assert_eq!(
+            ran,
+            [
+                "pw-dump",
+                "notify-send --app-name=subar Volume mpv 50%\nFirefox 100% (muted)",
+                "wpctl set-volume --limit 1.0 @DEFAULT_AUDIO_SINK@ 2%+",
+                "swaymsg -t get_tree",
+                "pw-dump",
+                "wpctl set-volume --limit 1.0 57 2%+",
+                "wpctl set-mute @DEFAULT_AUDIO_SINK@ toggle",
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_follows_pipewire_changes() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let sink = |id: u32, name: &str, volume: f64| {
+            format!(
+                r#"{{"id": {id}, "type": "PipeWire:Interface:Node", "info": {{
+                    "props": {{"node.name": "{name}", "media.class": "Audio/Sink"}},
+                    "params": {{"Props": [{{"mute": false, "channelVolumes": [{volume}, {volume}]}}]}}
+                }}}}"#
+            )
+        };
+        let default = |name: &str| {
+            format!(
+                r#"{{"id": 40, "metadata": [{{"subject": 0, "key": "default.audio.sink", "value": {{"name": "{name}"}}}}]}}"#
+            )
+        };
+        let dump = format!(
+            r#"[
+                {{"id": 40, "type": "PipeWire:Interface:Metadata", "props": {{"metadata.name": "default"}}, "metadata": [
+                    {{"subject": 0, "key": "default.audio.source", "value": {{"name": "alsa_input.usb"}}}},
+                    {{"subject": 0, "key": "default.audio.sink", "value": {{"name": "alsa_output.usb"}}}}
+                ]}},
+                {}
+            ]
+            [{{"id": 30, "info": {{"params": {{"Props": [{{"mute": true, "channelVolumes": [0.125, 0.125]}}]}}}}}}]
+            [{}, {}]
+            [{{"id": 70, "info": null}}, {}]
+            "#,
+            sink(30, "alsa_output.usb", 0.125),
+            sink(70, "bluez_output", 0.729),
+            default("bluez_output"),
+            default("alsa_output.usb"),
+        );
+        mock.stream("pw-dump --monitor", dump.into_bytes());
+        let mut tasks = start(
+            "[[block]]\nblock = \"volume\"\nbackend = \"pipewire\"",
+            &mock,
+        );
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔇 50% ","name":"volume""#));
+        assert!(!mock
+            .ran
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.starts_with("wpctl")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pw_dump_is_left_unread_while_paused() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let dump = r#"[
+            {"id": 40, "type": "PipeWire:Interface:Metadata", "props": {"metadata.name": "default"}, "metadata": [
+                {"subject": 0, "key": "default.audio.sink", "value": {"name": "alsa_output.usb"}}
+            ]},
+            {"id": 30, "type": "PipeWire:Interface:Node", "info": {
+                "props": {"node.name": "alsa_output.usb", "media.class": "Audio/Sink"},
+                "params": {"Props": [{"mute": false, "channelVolumes": [0.125, 0.125]}]}
+            }}
+        ]"#;
+        mock.stream("pw-dump --monitor", dump.as_bytes().to_vec());
+        let (pause, paused) = watch::channel(true);
+        let config = "[[block]]\nblock = \"volume\"\nbackend = \"pipewire\"";
+        let mut tasks = start_paused(config, &mock, &paused);
+        sleep(Duration::from_secs(60)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(!line.contains("50%"), "{line}");
+
+        pause.send_replace(false);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 50% ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_falls_back_to_pulseaudio() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "pactl get-sink-volume @DEFAULT_SINK@",
+            0,
+            concat!(
+                "Volume: front-left: 29491 /  45% / -20.81 dB,   front-right: 29491 /  45% / -20.81 dB\n",
+                "        balance 0.00\n",
+            ),
+        );
+        mock.command("pactl get-sink-mute @DEFAULT_SINK@", 0, "Mute: no\n");
+        mock.stream(
+            "pactl subscribe",
+            b"Event 'change' on sink-input #12\nEvent 'change' on sink #54\n".to_vec(),
+        );
+        let mut tasks = start("[[block]]\nblock = \"volume\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% ","name":"volume""#));
+        let ran = mock.ran.lock().unwrap();
+        let reads = ran.iter().filter(|c| c.contains("get-sink-volume"));
+        // Once to find the backend, and once for the change to the sink
+        assert_eq!(reads.count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pulseaudio_volume_stops_at_100() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let volume = |percent: u32| {
+            mock.command(
+                "pactl get-sink-volume @DEFAULT_SINK@",
+                0,
+                &format!("Volume: front-left: 0 /  {percent}% / 0 dB,   front-right: 0 /  {percent}% / 0 dB\n"),
+            );
+        };
+        volume(99);
+        mock.command("pactl get-sink-mute @DEFAULT_SINK@", 0, "Mute: no\n");
+        let config = "[[block]]\nblock = \"volume\"\nbackend = \"pulseaudio\"";
+        let mut tasks = start(config, &mock);
+        render(&mut tasks, &mock).await;
+        click(&tasks[0], BUTTON_SCROLL_UP, &[]).await;
+        render(&mut tasks, &mock).await;
+        volume(120);
+        sleep(Duration::from_secs(1)).await;
+        click(&tasks[0], BUTTON_SCROLL_UP, &[]).await;
+        render(&mut tasks, &mock).await;
+        volume(45);
+        sleep(Duration::from_secs(1)).await;
+        click(&tasks[0], BUTTON_SCROLL_UP, &[]).await;
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        let changes: Vec<_> = ran
+            .iter()
+            .filter(|c| c.starts_with("pactl set-sink-volume"))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "pactl set-sink-volume @DEFAULT_SINK@ 100%",
+                "pactl set-sink-volume @DEFAULT_SINK@ 120%",
+                "pactl set-sink-volume @DEFAULT_SINK@ +2%",
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_can_show_every_running_sink() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "pactl get-sink-volume @DEFAULT_SINK@",
+            0,
+            "Volume: front-left: 29491 /  45% / -20.81 dB,   front-right: 29491 /  45% / -20.81 dB\n",
+        );
+        mock.command("pactl get-sink-mute @DEFAULT_SINK@", 0, "Mute: no\n");
+        mock.command("pactl get-default-sink", 0, "speakers\n");
+        mock.command(
+            "pactl list sinks",
+            0,
+            concat!(
+                "Sink #1\n\tState: RUNNING\n\tName: speakers\n\tDescription: Speakers\n",
+                "\tMute: no\n\tVolume: front-left: 29491 /  45% / -20.81 dB\n",
+                "Sink #2\n\tState: RUNNING\n\tName: headset\n\tDescription: Headset\n",
+                "\tMute: no\n\tVolume: front-left: 58982 /  90% / -2.75 dB\n",
+                "Sink #3\n\tState: SUSPENDED\n\tName: hdmi\n\tDescription: HDMI\n",
+                "\tMute: yes\n\tVolume: front-left: 65536 / 100% / 0.00 dB\n",
+            ),
+        );
+        let config = "[[block]]\nblock = \"volume\"\nbackend = \"pulseaudio\"\nshow = \"running\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% 🔊 90% ","name":"volume""#));
+        let mut tasks = start(&format!("{config}\ncompact = true"), &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% +1 ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_is_read_when_it_changes() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("wpctl get-volume @DEFAULT_AUDIO_SINK@", 0, "Volume: 0.45\n");
+        mock.stream(
+            "pactl subscribe",
+            b"Event 'new' on client #99\nEvent 'change' on sink #54\n".to_vec(),
+        );
+        let mut tasks = start("[[block]]\nblock = \"volume\"", &mock);
+        sleep(Duration::from_secs(5)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% ","name":"volume""#));
+        let ran = mock.ran.lock().unwrap();
+        let reads = ran.iter().filter(|c| c.starts_with("wpctl get-volume"));
+        // Once when it starts, and once for the change to the sink
+        assert_eq!(reads.count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_over_100_and_odd_output() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let wpctl = "wpctl get-volume @DEFAULT_AUDIO_SINK@";
+        mock.command(wpctl, 0, "Volume: 1.32\n");
+        let mut tasks = start("[[block]]\nblock = \"volume\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔊 132% ","name":"volume""#));
+        mock.command(wpctl, 0, "Volume: 0.5\n");
+        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 50% ","name":"volume""#));
+        mock.command(wpctl, 0, "Could not connect to PipeWire\n");
+        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔊 ??? ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_icons_follow_thresholds() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let wpctl = "wpctl get-volume @DEFAULT_AUDIO_SINK@";
+        mock.command(wpctl, 0, "Volume: 0.45\n");
+        // Nerd Font icons
+        let config = r#"
+            [[block]]
+            block = "volume"
+            icons = ["\uf026", "\uf027", "\uf028"]
+            thresholds = [20, 50]
+            muted_icon = "\ueee8"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with("[{\"full_text\":\"\u{f027} 45% \""));
+        mock.command(wpctl, 0, "Volume: 0.50 [MUTED]\n");
+        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with("[{\"full_text\":\"\u{eee8} 50% \""));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_can_show_the_microphone() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("wpctl get-volume @DEFAULT_AUDIO_SINK@", 0, "Volume: 0.45\n");
+        let source = "wpctl get-volume @DEFAULT_AUDIO_SOURCE@";
+        mock.command(source, 0, "Volume: 0.30 [MUTED]\n");
+        let mut tasks = start("[[block]]\nblock = \"volume\"\ndevice = \"source\"", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🤐 30% ","name":"volume""#));
+        mock.command(source, 0, "Volume: 0.30\n");
+        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🎙️ 30% ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_can_target_a_device() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "pw-dump",
+            0,
+            r#"[
+                {"id": 30, "info": {
+                    "props": {"node.name": "alsa_output.pci", "node.description": "Built-in Audio", "media.class": "Audio/Sink"},
+                    "params": {"Props": [{"mute": false, "channelVolumes": [1.0]}]}
+                }},
+                {"id": 54, "info": {
+                    "props": {"node.name": "alsa_output.usb", "node.description": "USB DAC Analog Stereo", "media.class": "Audio/Sink"},
+                    "params": {"Props": [{"mute": false, "channelVolumes": [0.091]}]}
+                }}
+            ]"#,
+        );
+        mock.command("wpctl get-volume 54", 0, "Volume: 0.45\n");
+        let config = r#"
+            [[block]]
+            block = "volume"
+            backend = "wireplumber"
+            target = "usb dac"
+            format = "{icon} {volume}% {name}"
+        "#;
+        let mut tasks = start(config, &mock);
+        render(&mut tasks, &mock).await;
+        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(
+            line.starts_with(r#"[{"full_text":"🔉 45% USB DAC Analog Stereo ","name":"volume""#)
+        );
+        let ran = mock.ran.lock().unwrap();
+        assert_eq!(ran.iter().filter(|c| *c == "pw-dump").count(), 1);
+        assert_eq!(
+            ran.iter().filter(|c| *c == "wpctl get-volume 54").count(),
+            2
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_shows_the_active_port() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "pactl list sinks",
+            0,
+            concat!(
+                "Sink #54\n",
+                "\tState: RUNNING\n",
+                "\tName: alsa_output.pci-0000_00_1f.3.analog-stereo\n",
+                "\tDescription: Built-in Audio Analog Stereo\n",
+                "\tPorts:\n",
+                "\t\tanalog-output-speaker: Speakers (type: Speaker, priority: 10000, not available)\n",
+                "\t\tanalog-output-headphones: Headphones (type: Headphones, priority: 9900, available)\n",
+                "\tActive Port: analog-output-headphones\n",
+                "\tFormats:\n",
+                "\t\tpcm\n",
+                "\n",
+                "Sink #61\n",
+                "\tName: bluez_output.00_11_22\n",
+                "\tDescription: WH-1000XM4\n",
+            ),
+        );
+        let sink = "alsa_output.pci-0000_00_1f.3.analog-stereo";
+        mock.command("pactl get-default-sink", 0, &format!("{sink}\n"));
+        mock.command(
+            &format!("pactl get-sink-volume {sink}"),
+            0,
+            "Volume: front-left: 29491 /  45% / -20.81 dB,   front-right: 29491 /  45% / -20.81 dB\n",
+        );
+        mock.command(&format!("pactl get-sink-mute {sink}"), 0, "Mute: no\n");
+        let config = r#"
+            [[block]]
+            block = "volume"
+            backend = "pulseaudio"
+            format = "{icon} {volume}% {port}"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% Headphones ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_reads_alsa_controls() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command(
+            "amixer -c PCH sget PCM",
+            0,
+            concat!(
+                "Simple mixer control 'PCM',0\n",
+                "  Capabilities: pvolume pswitch\n",
+                "  Playback channels: Front Left - Front Right\n",
+                "  Limits: Playback 0 - 255\n",
+                "  Mono:\n",
+                "  Front Left: Playback 153 [60%] [-20.00dB] [off]\n",
+                "  Front Right: Playback 143 [56%] [-22.00dB] [off]\n",
+            ),
+        );
+        let config = r#"
+            [[block]]
+            block = "volume"
+            backend = "alsa"
+            card = "PCH"
+            control = "PCM"
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔇 58% ","name":"volume""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn volume_from_sndio_and_mixer() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sndioctl -n output.level output.mute", 0, "0.452\n1\n");
+        mock.command(
+            "mixer -o vol.volume vol.mute",
+            0,
+            "vol.volume=0.80:0.75\nvol.mute=0\n",
+        );
+        let config = "[[block]]\nblock = \"volume\"\nbackend = \"sndio\"\n\
+                      [[block]]\nblock = \"volume\"\nbackend = \"mixer\"";
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🔇 45% ","name":"volume","instance":"0""#));
+        assert!(line.contains(r#"{"full_text":"🔊 80% ","name":"volume","instance":"1""#));
+    }
+}
@@ -0,0 +1,1231 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::cache::Cache;
+use crate::events::Events;
+use crate::format::Template;
+use crate::http;
+use crate::location::{Locate, Location};
+use crate::output::BlockOutput;
+use crate::secret::Secret;
+use crate::system::{BoxFuture, Sys};
+
+pub static WEATHER_FALLBACK: &str = "🛰️ ???";
+static WEATHER_UPDATE_FREQUENCY: u64 = 5137;
+/// How often to fetch from a web API, which only updates every 10 minutes or
+/// so
+static WEATHER_FETCH_FREQUENCY: u64 = 10 * 60 * 1000;
+static WEATHER_MAX_RETRY: u64 = 5 * 60 * 1000;
+/// The oldest cached weather that's shown, marked stale, until it's fetched
+/// again
+static WEATHER_MAX_STALE: u64 = 24 * 60 * 60;
+static BOM_URL: &str = "https://api.weather.bom.gov.au/v1/locations";
+static OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+static OPENWEATHERMAP_URL: &str = "https://api.openweathermap.org/data/2.5";
+/// Where the OpenWeatherMap API key is read from if `api_key` isn't set
+static OPENWEATHERMAP_ENV: &str = "OPENWEATHERMAP_API_KEY";
+static WTTR_URL: &str = "https://wttr.in";
+/// The condition, temperature, feels like, humidity, wind, UV index, and
+/// description, in metric units
+static WTTR_FORMAT: &str = "format=%c+%t+%f+%h+%w+%u+%C&m";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub provider: Provider,
+    /// Where to show the weather for. A suburb, town, or postcode for the
+    /// "bom" provider, e.g. "Hobart" or "3000", or a city for
+    /// "openweathermap", e.g. "Berlin,DE". Anything wttr.in understands for
+    /// "wttr", which guesses from your IP address without one.
+    pub location: Option<String>,
+    /// Where to show the weather for with the "bom", "open_meteo", and
+    /// "openweathermap" providers when there's no `location`
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// How to find where the bar is without a location or coordinates:
+    /// "auto" for GeoClue and then the IP address, "geoclue", or "ip"
+    pub locate: Locate,
+    /// Needed for "openweathermap". Read from $OPENWEATHERMAP_API_KEY if
+    /// it's not set.
+    pub api_key: Option<Secret>,
+    /// How the conditions are shown: "emoji", or "nerd_font" for glyphs
+    /// from a Nerd Font's weather icons
+    pub icons: Icons,
+    /// Have bom-buddy check for new observations rather than relying on a
+    /// separate `bom-buddy monitor` process.
+    pub check: bool,
+    /// Seconds to keep showing the last weather after a restart before
+    /// fetching it again. 0 disables the cache.
+    pub cache_ttl: u64,
+    /// Placeholders: weather, icon, temp, feels_like, humidity, wind, rain,
+    /// max, min, uv, uv_max, description, station, hourly, warnings. Only
+    /// weather comes from bom-buddy, and station and warnings only from the
+    /// BOM.
+    pub format: Template,
+    /// The colour of the block from each UV index up, while the format shows
+    /// `{uv}` or `{uv_max}`
+    pub uv_colors: Vec<UvColor>,
+    /// How many hours of the forecast `{hourly}` shows. The forecast isn't
+    /// fetched while this is 0.
+    pub hours: usize,
+    /// How many hours apart they are
+    pub hour_step: usize,
+    /// How each hour is shown. Placeholders: icon, temp, rain (only when
+    /// there's a chance of rain), time
+    pub hour_format: Template,
+    /// How `{time}` is shown in `hour_format`, as a strftime format
+    pub time_format: String,
+    /// Put between each hour
+    pub hour_separator: String,
+    /// Put before the weather while there are warnings, which also make the
+    /// block urgent
+    pub warning_icon: String,
+    /// Include the BOM's minor warnings, like for frost or marine winds, as
+    /// well as the major ones
+    pub minor_warnings: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            provider: Provider::default(),
+            location: None,
+            latitude: None,
+            longitude: None,
+            locate: Locate::default(),
+            api_key: None,
+            icons: Icons::default(),
+            check: false,
+            cache_ttl: 300,
+            format: Template::parse("{weather}").unwrap(),
+            uv_colors: vec![
+                UvColor::new(0.0, "#88cc44"),
+                UvColor::new(3.0, "#ffaa00"),
+                UvColor::new(8.0, "#ff4444"),
+            ],
+            hours: 0,
+            hour_step: 3,
+            hour_format: Template::parse("{temp}° {time}[, rain {rain}%]").unwrap(),
+            time_format: "%H:%M".to_string(),
+            hour_separator: " → ".to_string(),
+            warning_icon: "⚠️".to_string(),
+            minor_warnings: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UvColor {
+    pub from: f64,
+    pub color: String,
+}
+
+impl UvColor {
+    fn new(from: f64, color: &str) -> Self {
+        Self {
+            from,
+            color: color.to_string(),
+        }
+    }
+}
+
+/// Where the weather comes from
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    /// Whatever `bom-buddy current` prints
+    #[default]
+    BomBuddy,
+    /// The Bureau of Meteorology's observations and forecasts, fetched
+    /// directly
+    Bom,
+    /// Open-Meteo's forecast, which covers anywhere and needs no API key
+    OpenMeteo,
+    /// OpenWeatherMap's current weather, which needs an API key
+    Openweathermap,
+    /// wttr.in's one-line format, which needs no API key or coordinates
+    Wttr,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Icons {
+    #[default]
+    Emoji,
+    NerdFont,
+}
+
+/// What the weather is like, in the terms every provider can be mapped to
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Clear,
+    MostlyClear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Showers,
+    Rain,
+    Storm,
+    Snow,
+    Wind,
+    Frost,
+    Cyclone,
+}
+
+impl Condition {
+    fn icon(self, night: bool, icons: Icons) -> &'static str {
+        use Condition::*;
+        match icons {
+            Icons::Emoji => match self {
+                Clear | MostlyClear if night => "🌙",
+                Clear => "☀️",
+                MostlyClear => "🌤️",
+                PartlyCloudy => "⛅",
+                Cloudy => "☁️",
+                Fog => "🌫️",
+                Showers => "🌦️",
+                Rain => "🌧️",
+                Storm => "⛈️",
+                Snow => "🌨️",
+                Wind => "💨",
+                Frost => "❄️",
+                Cyclone => "🌀",
+            },
+            Icons::NerdFont => match self {
+                Clear if night => "\u{e32b}",
+                Clear => "\u{e30d}",
+                MostlyClear if night => "\u{e379}",
+                MostlyClear => "\u{e30c}",
+                PartlyCloudy if night => "\u{e37e}",
+                PartlyCloudy => "\u{e302}",
+                Cloudy => "\u{e312}",
+                Fog => "\u{e313}",
+                Showers => "\u{e319}",
+                Rain => "\u{e318}",
+                Storm => "\u{e31d}",
+                Snow => "\u{e31a}",
+                Wind => "\u{e34b}",
+                Frost => "\u{e36f}",
+                Cyclone => "\u{e36e}",
+            },
+        }
+    }
+}
+
+/// Shown for conditions a provider doesn't describe in a way we know
+fn unknown_icon(icons: Icons) -> &'static str {
+    match icons {
+        Icons::Emoji => "🌡️",
+        Icons::NerdFont => "\u{e350}",
+    }
+}
+
+impl Provider {
+    fn build(self, config: &Config) -> Box<dyn WeatherProvider> {
+        let coordinates = || Location::new(config.latitude, config.longitude, config.locate);
+        let hourly = config.hours > 0;
+        match self {
+            Self::BomBuddy => Box::new(BomBuddy {
+                check: config.check,
+            }),
+            Self::Bom => Box::new(Bom {
+                location: config.location.clone(),
+                coordinates: coordinates(),
+                geohash: None,
+                hourly,
+                minor_warnings: config.minor_warnings,
+            }),
+            Self::OpenMeteo => Box::new(OpenMeteo {
+                coordinates: coordinates(),
+                hourly,
+            }),
+            Self::Openweathermap => Box::new(OpenWeatherMap {
+                api_key: config.api_key.clone(),
+                coordinates: coordinates(),
+                location: config.location.clone(),
+                hourly,
+            }),
+            Self::Wttr => Box::new(Wttr {
+                location: config.location.clone(),
+            }),
+        }
+    }
+}
+
+/// A source of weather, chosen with `provider`. Providers leave out anything
+/// they don't report.
+pub trait WeatherProvider: Send {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>>;
+
+    /// Today's forecast, for providers that have one
+    fn fetch_forecast<'a>(&'a mut self, _sys: &'a Sys) -> BoxFuture<'a, Result<Forecast>> {
+        Box::pin(async { Ok(Forecast::default()) })
+    }
+
+    /// The titles of the warnings in effect, for providers that issue them
+    fn fetch_warnings<'a>(&'a mut self, _sys: &'a Sys) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// How long to wait between fetches
+    fn frequency(&self) -> Duration {
+        Duration::from_millis(WEATHER_FETCH_FREQUENCY)
+    }
+}
+
+/// The current conditions
+#[derive(Default, PartialEq, Serialize, Deserialize)]
+pub struct Current {
+    /// Shown as the weather instead of the icon and temperature
+    pub summary: Option<String>,
+    pub condition: Option<Condition>,
+    pub night: bool,
+    /// Degrees Celsius
+    pub temp: Option<f64>,
+    pub feels_like: Option<f64>,
+    /// Relative humidity as a percent
+    pub humidity: Option<f64>,
+    /// Kilometres per hour
+    pub wind: Option<f64>,
+    pub uv: Option<f64>,
+    pub description: Option<String>,
+    pub station: Option<String>,
+}
+
+/// Today's forecast. The condition, description, and night are used when
+/// the current conditions don't have them, for providers that forecast
+/// rather than observe them.
+#[derive(Default, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub condition: Option<Condition>,
+    pub night: bool,
+    pub description: Option<String>,
+    /// Degrees Celsius
+    pub max: Option<f64>,
+    pub min: Option<f64>,
+    /// The chance of rain as a percent
+    pub rain: Option<f64>,
+    /// The highest UV index
+    pub uv: Option<f64>,
+    /// The coming hours, if `hours` asks for them
+    #[serde(default)]
+    pub hours: Vec<Hour>,
+}
+
+/// An hour of the forecast
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct Hour {
+    /// Seconds since the Unix epoch
+    pub time: i64,
+    pub condition: Option<Condition>,
+    pub night: bool,
+    /// Degrees Celsius
+    pub temp: Option<f64>,
+    /// The chance of rain as a percent
+    pub rain: Option<f64>,
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+struct Weather {
+    current: Current,
+    today: Forecast,
+    #[serde(default)]
+    warnings: Vec<String>,
+}
+
+pub async fn weather_task(
+    config: Config,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    events: Events,
+) -> Result<()> {
+    let provider = config.provider.build(&config);
+    show_weather(config, provider, sys, tx, events).await
+}
+
+/// Show the weather from any provider, ignoring `config.provider`
+pub async fn show_weather(
+    config: Config,
+    mut provider: Box<dyn WeatherProvider>,
+    sys: Sys,
+    tx: watch::Sender<BlockOutput>,
+    mut events: Events,
+) -> Result<()> {
+    let cache = Cache::new(&sys, "weather", Duration::from_secs(config.cache_ttl));
+    let mut last = None;
+    // Whether old weather from the cache is shown, which is better than
+    // nothing until the first fetch works
+    let mut showing_stale = false;
+    if let Some(cache) = &cache {
+        if let Some(cached) = cache.load_expired::<Weather>().await {
+            if !cached.expires_in.is_zero() {
+                tx.send(render(&config, &cached.value, sys.now()))?;
+                last = Some(cached.value);
+                events.wait(cached.expires_in).await;
+            } else if cached.age < Duration::from_secs(WEATHER_MAX_STALE) {
+                tx.send(BlockOutput {
+                    stale: true,
+                    ..render(&config, &cached.value, sys.now())
+                })?;
+                last = Some(cached.value);
+                showing_stale = true;
+            }
+        }
+    }
+    let mut backoff = Backoff::new(
+        provider.frequency(),
+        Duration::from_millis(WEATHER_MAX_RETRY),
+    );
+    loop {
+        let current = match provider.fetch_current(&sys).await {
+            Ok(current) => current,
+            Err(err) => {
+                eprintln!("Couldn't get the weather. {err:#}");
+                if !showing_stale {
+                    tx.send(WEATHER_FALLBACK.into())?;
+                }
+                events.wait(backoff.fail()).await;
+                continue;
+            }
+        };
+        showing_stale = false;
+        // The current conditions are still worth showing without it
+        let today = match provider.fetch_forecast(&sys).await {
+            Ok(today) => today,
+            Err(err) => {
+                eprintln!("Couldn't get the forecast. {err:#}");
+                Forecast::default()
+            }
+        };
+        let warnings = match provider.fetch_warnings(&sys).await {
+            Ok(warnings) => warnings,
+            Err(err) => {
+                eprintln!("Couldn't get the weather warnings. {err:#}");
+                Vec::new()
+            }
+        };
+        backoff.reset();
+        let weather = Weather {
+            current,
+            today,
+            warnings,
+        };
+        tx.send(render(&config, &weather, sys.now()))?;
+        if let Some(cache) = &cache {
+            if last.as_ref() != Some(&weather) {
+                cache.store(&weather).await;
+            }
+        }
+        last = Some(weather);
+        events.wait(provider.frequency()).await;
+    }
+}
+
+fn render(config: &Config, weather: &Weather, now: DateTime<Local>) -> BlockOutput {
+    let Weather {
+        current,
+        today,
+        warnings,
+    } = weather;
+    let degrees = |value: Option<f64>| value.map(|v| format!("{v:.1}"));
+    let whole = |value: Option<f64>| value.map(|v| format!("{v:.0}"));
+    let icon = match (current.condition, today.condition) {
+        (Some(condition), _) => Some(condition.icon(current.night, config.icons)),
+        (None, Some(condition)) => Some(condition.icon(today.night, config.icons)),
+        (None, None) => None,
+    };
+    // The current UV index is only known from some providers
+    let uv = current.uv.or(today.uv);
+    let status = config.format.render(|name| match name {
+        "weather" => current.summary.clone().or_else(|| {
+            let icon = icon.unwrap_or(unknown_icon(config.icons));
+            Some(format!("{icon} {}°C", degrees(current.temp)?))
+        }),
+        "icon" => icon.map(str::to_string),
+        "temp" => degrees(current.temp),
+        "feels_like" => degrees(current.feels_like),
+        "humidity" => whole(current.humidity),
+        "wind" => whole(current.wind),
+        "rain" => whole(today.rain),
+        "max" => whole(today.max),
+        "min" => whole(today.min),
+        "uv" => whole(uv),
+        "uv_max" => whole(today.uv),
+        "description" => current
+            .description
+            .clone()
+            .or_else(|| today.description.clone()),
+        "station" => current.station.clone(),
+        "hourly" => hourly(config, &today.hours, now),
+        "warnings" => (!warnings.is_empty()).then(|| warnings.join(", ")),
+        _ => None,
+    });
+    let shown_uv = if config.format.uses("uv") {
+        uv
+    } else if config.format.uses("uv_max") {
+        today.uv
+    } else {
+        None
+    };
+    let color = shown_uv.and_then(|uv| {
+        let color = config
+            .uv_colors
+            .iter()
+            .rev()
+            .find(|c| uv.round() >= c.from)?;
+        Some(color.color.clone())
+    });
+    if !warnings.is_empty() {
+        return BlockOutput {
+            full_text: format!("{} {status}", config.warning_icon),
+            color,
+            urgent: true,
+            ..BlockOutput::default()
+        };
+    }
+    BlockOutput {
+        full_text: status,
+        color,
+        ..BlockOutput::default()
+    }
+}
+
+/// The next `hours` hours after now, `hour_step` apart
+fn hourly(config: &Config, hours: &[Hour], now: DateTime<Local>) -> Option<String> {
+    let shown: Vec<_> = hours
+        .iter()
+        .filter(|hour| hour.time > now.timestamp())
+        .step_by(config.hour_step.max(1))
+        .take(config.hours)
+        .map(|hour| {
+            let time = DateTime::from_timestamp(hour.time, 0).map(|t| t.with_timezone(&Local));
+            config.hour_format.render(|name| match name {
+                "icon" => Some(
+                    match hour.condition {
+                        Some(condition) => condition.icon(hour.night, config.icons),
+                        None => unknown_icon(config.icons),
+                    }
+                    .to_string(),
+                ),
+                "temp" => hour.temp.map(|t| format!("{t:.0}")),
+                "rain" => hour.rain.filter(|r| *r > 0.0).map(|r| format!("{r:.0}")),
+                "time" => time.map(|t| t.format(&config.time_format).to_string()),
+                _ => None,
+            })
+        })
+        .collect();
+    (!shown.is_empty()).then(|| shown.join(&config.hour_separator))
+}
+
+async fn fetch_json(sys: &Sys, url: &str) -> Result<Value> {
+    Ok(serde_json::from_str(&sys.http_get(url, Vec::new()).await?)?)
+}
+
+/// Whatever `bom-buddy current` prints
+struct BomBuddy {
+    check: bool,
+}
+
+impl WeatherProvider for BomBuddy {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+        Box::pin(async move {
+            let mut bom_buddy = Command::new("bom-buddy");
+            bom_buddy.arg("current");
+            if self.check {
+                bom_buddy.arg("--check");
+            }
+            let output = sys.output(&mut bom_buddy).await?;
+            if !output.status.success() {
+                bail!("bom-buddy failed with {}", output.status);
+            }
+            Ok(Current {
+                summary: Some(String::from_utf8(output.stdout)?),
+                ..Current::default()
+            })
+        })
+    }
+
+    /// bom-buddy is local, and only reads the BOM when it needs to
+    fn frequency(&self) -> Duration {
+        Duration::from_millis(WEATHER_UPDATE_FREQUENCY)
+    }
+}
+
+/// The latest observations and today's forecast from the BOM's API
+struct Bom {
+    location: Option<String>,
+    /// Used without a location, for the nearest place the BOM forecasts
+    coordinates: Location,
+    geohash: Option<String>,
+    hourly: bool,
+    minor_warnings: bool,
+}
+
+impl Bom {
+    /// The location's geohash, which is searched for the first time, or
+    /// worked out from the coordinates
+    async fn geohash(&mut self, sys: &Sys) -> Result<&str> {
+        match &self.location {
+            Some(location) if self.geohash.is_none() => {
+                let url = format!("{BOM_URL}?search={}", http::encode(location));
+                let found = fetch_json(sys, &url).await?;
+                let found = found["data"][0]["geohash"]
+                    .as_str()
+                    .with_context(|| format!("The BOM doesn't know {location:?}"))?;
+                self.geohash = Some(found.to_string());
+            }
+            Some(_) => {}
+            None => {
+                let (latitude, longitude) = self.coordinates.coordinates(sys).await?;
+                self.geohash = Some(geohash(latitude, longitude));
+            }
+        }
+        Ok(self.geohash.as_deref().unwrap_or_default())
+    }
+}
+
+impl WeatherProvider for Bom {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+        Box::pin(async move {
+            let geohash = self.geohash(sys).await?;
+            // Observations are only available for the surrounding area
+            let area = &geohash[..geohash.len().min(6)];
+            let observations = fetch_json(sys, &format!("{BOM_URL}/{area}/observations")).await?;
+            let now = &observations["data"];
+            Ok(Current {
+                temp: now["temp"].as_f64(),
+                feels_like: now["temp_feels_like"].as_f64(),
+                humidity: now["humidity"].as_f64(),
+                wind: now["wind"]["speed_kilometre"].as_f64(),
+                station: now["station"]["name"].as_str().map(str::to_string),
+                ..Current::default()
+            })
+        })
+    }
+
+    fn fetch_forecast<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Forecast>> {
+        Box::pin(async move {
+            let hourly = self.hourly;
+            let geohash = self.geohash(sys).await?;
+            let forecasts =
+                fetch_json(sys, &format!("{BOM_URL}/{geohash}/forecasts/daily")).await?;
+            let today = &forecasts["data"][0];
+            let mut hours = Vec::new();
+            if hourly {
+                let url = format!("{BOM_URL}/{geohash}/forecasts/hourly");
+                let hourly = fetch_json(sys, &url).await?;
+                for hour in hourly["data"].as_array().into_iter().flatten() {
+                    let Some(time) = hour["time"].as_str() else {
+                        continue;
+                    };
+                    let Ok(time) = DateTime::parse_from_rfc3339(time) else {
+                        continue;
+                    };
+                    hours.push(Hour {
+                        time: time.timestamp(),
+                        condition: hour["icon_descriptor"].as_str().and_then(bom_condition),
+                        night: hour["is_night"].as_bool().unwrap_or(false),
+                        temp: hour["temp"].as_f64(),
+                        rain: hour["rain"]["chance"].as_f64(),
+                    });
+                }
+            }
+            Ok(Forecast {
+                condition: today["icon_descriptor"].as_str().and_then(bom_condition),
+                night: today["now"]["is_night"].as_bool().unwrap_or(false),
+                description: today["short_text"]
+                    .as_str()
+                    .map(|text| text.trim_end_matches('.').to_string()),
+                max: today["temp_max"].as_f64(),
+                min: today["temp_min"].as_f64(),
+                rain: today["rain"]["chance"].as_f64(),
+                uv: today["uv"]["max_index"].as_f64(),
+                hours,
+            })
+        })
+    }
+
+    fn fetch_warnings<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let minor = self.minor_warnings;
+            let geohash = self.geohash(sys).await?;
+            let warnings = fetch_json(sys, &format!("{BOM_URL}/{geohash}/warnings")).await?;
+            let warnings = warnings["data"].as_array().into_iter().flatten();
+            Ok(warnings
+                .filter(|warning| warning["phase"] != "cancelled")
+                .filter(|warning| minor || warning["warning_group_type"] == "major")
+                .filter_map(|warning| warning["short_title"].as_str())
+                .map(str::to_string)
+                .collect())
+        })
+    }
+}
+
+/// Open-Meteo's current conditions and forecast
+struct OpenMeteo {
+    coordinates: Location,
+    hourly: bool,
+}
+
+impl OpenMeteo {
+    async fn fetch(&mut self, sys: &Sys, query: &str) -> Result<Value> {
+        let (latitude, longitude) = self.coordinates.coordinates(sys).await?;
+        let url = format!("{OPEN_METEO_URL}?latitude={latitude}&longitude={longitude}&{query}");
+        fetch_json(sys, &url).await
+    }
+}
+
+impl WeatherProvider for OpenMeteo {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+        Box::pin(async move {
+            let query = "current=temperature_2m,apparent_temperature,relative_humidity_2m,\
+                         wind_speed_10m,weather_code,is_day,uv_index";
+            let forecast = self.fetch(sys, query).await?;
+            let now = &forecast["current"];
+            let code = now["weather_code"].as_u64();
+            Ok(Current {
+                condition: code.and_then(wmo_condition),
+                night: now["is_day"].as_u64() == Some(0),
+                temp: now["temperature_2m"].as_f64(),
+                feels_like: now["apparent_temperature"].as_f64(),
+                humidity: now["relative_humidity_2m"].as_f64(),
+                wind: now["wind_speed_10m"].as_f64(),
+                uv: now["uv_index"].as_f64(),
+                description: code.and_then(wmo_description).map(str::to_string),
+                ..Current::default()
+            })
+        })
+    }
+
+    fn fetch_forecast<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Forecast>> {
+        Box::pin(async move {
+            let mut query = "daily=temperature_2m_max,temperature_2m_min,\
+                             precipitation_probability_max,uv_index_max&timezone=auto&forecast_days=1"
+                .to_string();
+            if self.hourly {
+                query.push_str(
+                    "&hourly=temperature_2m,precipitation_probability,weather_code,is_day\
+                     &timeformat=unixtime&forecast_hours=48",
+                );
+            }
+            let forecast = self.fetch(sys, &query).await?;
+            let today = &forecast["daily"];
+            let hourly = &forecast["hourly"];
+            let times = hourly["time"].as_array().into_iter().flatten();
+            let hours = times
+                .enumerate()
+                .filter_map(|(i, time)| {
+                    Some(Hour {
+                        time: time.as_i64()?,
+                        condition: hourly["weather_code"][i].as_u64().and_then(wmo_condition),
+                        night: hourly["is_day"][i].as_u64() == Some(0),
+                        temp: hourly["temperature_2m"][i].as_f64(),
+                        rain: hourly["precipitation_probability"][i].as_f64(),
+                    })
+                })
+                .collect();
+            Ok(Forecast {
+                max: today["temperature_2m_max"][0].as_f64(),
+                min: today["temperature_2m_min"][0].as_f64(),
+                rain: today["precipitation_probability_max"][0].as_f64(),
+                uv: today["uv_index_max"][0].as_f64(),
+                hours,
+                ..Forecast::default()
+            })
+        })
+    }
+}
+
+/// OpenWeatherMap's current weather, and its forecast every 3 hours. Its
+/// free API has no daily forecast.
+struct OpenWeatherMap {
+    api_key: Option<Secret>,
+    coordinates: Location,
+    location: Option<String>,
+    hourly: bool,
+}
+
+impl OpenWeatherMap {
+    /// Fetch an endpoint like "weather" for the location
+    async fn fetch(&mut self, sys: &Sys, endpoint: &str) -> Result<Value> {
+        let key = http::encode(&match &self.api_key {
+            Some(key) => key.resolve(sys).await?,
+            None => std::env::var(OPENWEATHERMAP_ENV).with_context(|| {
+                format!("The openweathermap provider needs an api_key or ${OPENWEATHERMAP_ENV}")
+            })?,
+        });
+        let place = match (self.coordinates.fixed(), &self.location) {
+            (None, Some(location)) => format!("q={}", http::encode(location)),
+            _ => {
+                let (latitude, longitude) = self.coordinates.coordinates(sys).await?;
+                format!("lat={latitude}&lon={longitude}")
+            }
+        };
+        let url = format!("{OPENWEATHERMAP_URL}/{endpoint}?{place}&units=metric&appid={key}");
+        // The key has to be in the URL, so it's kept out of the logs
+        let body = sys
+            .http_get(&url, Vec::new())
+            .await
+            .map_err(|err| anyhow!(format!("{err:#}").replace(&key, "<api_key>")))?;
+        Ok(serde_json::from_str(&body)?)
+    }
+}
+
+impl WeatherProvider for OpenWeatherMap {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+        Box::pin(async move {
+            let current = self.fetch(sys, "weather").await?;
+            let conditions = &current["weather"][0];
+            let description = conditions["description"].as_str().map(|description| {
+                let mut chars = description.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            });
+            Ok(Current {
+                condition: conditions["id"].as_u64().and_then(openweathermap_condition),
+                night: conditions["icon"]
+                    .as_str()
+                    .is_some_and(|icon| icon.ends_with('n')),
+                temp: current["main"]["temp"].as_f64(),
+                feels_like: current["main"]["feels_like"].as_f64(),
+                humidity: current["main"]["humidity"].as_f64(),
+                // In metres per second
+                wind: current["wind"]["speed"].as_f64().map(|speed| speed * 3.6),
+                description,
+                ..Current::default()
+            })
+        })
+    }
+
+    fn fetch_forecast<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Forecast>> {
+        Box::pin(async move {
+            if !self.hourly {
+                return Ok(Forecast::default());
+            }
+            let forecast = self.fetch(sys, "forecast").await?;
+            let list = forecast["list"].as_array().into_iter().flatten();
+            let hours = list
+                .filter_map(|hour| {
+                    Some(Hour {
+                        time: hour["dt"].as_i64()?,
+                        condition: hour["weather"][0]["id"]
+                            .as_u64()
+                            .and_then(openweathermap_condition),
+                        night: hour["sys"]["pod"] == "n",
+                        temp: hour["main"]["temp"].as_f64(),
+                        // The probability of precipitation, from 0 to 1
+                        rain: hour["pop"].as_f64().map(|pop| pop * 100.0),
+                    })
+                })
+                .collect();
+            Ok(Forecast {
+                hours,
+                ..Forecast::default()
+            })
+        })
+    }
+}
+
+/// The current weather from wttr.in, which prints a line like "⛅️ +14°C
+/// +11°C 73% ↗17km/h 4 Partly cloudy"
+struct Wttr {
+    location: Option<String>,
+}
+
+impl WeatherProvider for Wttr {
+    fn fetch_current<'a>(&'a mut self, sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+        Box::pin(async move {
+            let location = self.location.as_deref().map(http::encode);
+            let url = format!("{WTTR_URL}/{}?{WTTR_FORMAT}", location.unwrap_or_default());
+            let line = sys.http_get(&url, Vec::new()).await?;
+            let mut fields = line.split_whitespace();
+            let (Some(icon), Some(temp), Some(feels_like), Some(humidity), Some(wind), Some(uv)) = (
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) else {
+                bail!("wttr.in sent {line:?}");
+            };
+            // The first number in a field, like -3 in "-3°C" or 17 in "↗17km/h"
+            let number = |field: &str| {
+                let start = field.find(|c: char| c.is_ascii_digit() || c == '-')?;
+                let number = &field[start..];
+                let end = number[1..]
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .map_or(number.len(), |end| end + 1);
+                number[..end].parse::<f64>().ok()
+            };
+            let description: Vec<_> = fields.collect();
+            Ok(Current {
+                condition: wttr_condition(icon),
+                temp: number(temp),
+                feels_like: number(feels_like),
+                humidity: number(humidity),
+                wind: number(wind),
+                uv: number(uv),
+                description: Some(description.join(" ")).filter(|d| !d.is_empty()),
+                ..Current::default()
+            })
+        })
+    }
+}
+
+/// The 7 character geohash of a point, about 150 metres across, which is
+/// how the BOM identifies places
+fn geohash(latitude: f64, longitude: f64) -> String {
+    let alphabet = b"0123456789bcdefghjkmnpqrstuvwxyz";
+    let (mut latitudes, mut longitudes) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::new();
+    // Bits alternate between longitude and latitude, starting with longitude
+    let mut even = true;
+    while hash.len() < 7 {
+        let mut index = 0;
+        for _ in 0..5 {
+            let (range, value): (&mut (f64, f64), f64) = if even {
+                (&mut longitudes, longitude)
+            } else {
+                (&mut latitudes, latitude)
+            };
+            let middle = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= middle {
+                index |= 1;
+                range.0 = middle;
+            } else {
+                range.1 = middle;
+            }
+            even = !even;
+        }
+        hash.push(alphabet[index] as char);
+    }
+    hash
+}
+
+/// A WMO weather interpretation code, as Open-Meteo reports
+fn wmo_condition(code: u64) -> Option<Condition> {
+    Some(match code {
+        0 => Condition::Clear,
+        1 => Condition::MostlyClear,
+        2 => Condition::PartlyCloudy,
+        3 => Condition::Cloudy,
+        45 | 48 => Condition::Fog,
+        51..=57 | 80 | 81 => Condition::Showers,
+        61..=67 | 82 => Condition::Rain,
+        71..=77 | 85 | 86 => Condition::Snow,
+        95..=99 => Condition::Storm,
+        _ => return None,
+    })
+}
+
+fn wmo_description(code: u64) -> Option<&'static str> {
+    Some(match code {
+        0 => "Clear",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 => "Drizzle",
+        56 | 57 => "Freezing drizzle",
+        61 | 63 => "Rain",
+        65 => "Heavy rain",
+        66 | 67 => "Freezing rain",
+        71 | 73 | 75 | 77 => "Snow",
+        80 | 81 => "Showers",
+        82 => "Heavy showers",
+        85 | 86 => "Snow showers",
+        95 => "Thunderstorm",
+        96 | 99 => "Thunderstorm with hail",
+        _ => return None,
+    })
+}
+
+/// OpenWeatherMap's condition codes, where the hundreds are the group, like
+/// 5xx for rain
+fn openweathermap_condition(id: u64) -> Option<Condition> {
+    Some(match id {
+        200..=299 => Condition::Storm,
+        300..=399 | 500 | 520 => Condition::Showers,
+        501..=531 => Condition::Rain,
+        600..=699 => Condition::Snow,
+        771 => Condition::Wind,
+        781 => Condition::Cyclone,
+        700..=799 => Condition::Fog,
+        800 => Condition::Clear,
+        801 => Condition::MostlyClear,
+        802 => Condition::PartlyCloudy,
+        803 | 804 => Condition::Cloudy,
+        _ => return None,
+    })
+}
+
+/// The emoji wttr.in uses for each of its conditions
+fn wttr_condition(icon: &str) -> Option<Condition> {
+    Some(match icon.trim_end_matches('\u{fe0f}') {
+        "☀" => Condition::Clear,
+        "⛅" => Condition::PartlyCloudy,
+        "☁" => Condition::Cloudy,
+        "🌫" => Condition::Fog,
+        "🌦" => Condition::Showers,
+        "🌧" => Condition::Rain,
+        "🌩" | "⛈" => Condition::Storm,
+        "🌨" | "❄" => Condition::Snow,
+        _ => return None,
+    })
+}
+
+/// The BOM's icon descriptors, like "mostly_sunny"
+fn bom_condition(descriptor: &str) -> Option<Condition> {
+    Some(match descriptor {
+        "sunny" | "clear" => Condition::Clear,
+        "mostly_sunny" => Condition::MostlyClear,
+        "partly_cloudy" => Condition::PartlyCloudy,
+        "cloudy" => Condition::Cloudy,
+        "hazy" | "fog" => Condition::Fog,
+        "light_rain" | "light_showers" | "showers" => Condition::Showers,
+        "rain" | "heavy_showers" => Condition::Rain,
+        "storm" => Condition::Storm,
+        "windy" | "dust" => Condition::Wind,
+        "frost" => Condition::Frost,
+        "snow" => Condition::Snow,
+        "cyclone" => Condition::Cyclone,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::watch;
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::testing::{render, start};
+    use crate::bar::Taskmaster;
+    use crate::system::mock::Mock;
+    use crate::system::{BoxFuture, Sys};
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_falls_back_without_bom_buddy() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let mut tasks = start("[[block]]\nblock = \"weather\"\ncache_ttl = 0", &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🛰️ ??? ","name":"weather""#));
+
+        mock.command("bom-buddy current", 0, "☀️ 21.3°C");
+        sleep(Duration::from_secs(10)).await;
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"☀️ 21.3°C ","name":"weather""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_from_the_bom() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let url = "https://api.weather.bom.gov.au/v1/locations";
+        mock.url(
+            &format!("{url}?search=North%20Hobart"),
+            r#"{"data":[{"geohash":"r22u09g","name":"North Hobart","state":"TAS"}]}"#,
+        );
+        mock.url(
+            &format!("{url}/r22u09/observations"),
+            r#"{"data":{"temp":14.3,"temp_feels_like":11.2,"humidity":73,"wind":{"speed_kilometre":17}}}"#,
+        );
+        mock.url(
+            &format!("{url}/r22u09g/forecasts/daily"),
+            r#"{"data":[{"temp_max":19,"temp_min":null,"icon_descriptor":"showers","short_text":"Showers.","rain":{"chance":80}}]}"#,
+        );
+        mock.url(
+            &format!("{url}/r22u09g/warnings"),
+            r#"{"data":[
+                {"short_title":"Severe Thunderstorm Warning","warning_group_type":"major","phase":"new"},
+                {"short_title":"Frost Warning","warning_group_type":"minor","phase":"new"},
+                {"short_title":"Fire Weather Warning","warning_group_type":"major","phase":"cancelled"}
+            ]}"#,
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"bom\"\nlocation = \"North Hobart\"\n",
+            "cache_ttl = 0\nformat = \"{weather} ({feels_like}) {max}°[/{min}°] {rain}%[ {warnings}]\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = "⚠️ 🌦️ 14.3°C (11.2) 19° 80% Severe Thunderstorm Warning ";
+        assert!(line.starts_with(&format!(
+            r#"[{{"full_text":"{expected}","urgent":true,"name":"weather""#
+        )));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_finds_where_it_is() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://ipinfo.io/json",
+            r#"{"city":"Sydney","loc":"-33.8678,151.2073"}"#,
+        );
+        let url = "https://api.weather.bom.gov.au/v1/locations";
+        mock.url(
+            &format!("{url}/r3gx2f/observations"),
+            r#"{"data":{"temp":22.4}}"#,
+        );
+        mock.url(
+            &format!("{url}/r3gx2f9/forecasts/daily"),
+            r#"{"data":[{"temp_max":26}]}"#,
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"bom\"\nlocate = \"ip\"\n",
+            "cache_ttl = 0\nformat = \"{temp}° ({max}°)\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"22.4° (26°) ","name":"weather""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_shows_the_coming_hours() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let url = "https://api.weather.bom.gov.au/v1/locations";
+        mock.url(
+            &format!("{url}?search=3000"),
+            r#"{"data":[{"geohash":"r1r0fsn"}]}"#,
+        );
+        mock.url(
+            &format!("{url}/r1r0fs/observations"),
+            r#"{"data":{"temp":18.2}}"#,
+        );
+        mock.url(
+            &format!("{url}/r1r0fsn/forecasts/daily"),
+            r#"{"data":[{"icon_descriptor":"sunny"}]}"#,
+        );
+        let hour = |time: &str, temp: u32, rain: u32| {
+            format!(
+                r#"{{"time":"2026-10-14T{time}:00:00Z","temp":{temp},"rain":{{"chance":{rain}}}}}"#
+            )
+        };
+        let hours = [
+            hour("09", 17, 0),
+            hour("10", 18, 0),
+            hour("11", 17, 10),
+            hour("12", 16, 30),
+            hour("13", 14, 60),
+        ];
+        mock.url(
+            &format!("{url}/r1r0fsn/forecasts/hourly"),
+            &format!(r#"{{"data":[{}]}}"#, hours.join(",")),
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"bom\"\nlocation = \"3000\"\n",
+            "cache_ttl = 0\nhours = 2\nformat = \"now {temp}°[ → {hourly}]\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = "now 18.2° → 18° 10:00 → 14° 13:00, rain 60% ";
+        assert!(line.starts_with(&format!(r#"[{{"full_text":"{expected}","name":"weather""#)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_from_open_meteo() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let url = "https://api.open-meteo.com/v1/forecast?latitude=52.52&longitude=13.41";
+        mock.url(
+            &format!(
+                "{url}&current=temperature_2m,apparent_temperature,relative_humidity_2m,\
+                 wind_speed_10m,weather_code,is_day,uv_index"
+            ),
+            r#"{"current":{"temperature_2m":8.2,"apparent_temperature":5.9,"weather_code":2,"is_day":0,"uv_index":3.4}}"#,
+        );
+        mock.url(
+            &format!(
+                "{url}&daily=temperature_2m_max,temperature_2m_min,\
+                 precipitation_probability_max,uv_index_max&timezone=auto&forecast_days=1"
+            ),
+            r#"{"daily":{"temperature_2m_max":[11.4],"temperature_2m_min":[4.1]}}"#,
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"open_meteo\"\n",
+            "latitude = 52.52\nlongitude = 13.41\ncache_ttl = 0\n",
+            "format = \"{weather} {description} {min}-{max}°[ {rain}%] UV {uv}\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = r##"⛅ 8.2°C Partly cloudy 4-11° UV 3 ","color":"#ffaa00""##;
+        assert!(line.starts_with(&format!(r#"[{{"full_text":"{expected},"name":"weather""#)));
+    }
+
+    /// Snow, with a forecast that can't be fetched
+    struct Snowing;
+
+    impl WeatherProvider for Snowing {
+        fn fetch_current<'a>(&'a mut self, _sys: &'a Sys) -> BoxFuture<'a, Result<Current>> {
+            Box::pin(async {
+                Ok(Current {
+                    condition: Some(Condition::Snow),
+                    temp: Some(-2.5),
+                    ..Current::default()
+                })
+            })
+        }
+
+        fn fetch_forecast<'a>(&'a mut self, _sys: &'a Sys) -> BoxFuture<'a, Result<Forecast>> {
+            Box::pin(async { anyhow::bail!("No forecast") })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_shows_the_current_conditions_without_a_forecast() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let config = toml::from_str("cache_ttl = 0\nformat = \"{weather}[ {max}°]\"").unwrap();
+        let (_, paused) = watch::channel(false);
+        let sys: Sys = mock.clone();
+        let task = Taskmaster::new("weather", WEATHER_FALLBACK, &paused, |tx, events| {
+            show_weather(config, Box::new(Snowing), sys, tx, events)
+        });
+        let line = render(&mut [task], &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"🌨️ -2.5°C","name":"weather""#));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_from_wttr() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://wttr.in/San%20Francisco?format=%c+%t+%f+%h+%w+%u+%C&m",
+            "🌦  +14°C +11°C 73% ↗17km/h 2 Light rain shower\n",
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"wttr\"\n",
+            "location = \"San Francisco\"\ncache_ttl = 0\n",
+            "format = \"{weather} {description} {humidity}% {wind}km/h\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = "🌦️ 14.0°C Light rain shower 73% 17km/h ";
+        assert!(line.starts_with(&format!(r#"[{{"full_text":"{expected}","name":"weather""#)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn weather_from_openweathermap() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.url(
+            "https://api.openweathermap.org/data/2.5/weather?q=Berlin%2CDE&units=metric&appid=abc123",
+            r#"{"weather":[{"id":500,"description":"light rain","icon":"10n"}],"main":{"temp":7.5,"feels_like":4.81},"wind":{"speed":5}}"#,
+        );
+        let config = concat!(
+            "[[block]]\nblock = \"weather\"\nprovider = \"openweathermap\"\n",
+            "location = \"Berlin,DE\"\napi_key = \"abc123\"\nicons = \"nerd_font\"\n",
+            "cache_ttl = 0\nformat = \"{icon} {description}, feels like {feels_like}°C, {wind} km/h\"",
+        );
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        let expected = "\u{e319} Light rain, feels like 4.8°C, 18 km/h ";
+        assert!(line.starts_with(&format!(r#"[{{"full_text":"{expected}","name":"weather""#)));
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::{anyhow, bail, Context, Result};
+use tokio::process::Command;
+
+use crate::system::Sys;
+
+/// Read kernel state with sysctl(8), since the BSDs don't have /proc or
+/// sysfs. The values come back in the same order as `names`.
+pub async fn sysctl<const N: usize>(sys: &Sys, names: [&str; N]) -> Result<[String; N]> {
+    let stdout = run(sys, Command::new("sysctl").arg("-n").args(names)).await?;
+    let values: Vec<String> = stdout.lines().map(|line| line.trim().to_string()).collect();
+    values
+        .try_into()
+        .map_err(|_| anyhow!("sysctl didn't print {}", names.join(" ")))
+}
+
+/// Read numbers with sysctl(8), in the same order as `names`
+pub async fn sysctl_numbers<const N: usize>(sys: &Sys, names: [&str; N]) -> Result<[f64; N]> {
+    let values = sysctl(sys, names).await?;
+    let mut numbers = [0.0; N];
+    for ((number, name), value) in numbers.iter_mut().zip(names).zip(&values) {
+        *number = self::number(name, value)?;
+    }
+    Ok(numbers)
+}
+
+/// Parse a number from sysctl(8) or another of the BSDs' tools
+pub fn number(name: &str, value: &str) -> Result<f64> {
+    value
+        .parse()
+        .with_context(|| format!("{name} isn't a number: {value}"))
+}
+
+/// Run a command and return its stdout if it succeeded
+pub async fn run(sys: &Sys, command: &mut Command) -> Result<String> {
+    let output = sys.output(command).await?;
+    if !output.status.success() {
+        let program = command.as_std().get_program().to_string_lossy();
+        bail!("{program} failed with {}", output.status);
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
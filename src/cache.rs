@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::system::Sys;
+
+/// A value saved in the XDG cache directory so blocks can show something
+/// useful straight after startup, and skip fetching data that's still fresh.
+pub struct Cache {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    /// Seconds since the Unix epoch
+    saved: u64,
+    value: T,
+}
+
+pub struct Cached<T> {
+    pub value: T,
+    /// How long until the value should be fetched again
+    pub expires_in: Duration,
+    /// How long ago it was saved
+    pub age: Duration,
+}
+
+impl Cache {
+    /// A cache entry for `key` that stays fresh for `ttl`. Returns None if
+    /// the TTL is zero or `sys` has no cache directory.
+    pub fn new(sys: &Sys, key: &str, ttl: Duration) -> Option<Self> {
+        if ttl.is_zero() {
+            return None;
+        }
+        let dir = sys.cache_dir()?;
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!("{name}.json"));
+        Some(Self { path, ttl })
+    }
+
+    /// The cached value, if there is one and it hasn't expired
+    pub async fn load<T: DeserializeOwned>(&self) -> Option<Cached<T>> {
+        let cached = self.load_expired().await?;
+        (cached.age <= self.ttl).then_some(cached)
+    }
+
+    /// The cached value even if it's expired, for showing until a fresh one
+    /// is fetched
+    pub async fn load_expired<T: DeserializeOwned>(&self) -> Option<Cached<T>> {
+        let contents = tokio::fs::read(&self.path).await.ok()?;
+        let entry: Entry<T> = serde_json::from_slice(&contents).ok()?;
+        let age = Duration::from_secs(now().saturating_sub(entry.saved));
+        Some(Cached {
+            value: entry.value,
+            expires_in: self.ttl.saturating_sub(age),
+            age,
+        })
+    }
+
+    /// Save a value. Failures are logged rather than returned since the
+    /// cache is only an optimisation.
+    pub async fn store<T: Serialize>(&self, value: &T) {
+        let entry = Entry {
+            saved: now(),
+            value,
+        };
+        let result = async {
+            if let Some(dir) = self.path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            let contents = serde_json::to_vec(&entry)?;
+            tokio::fs::write(&self.path, contents).await?;
+            anyhow::Ok(())
+        };
+        if let Err(err) = result.await {
+            eprintln!("Couldn't write {}. {err}", self.path.display());
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bar::testing::Fixture;
+    use crate::system::mock::Mock;
+
+    fn cache(dir: &Fixture, ttl: u64) -> Cache {
+        Cache {
+            path: PathBuf::from(dir.path("subar/weather.json")),
+            ttl: Duration::from_secs(ttl),
+        }
+    }
+
+    #[tokio::test]
+    async fn values_are_fresh_until_their_ttl() {
+        let dir = Fixture::new();
+        let cache = cache(&dir, 60);
+        assert!(cache.load::<String>().await.is_none());
+        cache.store(&"sunny").await;
+        let cached = cache.load::<String>().await.unwrap();
+        assert_eq!(cached.value, "sunny");
+        assert!(cached.age < Duration::from_secs(5));
+        assert!(cached.expires_in > Duration::from_secs(55));
+    }
+
+    #[tokio::test]
+    async fn expired_values_are_only_loaded_on_request() {
+        let dir = Fixture::new();
+        let cache = cache(&dir, 60);
+        let saved = now() - 120;
+        dir.write(
+            "subar/weather.json",
+            format!(r#"{{"saved":{saved},"value":"rain"}}"#),
+        );
+        assert!(cache.load::<String>().await.is_none());
+        let cached = cache.load_expired::<String>().await.unwrap();
+        assert_eq!(cached.value, "rain");
+        assert!(cached.age >= Duration::from_secs(120));
+        assert_eq!(cached.expires_in, Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn corrupt_files_are_ignored_and_replaced() {
+        let dir = Fixture::new();
+        let cache = cache(&dir, 60);
+        for contents in ["", "{\"saved\":", "not json", r#"{"saved":1,"value":[1]}"#] {
+            dir.write("subar/weather.json", contents);
+            assert!(
+                cache.load_expired::<String>().await.is_none(),
+                "{contents:?}"
+            );
+        }
+        cache.store(&"fog").await;
+        assert_eq!(cache.load::<String>().await.unwrap().value, "fog");
+    }
+
+    #[test]
+    fn caching_can_be_turned_off() {
+        let sys: Sys = Mock::new("2026-10-14 09:30:00");
+        assert!(Cache::new(&sys, "weather", Duration::ZERO).is_none());
+        // Mocks have no cache directory
+        assert!(Cache::new(&sys, "weather", Duration::from_secs(60)).is_none());
+    }
+}
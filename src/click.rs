@@ -0,0 +1,168 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::events::BlockEvent;
+use crate::format::Template;
+use crate::sandbox::{self, Sandbox};
+use crate::system::Sys;
+
+pub const BUTTON_LEFT: u8 = 1;
+pub const BUTTON_MIDDLE: u8 = 2;
+pub const BUTTON_RIGHT: u8 = 3;
+pub const BUTTON_SCROLL_UP: u8 = 4;
+pub const BUTTON_SCROLL_DOWN: u8 = 5;
+
+/// A click event sent by the bar on stdin.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClickEvent {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub instance: String,
+    pub button: u8,
+    /// e.g. Shift, Control, Mod1 (Alt), Mod4 (Super)
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+/// Shell commands to run when a block is clicked with each button.
+/// Placeholders: name, instance, button
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OnClick {
+    pub left: Option<Template>,
+    pub middle: Option<Template>,
+    pub right: Option<Template>,
+    pub scroll_up: Option<Template>,
+    pub scroll_down: Option<Template>,
+}
+
+/// A button by name, like "left" or "scroll_up", or by number
+pub fn parse_button(button: &str) -> Option<u8> {
+    match button {
+        "left" => Some(BUTTON_LEFT),
+        "middle" => Some(BUTTON_MIDDLE),
+        "right" => Some(BUTTON_RIGHT),
+        "scroll_up" => Some(BUTTON_SCROLL_UP),
+        "scroll_down" => Some(BUTTON_SCROLL_DOWN),
+        _ => button.parse().ok(),
+    }
+}
+
+impl OnClick {
+    fn command(&self, button: u8) -> Option<&Template> {
+        match button {
+            BUTTON_LEFT => self.left.as_ref(),
+            BUTTON_MIDDLE => self.middle.as_ref(),
+            BUTTON_RIGHT => self.right.as_ref(),
+            BUTTON_SCROLL_UP => self.scroll_up.as_ref(),
+            BUTTON_SCROLL_DOWN => self.scroll_down.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (1..=5).all(|button| self.command(button).is_none())
+    }
+}
+
+/// Where to send one block's clicks
+pub struct Route {
+    pub name: &'static str,
+    pub instance: String,
+    /// The block's task, if it has click actions of its own
+    pub events: Option<mpsc::Sender<BlockEvent>>,
+    pub on_click: OnClick,
+    /// Where the block's commands are run, which its click commands share
+    pub sandbox: Option<Sandbox>,
+}
+
+/// Read click events from stdin and pass them on to the block that was
+/// clicked. The protocol is an endless JSON array with one event per line.
+pub async fn read_clicks(routes: Vec<Route>, sys: Sys) -> Result<()> {
+    let mut lines = BufReader::new(stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim_start_matches(['[', ',']).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let event: ClickEvent = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("Couldn't parse click event {line}. {err}");
+                continue;
+            }
+        };
+        dispatch(&routes, &sys, event);
+    }
+    Ok(())
+}
+
+/// Run the clicked block's command for the button, or send the click to the
+/// block's task
+pub fn dispatch(routes: &[Route], sys: &Sys, event: ClickEvent) {
+    let route = routes
+        .iter()
+        .find(|r| r.name == event.name && r.instance == event.instance);
+    let Some(route) = route else {
+        return;
+    };
+    if let Some(command) = route.on_click.command(event.button) {
+        let command = command.render(|name| match name {
+            "name" => Some(event.name.clone()),
+            "instance" => Some(event.instance.clone()),
+            "button" => Some(event.button.to_string()),
+            _ => None,
+        });
+        let sys = match &route.sandbox {
+            Some(sandbox) => sandbox::wrap(sys.clone(), sandbox.clone()),
+            None => sys.clone(),
+        };
+        tokio::spawn(run(sys, command));
+    } else if let Some(tx) = &route.events {
+        // Drop clicks if the block is too busy to keep up
+        let _ = tx.try_send(BlockEvent::Click(event));
+    }
+}
+
+/// Click `block`, an instance or the name of every block of that type, as
+/// though it were clicked with `button` on the bar, e.g. from a keybinding
+pub fn press<'a>(
+    routes: impl IntoIterator<Item = &'a Route>,
+    sys: &Sys,
+    block: &str,
+    button: &str,
+) -> Result<()> {
+    let Some(button) = parse_button(button) else {
+        bail!("{button} isn't a button");
+    };
+    let matches: Vec<_> = routes
+        .into_iter()
+        .filter(|r| r.instance == block || r.name == block)
+        .collect();
+    if matches.is_empty() {
+        bail!("No block named {block}");
+    }
+    for route in matches {
+        let event = ClickEvent {
+            name: route.name.to_string(),
+            instance: route.instance.clone(),
+            button,
+            modifiers: Vec::new(),
+        };
+        dispatch(std::slice::from_ref(route), sys, event);
+    }
+    Ok(())
+}
+
+/// Run a shell command, only logging if it fails
+pub async fn run(sys: Sys, command: String) {
+    match sys.output(Command::new("sh").arg("-c").arg(&command)).await {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => eprintln!("`{command}` failed with {}", output.status),
+        Err(err) => eprintln!("Couldn't run `{command}`. {err}"),
+    }
+}
@@ -0,0 +1,272 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+#[cfg(feature = "mpd")]
+use crate::blocks::mpd;
+#[cfg(feature = "script")]
+use crate::blocks::script;
+#[cfg(feature = "weather")]
+use crate::blocks::{aqi, weather};
+use crate::blocks::{
+    battery, calendar, camera, clipboard, clock, cpu, fortune, gpu, load, memory, moon, pomodoro,
+    schedule, stopwatch, sun, swap, temperature, timer,
+};
+#[cfg(feature = "http")]
+use crate::blocks::{chat, ci, github, holiday, parcel, transit};
+#[cfg(feature = "audio")]
+use crate::blocks::{easyeffects, headset, input_level, volume};
+#[cfg(feature = "dbus")]
+use crate::blocks::{mpris, power_profile};
+use crate::click::OnClick;
+use crate::sandbox::Sandbox;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Serve the org.subar.Bar interface on the session bus
+    pub dbus: bool,
+    /// Signal the bar sends when it's hidden. Blocks stop updating until
+    /// `cont_signal` arrives, then update straight away. SIGSTOP (19) lets
+    /// the kernel suspend the whole process instead, which it can't notice,
+    /// so blocks aren't refreshed when it continues. 0 keeps updating while
+    /// hidden.
+    pub stop_signal: u8,
+    /// Signal the bar sends when it's shown again
+    pub cont_signal: u8,
+    /// Language of the text blocks show, such as "de" or "pt-BR". Defaults
+    /// to the locale from the environment.
+    pub locale: Option<String>,
+    pub stale: Stale,
+    pub loading: Loading,
+    /// Seconds the status line can go without updating before the renderer
+    /// is restarted. 0 turns the watchdog off.
+    pub watchdog: u64,
+    /// Keep statistics such as time spent listening to music, for
+    /// `subar report`
+    pub history: bool,
+    #[serde(rename = "block")]
+    pub blocks: Vec<Block>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dbus: true,
+            stop_signal: 10,
+            cont_signal: 18,
+            locale: None,
+            stale: Stale::default(),
+            loading: Loading::default(),
+            watchdog: 10,
+            history: false,
+            blocks: vec![
+                #[cfg(feature = "mpd")]
+                BlockConfig::Mpd(Box::default()).into(),
+                #[cfg(feature = "audio")]
+                BlockConfig::Volume(volume::Config::default()).into(),
+                #[cfg(feature = "weather")]
+                BlockConfig::Weather(weather::Config::default()).into(),
+            ],
+        }
+    }
+}
+
+/// How blocks that have stopped updating are shown
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Stale {
+    /// Seconds a block can go without updating, past its usual interval,
+    /// before it's marked stale
+    pub grace: u64,
+    /// Text colour of stale blocks
+    pub color: Option<String>,
+    /// Put after the text of stale blocks
+    pub marker: String,
+}
+
+impl Default for Stale {
+    fn default() -> Self {
+        Self {
+            grace: 30,
+            color: Some("#888888".to_string()),
+            marker: "*".to_string(),
+        }
+    }
+}
+
+/// How blocks are shown until their first update, so slow ones don't look
+/// broken while they start
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Loading {
+    /// Shown after the block's icon one at a time, as a spinner
+    pub frames: Vec<String>,
+    /// Milliseconds each frame is shown for
+    pub interval: u64,
+    /// Text colour of loading blocks
+    pub color: Option<String>,
+}
+
+impl Default for Loading {
+    fn default() -> Self {
+        Self {
+            frames: "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏".chars().map(String::from).collect(),
+            interval: 100,
+            color: Some("#888888".to_string()),
+        }
+    }
+}
+
+impl Loading {
+    /// The spinner frame to show at `now`
+    fn frame(&self, now: DateTime<Local>) -> &str {
+        if self.frames.is_empty() {
+            return "";
+        }
+        let step = now.timestamp_millis().max(0) as u64 / self.interval.max(1);
+        &self.frames[(step % self.frames.len() as u64) as usize]
+    }
+
+    /// What a block with `fallback` text shows while it's loading: the
+    /// fallback's icon and the spinner
+    pub fn text(&self, fallback: &str, now: DateTime<Local>) -> String {
+        let icon = fallback.split_whitespace().next().unwrap_or_default();
+        format!("{icon} {}", self.frame(now)).trim().to_string()
+    }
+}
+
+/// A block along with the settings every block has
+#[derive(Deserialize)]
+pub struct Block {
+    /// Sent to the bar as the block's instance and given to click commands
+    /// as `{instance}`, e.g. "sdb" to tell two disk blocks apart. Defaults
+    /// to the block's position in the bar.
+    pub instance: Option<String>,
+    /// Shell commands run when the block is clicked, instead of the block's
+    /// own click actions
+    #[serde(default)]
+    pub on_click: OnClick,
+    /// Limits on the commands the block runs
+    pub sandbox: Option<Sandbox>,
+    /// ACPI events that update the block straight away, matched by how
+    /// `acpi_listen` prints them, e.g. ["ac_adapter", "button/lid"]
+    #[serde(default)]
+    pub refresh_on: Vec<String>,
+    /// udev subsystems whose devices coming and going update the block
+    /// straight away, e.g. ["sound"] for a USB DAC or ["block"] for drives
+    #[serde(default)]
+    pub refresh_on_devices: Vec<String>,
+    #[serde(flatten)]
+    pub kind: BlockConfig,
+}
+
+impl From<BlockConfig> for Block {
+    fn from(kind: BlockConfig) -> Self {
+        Self {
+            instance: None,
+            on_click: OnClick::default(),
+            sandbox: None,
+            refresh_on: Vec::new(),
+            refresh_on_devices: Vec::new(),
+            kind,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "block", rename_all = "snake_case")]
+pub enum BlockConfig {
+    #[cfg(feature = "weather")]
+    Aqi(aqi::Config),
+    Battery(battery::Config),
+    Calendar(calendar::Config),
+    Camera(camera::Config),
+    #[cfg(feature = "http")]
+    Chat(chat::Config),
+    #[cfg(feature = "http")]
+    Ci(ci::Config),
+    Clipboard(clipboard::Config),
+    Clock(clock::Config),
+    Cpu(cpu::Config),
+    #[cfg(feature = "audio")]
+    Easyeffects(easyeffects::Config),
+    Fortune(fortune::Config),
+    #[cfg(feature = "http")]
+    Github(github::Config),
+    Gpu(gpu::Config),
+    #[cfg(feature = "audio")]
+    Headset(headset::Config),
+    #[cfg(feature = "http")]
+    Holiday(holiday::Config),
+    #[cfg(feature = "audio")]
+    InputLevel(input_level::Config),
+    Load(load::Config),
+    Memory(memory::Config),
+    Moon(moon::Config),
+    /// Boxed because it has far more options than the other blocks
+    #[cfg(feature = "mpd")]
+    Mpd(Box<mpd::Config>),
+    #[cfg(feature = "dbus")]
+    Mpris(mpris::Config),
+    #[cfg(feature = "http")]
+    Parcel(parcel::Config),
+    Pomodoro(pomodoro::Config),
+    #[cfg(feature = "dbus")]
+    PowerProfile(power_profile::Config),
+    Schedule(schedule::Config),
+    #[cfg(feature = "script")]
+    Script(script::Config),
+    Stopwatch(stopwatch::Config),
+    Sun(sun::Config),
+    Swap(swap::Config),
+    Temperature(temperature::Config),
+    Timer(timer::Config),
+    #[cfg(feature = "http")]
+    Transit(transit::Config),
+    #[cfg(feature = "audio")]
+    Volume(volume::Config),
+    #[cfg(feature = "weather")]
+    Weather(weather::Config),
+}
+
+impl Config {
+    /// Load the config from `path`, or from the default location if none is
+    /// given. A missing file at the default location isn't an error.
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path, true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("Couldn't read {}", path.display()))
+            }
+        };
+        toml::from_str(&contents).with_context(|| format!("Couldn't parse {}", path.display()))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/subar`, or `~/.config/subar`
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("subar"))
+}
+
+fn default_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
@@ -0,0 +1,378 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use tokio::sync::{mpsc, watch};
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+use zbus::{interface, proxy, Connection};
+
+use crate::click::{self, Route};
+use crate::events::BlockEvent;
+use crate::notify;
+use crate::output::BlockOutput;
+use crate::system::Sys;
+
+static BUS_NAME: &str = "org.subar.Bar";
+static OBJECT_PATH: &str = "/org/subar/Bar";
+
+/// What the D-Bus service needs to know about each block
+pub struct BlockHandle {
+    pub name: &'static str,
+    pub instance: String,
+    pub rx: watch::Receiver<BlockOutput>,
+    pub events: mpsc::Sender<BlockEvent>,
+    pub hidden: Arc<AtomicBool>,
+    pub route: Route,
+}
+
+struct Bar {
+    blocks: Vec<BlockHandle>,
+    /// For the commands blocks run when they're clicked
+    sys: Sys,
+}
+
+impl Bar {
+    /// Find blocks by instance, or every block with the given name
+    fn matching(&self, block: &str) -> fdo::Result<Vec<&BlockHandle>> {
+        let matches: Vec<_> = self
+            .blocks
+            .iter()
+            .filter(|b| b.instance == block || b.name == block)
+            .collect();
+        if matches.is_empty() {
+            return Err(fdo::Error::InvalidArgs(format!("No block named {block}")));
+        }
+        Ok(matches)
+    }
+
+    fn set_hidden(&self, block: &str, hidden: bool) -> fdo::Result<()> {
+        for b in self.matching(block)? {
+            b.hidden.store(hidden, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "org.subar.Bar")]
+impl Bar {
+    /// Name, instance, and current text of every block
+    fn list_blocks(&self) -> Vec<(String, String, String)> {
+        self.blocks
+            .iter()
+            .map(|b| {
+                (
+                    b.name.to_string(),
+                    b.instance.clone(),
+                    b.rx.borrow().full_text.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Update a block now rather than waiting for its next scheduled update
+    fn refresh(&self, block: &str) -> fdo::Result<()> {
+        for b in self.matching(block)? {
+            let _ = b.events.try_send(BlockEvent::Refresh);
+        }
+        Ok(())
+    }
+
+    /// Click a block with a button like "left" or "scroll_up", or its number,
+    /// the same as clicking it on the bar
+    fn click(&self, block: &str, button: &str) -> fdo::Result<()> {
+        let routes = self.blocks.iter().map(|b| &b.route);
+        click::press(routes, &self.sys, block, button)
+            .map_err(|err| fdo::Error::InvalidArgs(err.to_string()))
+    }
+
+    /// Send a command to the blocks that take them, like "25m" to start a
+    /// timer
+    fn send(&self, block: &str, command: &str) -> fdo::Result<()> {
+        for b in self.matching(block)? {
+            let _ = b.events.try_send(BlockEvent::Command(command.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Read a block's text aloud with speech-dispatcher, or the whole bar's
+    /// if `block` is empty
+    async fn speak(&self, block: &str) -> fdo::Result<()> {
+        let blocks = match block {
+            "" => self.blocks.iter().collect(),
+            block => self.matching(block)?,
+        };
+        let texts: Vec<_> = blocks
+            .iter()
+            .filter(|b| !b.hidden.load(Ordering::Relaxed))
+            .map(|b| b.rx.borrow().full_text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect();
+        notify::speak(&self.sys, &texts.join(". "))
+            .await
+            .map_err(|err| fdo::Error::Failed(format!("{err:#}")))
+    }
+
+    fn hide(&self, block: &str) -> fdo::Result<()> {
+        self.set_hidden(block, true)
+    }
+
+    fn show(&self, block: &str) -> fdo::Result<()> {
+        self.set_hidden(block, false)
+    }
+
+    #[zbus(signal)]
+    async fn block_changed(
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        instance: &str,
+        text: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Serve the org.subar.Bar interface on the session bus
+pub async fn serve(blocks: Vec<BlockHandle>, sys: Sys) -> Result<()> {
+    let conn = Connection::session().await?;
+    attach(&conn, blocks, sys).await?;
+    conn.request_name(BUS_NAME).await?;
+    // The service stops when the connection is dropped
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+/// Serve the interface on `conn` and signal each block's changes
+async fn attach(conn: &Connection, blocks: Vec<BlockHandle>, sys: Sys) -> Result<()> {
+    let watched: Vec<_> = blocks
+        .iter()
+        .map(|b| (b.name, b.instance.clone(), b.rx.clone()))
+        .collect();
+    conn.object_server()
+        .at(OBJECT_PATH, Bar { blocks, sys })
+        .await?;
+    for (name, instance, rx) in watched {
+        tokio::spawn(emit_changes(conn.clone(), name, instance, rx));
+    }
+    Ok(())
+}
+
+async fn emit_changes(
+    conn: Connection,
+    name: &'static str,
+    instance: String,
+    mut rx: watch::Receiver<BlockOutput>,
+) -> Result<()> {
+    let emitter = SignalEmitter::new(&conn, OBJECT_PATH)?;
+    let mut last = String::new();
+    while rx.changed().await.is_ok() {
+        let text = rx.borrow_and_update().full_text.clone();
+        // Blocks resend their text on every update, so only signal real changes
+        if text != last {
+            Bar::block_changed(&emitter, name, &instance, &text).await?;
+            last = text;
+        }
+    }
+    Ok(())
+}
+
+#[proxy(
+    interface = "org.subar.Bar",
+    default_service = "org.subar.Bar",
+    default_path = "/org/subar/Bar"
+)]
+trait Remote {
+    fn click(&self, block: &str, button: &str) -> zbus::Result<()>;
+    fn speak(&self, block: &str) -> zbus::Result<()>;
+    fn send(&self, block: &str, command: &str) -> zbus::Result<()>;
+}
+
+/// What a command like `subar click mpd left` asks the running bar to do
+#[derive(Debug, PartialEq)]
+enum Request {
+    Click {
+        block: String,
+        button: String,
+    },
+    /// Speak every block if `block` is empty
+    Speak {
+        block: String,
+    },
+    Send {
+        block: String,
+        command: String,
+    },
+}
+
+impl Request {
+    fn parse(command: &str, args: &[String]) -> Result<Self> {
+        Ok(match (command, args) {
+            ("click", [block, button]) => Self::Click {
+                block: block.clone(),
+                button: button.clone(),
+            },
+            ("speak", []) => Self::Speak {
+                block: String::new(),
+            },
+            ("speak", [block]) => Self::Speak {
+                block: block.clone(),
+            },
+            ("ctl", [block, command @ ..]) if !command.is_empty() => Self::Send {
+                block: block.clone(),
+                command: command.join(" "),
+            },
+            _ => bail!(
+                "Usage: subar click <block> <button>, subar speak [block], \
+                 or subar ctl <block> <command>"
+            ),
+        })
+    }
+}
+
+/// Control the running bar, for commands like `subar click mpd left`
+pub async fn control(command: &str, args: &[String]) -> Result<()> {
+    let request = Request::parse(command, args)?;
+    let conn = Connection::session().await?;
+    let bar = RemoteProxy::new(&conn).await?;
+    match request {
+        Request::Click { block, button } => bar.click(&block, &button).await?,
+        Request::Speak { block } => bar.speak(&block).await?,
+        Request::Send { block, command } => bar.send(&block, &command).await?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use tokio::net::UnixStream;
+    use zbus::export::futures_core::Stream;
+    use zbus::{connection, Guid, MessageStream};
+
+    use super::*;
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    const TIMERS: &str = r#"
+        [[block]]
+        block = "timer"
+        instance = "tea"
+
+        [[block]]
+        block = "timer"
+        instance = "eggs"
+        "#;
+
+    fn request(command: &str, args: &[&str]) -> Option<Request> {
+        let args: Vec<_> = args.iter().map(|a| a.to_string()).collect();
+        Request::parse(command, &args).ok()
+    }
+
+    #[test]
+    fn commands_are_parsed_into_requests() {
+        let click = Request::Click {
+            block: "mpd".to_string(),
+            button: "left".to_string(),
+        };
+        assert_eq!(request("click", &["mpd", "left"]), Some(click));
+        let speak = |block: &str| Request::Speak {
+            block: block.to_string(),
+        };
+        assert_eq!(request("speak", &[]), Some(speak("")));
+        assert_eq!(request("speak", &["clock"]), Some(speak("clock")));
+        let send = Request::Send {
+            block: "timer".to_string(),
+            command: "1h 30m".to_string(),
+        };
+        assert_eq!(request("ctl", &["timer", "1h", "30m"]), Some(send));
+        for (command, args) in [
+            ("click", &["mpd"][..]),
+            ("click", &["mpd", "left", "right"]),
+            ("speak", &["clock", "mpd"]),
+            ("ctl", &["timer"]),
+            ("ctl", &[]),
+            ("dance", &[]),
+        ] {
+            assert_eq!(request(command, args), None, "{command} {args:?}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_reach_the_right_block() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let mut tasks = start(TIMERS, &mock);
+        let blocks = tasks.iter().map(|t| t.dbus_handle()).collect();
+        let sys: Sys = mock.clone();
+        let bar = Bar { blocks, sys };
+        render(&mut tasks, &mock).await;
+
+        bar.send("eggs", "6m").unwrap();
+        bar.click("tea", "left").unwrap();
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(concat!(
+            r#"[{"full_text":"⏲️ 25:00 ","name":"timer","instance":"tea","#,
+            r#""separator":false,"separator_block_width":0},"#,
+            r#"{"full_text":"⏲️ 06:00 ","name":"timer","instance":"eggs""#,
+        )));
+        let texts: Vec<_> = bar
+            .list_blocks()
+            .into_iter()
+            .map(|(.., text)| text)
+            .collect();
+        assert_eq!(texts[..2], ["⏲️ 25:00", "⏲️ 06:00"]);
+
+        bar.send("timer", "stop").unwrap();
+        bar.hide("eggs").unwrap();
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"⏲️ ","name":"timer","instance":"tea","#));
+        assert!(!line.contains("eggs"));
+        bar.show("timer").unwrap();
+        assert!(render(&mut tasks, &mock).await.contains("eggs"));
+
+        assert!(bar.send("toast", "5m").is_err());
+        assert!(bar.click("tea", "elbow").is_err());
+        assert!(bar.refresh("toast").is_err());
+    }
+
+    /// Real time, since paused time would skip ahead while zbus waits on the
+    /// socket
+    #[tokio::test]
+    async fn remote_commands_are_served_and_changes_are_signalled() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let tasks = start(TIMERS, &mock);
+        let (server, client) = UnixStream::pair().unwrap();
+        let guid = Guid::generate();
+        let (server, client) = tokio::try_join!(
+            connection::Builder::unix_stream(server)
+                .server(guid)
+                .unwrap()
+                .p2p()
+                .build(),
+            connection::Builder::unix_stream(client).p2p().build(),
+        )
+        .unwrap();
+        let blocks = tasks.iter().map(|t| t.dbus_handle()).collect();
+        attach(&server, blocks, mock.clone()).await.unwrap();
+        let mut signals = MessageStream::from(&client);
+
+        let bar = RemoteProxy::new(&client).await.unwrap();
+        bar.send("eggs", "6m").await.unwrap();
+        assert!(bar.click("tea", "elbow").await.is_err());
+        // Each block's first text is signalled too
+        let mut eggs = Vec::new();
+        while eggs.len() < 2 {
+            let message = poll_fn(|cx| Pin::new(&mut signals).poll_next(cx)).await;
+            let message = message.unwrap().unwrap();
+            let header = message.header();
+            if header.member().is_none_or(|m| m != "BlockChanged") {
+                continue;
+            }
+            let (name, instance, text): (String, String, String) =
+                message.body().deserialize().unwrap();
+            if (name.as_str(), instance.as_str()) == ("timer", "eggs") {
+                eggs.push(text);
+            }
+        }
+        assert_eq!(eggs, ["⏲️", "⏲️ 06:00"]);
+    }
+}
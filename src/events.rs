@@ -0,0 +1,71 @@
+use std::future::Future;
+use std::pin::pin;
+
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, Duration};
+
+use crate::click::ClickEvent;
+
+/// Something a block should react to before its next scheduled update
+#[derive(Debug)]
+pub enum BlockEvent {
+    Click(ClickEvent),
+    Refresh,
+    /// Sent with `subar ctl <block> <command>`, like "25m" to start a timer
+    Command(String),
+}
+
+pub struct Events {
+    rx: mpsc::Receiver<BlockEvent>,
+    /// True while the bar is hidden
+    paused: watch::Receiver<bool>,
+}
+
+impl Events {
+    pub fn new(rx: mpsc::Receiver<BlockEvent>, paused: watch::Receiver<bool>) -> Self {
+        Self { rx, paused }
+    }
+
+    /// Sleep for `duration`, waking early if the block is clicked or asked to
+    /// refresh. Returns the click, if there was one. While the bar is paused
+    /// this keeps waiting, then returns as soon as it's resumed so the block
+    /// is up to date when it's shown again.
+    pub async fn wait(&mut self, duration: Duration) -> Option<ClickEvent> {
+        match self.next(duration).await {
+            Some(BlockEvent::Click(click)) => Some(click),
+            _ => None,
+        }
+    }
+
+    /// Like [`Events::wait`], but returns whatever woke the block, for
+    /// blocks that take commands
+    pub async fn next(&mut self, duration: Duration) -> Option<BlockEvent> {
+        let event = tokio::select! {
+            Some(event) = self.rx.recv() => Some(event),
+            _ = sleep(duration) => None,
+            Ok(_) = self.paused.wait_for(|paused| *paused) => None,
+        };
+        let _ = self.paused.wait_for(|paused| !paused).await;
+        event
+    }
+
+    /// Whether the bar is hidden, for use with [`while_shown`] alongside
+    /// waiting for events
+    pub fn paused(&self) -> watch::Receiver<bool> {
+        self.paused.clone()
+    }
+}
+
+/// Poll `future` only while the bar is shown, so a block following a
+/// command's output leaves it unread while the bar is paused and catches up
+/// once it's resumed
+pub async fn while_shown<F: Future>(paused: &mut watch::Receiver<bool>, future: F) -> F::Output {
+    let mut future = pin!(future);
+    loop {
+        let _ = paused.wait_for(|paused| !paused).await;
+        tokio::select! {
+            output = &mut future => return output,
+            Ok(_) = paused.wait_for(|paused| *paused) => {}
+        }
+    }
+}
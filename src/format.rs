@@ -0,0 +1,391 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use tokio::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::locale::tr;
+
+/// A parsed format string such as `"{icon} {volume}%"`.
+///
+/// Placeholders are written as `{name}` and literal braces as `{{` and `}}`.
+/// Anything between `[` and `]` is only shown when every placeholder in it
+/// has a value, e.g. `"{title}[ ({album})]"`. Literal brackets are written as
+/// `[[` and `]]`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+    Section(Vec<Segment>),
+}
+
+impl Template {
+    pub fn parse(format: &str) -> Result<Self> {
+        // The sections being parsed, innermost last
+        let mut sections = vec![Vec::new()];
+        let mut literal = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            let segments = sections.last_mut().unwrap();
+            match c {
+                '{' | '}' | '[' | ']' if chars.peek() == Some(&c) => {
+                    chars.next();
+                    literal.push(c);
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => bail!("Unclosed placeholder in format string {format:?}"),
+                        }
+                    }
+                    push_literal(segments, &mut literal);
+                    segments.push(Segment::Placeholder(name.trim().to_string()));
+                }
+                '}' => bail!("Unmatched '}}' in format string {format:?}"),
+                '[' => {
+                    push_literal(segments, &mut literal);
+                    sections.push(Vec::new());
+                }
+                ']' => {
+                    push_literal(segments, &mut literal);
+                    if sections.len() == 1 {
+                        bail!("Unmatched ']' in format string {format:?}");
+                    }
+                    let section = sections.pop().unwrap();
+                    sections.last_mut().unwrap().push(Segment::Section(section));
+                }
+                c => literal.push(c),
+            }
+        }
+        if sections.len() > 1 {
+            bail!("Unclosed '[' in format string {format:?}");
+        }
+        let mut segments = sections.pop().unwrap();
+        push_literal(&mut segments, &mut literal);
+        Ok(Self { segments })
+    }
+
+    /// Fill in the template, looking up each placeholder with `value`.
+    /// Placeholders the block doesn't know about are left empty.
+    pub fn render(&self, value: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        render_segments(&self.segments, &value, &mut out);
+        out
+    }
+
+    /// Whether the template has the placeholder `name`, for values that are
+    /// only worth fetching if they're shown
+    pub fn uses(&self, name: &str) -> bool {
+        fn uses(segments: &[Segment], name: &str) -> bool {
+            segments.iter().any(|segment| match segment {
+                Segment::Literal(_) => false,
+                Segment::Placeholder(placeholder) => placeholder == name,
+                Segment::Section(section) => uses(section, name),
+            })
+        }
+        uses(&self.segments, name)
+    }
+}
+
+fn push_literal(segments: &mut Vec<Segment>, literal: &mut String) {
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(std::mem::take(literal)));
+    }
+}
+
+/// Render `segments` onto `out`, returning whether every placeholder had a
+/// value
+fn render_segments(
+    segments: &[Segment],
+    value: &impl Fn(&str) -> Option<String>,
+    out: &mut String,
+) -> bool {
+    let mut complete = true;
+    for segment in segments {
+        match segment {
+            Segment::Literal(s) => out.push_str(s),
+            Segment::Placeholder(name) => match value(name) {
+                Some(v) if !v.is_empty() => out.push_str(&v),
+                _ => complete = false,
+            },
+            Segment::Section(section) => {
+                let mut rendered = String::new();
+                if render_segments(section, value, &mut rendered) {
+                    out.push_str(&rendered);
+                }
+            }
+        }
+    }
+    complete
+}
+
+impl TryFrom<String> for Template {
+    type Error = anyhow::Error;
+
+    fn try_from(format: String) -> Result<Self> {
+        Self::parse(&format)
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let minutes = total_seconds / 60;
+    let seconds = total_seconds % 60;
+    format!("{:02}:{:02}", minutes, seconds)
+}
+
+/// Hours, minutes, and seconds, such as "01:05:09"
+pub fn format_hms(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+    format!("{hours:02}:{minutes:02}:{seconds:02}")
+}
+
+/// A short human readable countdown such as "1h 05m", "12m", or "40s"
+pub fn format_countdown(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    if hours > 0 {
+        let (hours, minutes) = (hours.to_string(), format!("{minutes:02}"));
+        tr(
+            "duration-hours",
+            &[("hours", &hours), ("minutes", &minutes)],
+        )
+    } else if minutes > 0 {
+        tr("duration-minutes", &[("minutes", &minutes.to_string())])
+    } else {
+        tr(
+            "duration-seconds",
+            &[("seconds", &total_seconds.to_string())],
+        )
+    }
+}
+
+/// A short human readable size such as "512M" or "5.2G", in powers of 1024
+pub fn format_bytes(bytes: f64) -> String {
+    let mut size = bytes / 1024.0;
+    let mut units = ["K", "M", "G", "T", "P"].into_iter().peekable();
+    let mut unit = units.next().unwrap();
+    while size >= 1024.0 && units.peek().is_some() {
+        size /= 1024.0;
+        unit = units.next().unwrap();
+    }
+    if size < 10.0 {
+        format!("{size:.1}{unit}")
+    } else {
+        format!("{size:.0}{unit}")
+    }
+}
+
+/// Cut `s` down to at most `max_len` graphemes, ending it with an ellipsis
+/// if anything was removed
+pub fn truncate(s: &str, max_len: usize) -> String {
+    match s.grapheme_indices(true).nth(max_len) {
+        Some((offset, _)) => format!("{}…", s[..offset].trim_end()),
+        None => s.to_string(),
+    }
+}
+
+/// Which part of "Artist - Title" gives way when a song is too long
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncation {
+    /// Cut off the end, which can lose the whole title to a long list of
+    /// artists
+    #[default]
+    End,
+    /// Shorten the artists first, keeping as much of the title as fits
+    Artist,
+}
+
+/// "Artist - Title" in at most `max_len` graphemes, cut down according to
+/// `truncation`. With [`Truncation::Artist`], more than one artist becomes
+/// "First et al.", then the artists are cut short, and the title is only
+/// cut once there's no room left for any of them.
+pub fn truncate_song(
+    artists: &[String],
+    title: &str,
+    max_len: usize,
+    truncation: Truncation,
+) -> String {
+    let joined = match artists.len() {
+        2 => artists.join(" & "),
+        _ => artists.join(", "),
+    };
+    let song = format!("{joined} - {title}");
+    let len = |s: &str| s.graphemes(true).count();
+    if truncation == Truncation::End || len(&song) <= max_len {
+        return truncate(&song, max_len);
+    }
+    if artists.len() > 1 {
+        let song = format!("{} et al. - {title}", artists[0]);
+        if len(&song) <= max_len {
+            return song;
+        }
+    }
+    // Enough of the artist to be recognisable, plus the ellipsis
+    const MIN_ARTIST: usize = 4;
+    let room = max_len.saturating_sub(len(title) + len(" - "));
+    if room > MIN_ARTIST {
+        return format!("{} - {title}", truncate(&joined, room - 1));
+    }
+    truncate(title, max_len)
+}
+
+/// A `width` graphemes wide window onto `s`, scrolled along by `offset`
+/// graphemes and wrapping round with `gap` between the end and the start
+pub fn marquee(s: &str, width: usize, offset: usize, gap: &str) -> String {
+    let graphemes: Vec<_> = s.graphemes(true).chain(gap.graphemes(true)).collect();
+    graphemes
+        .iter()
+        .cycle()
+        .skip(offset % graphemes.len().max(1))
+        .take(width)
+        .copied()
+        .collect()
+}
+
+/// A tiny bar chart such as "▁▃▅█", with a character for each fraction picked
+/// from `chars`, which go from empty to full
+pub fn sparkline(fractions: impl IntoIterator<Item = f64>, chars: &str) -> String {
+    let chars: Vec<char> = chars.chars().collect();
+    let Some(last) = chars.len().checked_sub(1) else {
+        return String::new();
+    };
+    fractions
+        .into_iter()
+        .map(|fraction| chars[(fraction.clamp(0.0, 1.0) * last as f64).round() as usize])
+        .collect()
+}
+
+/// A bar such as "▰▰▱▱▱" that's `fraction` full, drawn with the first
+/// character of `chars` for the filled part and the second for the rest
+pub fn progress_bar(fraction: f64, width: usize, chars: &str) -> String {
+    let mut chars = chars.chars();
+    let filled_char = chars.next().unwrap_or('▰');
+    let empty_char = chars.next().unwrap_or('▱');
+    let filled = (fraction.clamp(0.0, 1.0) * width as f64).round() as usize;
+    let mut bar: String = std::iter::repeat_n(filled_char, filled).collect();
+    bar.extend(std::iter::repeat_n(empty_char, width - filled));
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: &str, values: &[(&str, &str)]) -> String {
+        Template::parse(format).unwrap().render(|name| {
+            values
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn templates_fill_in_placeholders() {
+        let values = [("icon", "🔊"), ("volume", "45")];
+        assert_eq!(render("{icon} {volume}%", &values), "🔊 45%");
+        assert_eq!(render("{ volume }", &values), "45");
+        assert_eq!(render("{icon}{unknown}", &values), "🔊");
+        assert_eq!(render("{{{volume}}} [[x]]", &values), "{45} [x]");
+    }
+
+    #[test]
+    fn template_sections_need_every_value() {
+        let values = [("title", "Title"), ("album", "Album"), ("empty", "")];
+        assert_eq!(render("{title}[ ({album})]", &values), "Title (Album)");
+        assert_eq!(render("{title}[ ({year})]", &values), "Title");
+        assert_eq!(render("{title}[ {empty}]", &values), "Title");
+        // An inner section that's dropped doesn't drop the outer one
+        assert_eq!(render("[{album}[ {year}]!]", &values), "Album!");
+    }
+
+    #[test]
+    fn bad_templates_are_rejected() {
+        for format in ["{icon", "icon}", "[{icon}", "{icon}]"] {
+            assert!(Template::parse(format).is_err(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn templates_know_which_placeholders_they_use() {
+        let template = Template::parse("{icon}[ {album}]").unwrap();
+        assert!(template.uses("icon"));
+        assert!(template.uses("album"));
+        assert!(!template.uses("title"));
+    }
+
+    #[test]
+    fn durations_are_formatted() {
+        assert_eq!(format_duration(Duration::from_secs(125)), "02:05");
+        assert_eq!(format_hms(Duration::from_secs(3909)), "01:05:09");
+        assert_eq!(format_countdown(Duration::from_secs(3900)), "1h 05m");
+        assert_eq!(format_countdown(Duration::from_secs(720)), "12m");
+        assert_eq!(format_countdown(Duration::from_secs(40)), "40s");
+    }
+
+    #[test]
+    fn sizes_are_formatted_in_powers_of_1024() {
+        assert_eq!(format_bytes(512.0 * 1024.0 * 1024.0), "512M");
+        assert_eq!(format_bytes(5.2 * 1024.0 * 1024.0 * 1024.0), "5.2G");
+        assert_eq!(format_bytes(100.0), "0.1K");
+        assert_eq!(format_bytes(2048.0_f64.powi(6)), "65536P");
+    }
+
+    #[test]
+    fn truncating_counts_graphemes() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("a long title", 6), "a long…");
+        assert_eq!(truncate("👩‍👩‍👧👍🏽 done", 2), "👩‍👩‍👧👍🏽…");
+    }
+
+    #[test]
+    fn songs_can_keep_their_titles() {
+        let artists = ["Artist One".to_string(), "Artist Two".to_string()];
+        let song = |max_len, truncation| truncate_song(&artists, "Title", max_len, truncation);
+        assert_eq!(
+            song(40, Truncation::Artist),
+            "Artist One & Artist Two - Title"
+        );
+        assert_eq!(song(20, Truncation::End), "Artist One & Artist…");
+        assert_eq!(song(25, Truncation::Artist), "Artist One et al. - Title");
+        assert_eq!(song(24, Truncation::Artist), "Artist One & Ar… - Title");
+        assert_eq!(song(16, Truncation::Artist), "Artist… - Title");
+        assert_eq!(song(8, Truncation::Artist), "Title");
+        assert_eq!(song(4, Truncation::Artist), "Titl…");
+    }
+
+    #[test]
+    fn marquees_wrap_round() {
+        assert_eq!(marquee("abcdef", 4, 0, " | "), "abcd");
+        assert_eq!(marquee("abcdef", 4, 4, " | "), "ef |");
+        assert_eq!(marquee("abcdef", 4, 9, " | "), "abcd");
+        assert_eq!(marquee("", 4, 3, ""), "");
+    }
+
+    #[test]
+    fn sparklines_pick_a_character_for_each_fraction() {
+        assert_eq!(sparkline([0.0, 0.5, 1.0, 2.0, -1.0], "▁▃▅█"), "▁▅██▁");
+        assert_eq!(sparkline([0.5], ""), "");
+    }
+
+    #[test]
+    fn progress_bars_are_filled_to_the_fraction() {
+        assert_eq!(progress_bar(0.4, 5, "▰▱"), "▰▰▱▱▱");
+        assert_eq!(progress_bar(1.5, 3, "#-"), "###");
+        assert_eq!(progress_bar(0.5, 4, ""), "▰▰▱▱");
+    }
+}
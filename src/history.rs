@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Days, Local};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::time::Duration;
+
+use crate::format::format_countdown;
+use crate::locale::tr;
+use crate::notify::notify;
+use crate::system;
+
+/// Only set when `history = true` is in the config
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// How a statistic is added up and shown
+enum Unit {
+    Seconds,
+    Count,
+}
+
+/// Something blocks count for `subar report`
+pub struct Stat {
+    /// Also the message shown for it in the report, after `report-`
+    name: &'static str,
+    unit: Unit,
+}
+
+pub static MPD_LISTENED: Stat = Stat {
+    name: "mpd-listened",
+    unit: Unit::Seconds,
+};
+pub static MPD_SONGS: Stat = Stat {
+    name: "mpd-songs",
+    unit: Unit::Count,
+};
+
+pub static POMODOROS: Stat = Stat {
+    name: "pomodoros",
+    unit: Unit::Count,
+};
+
+pub static BATTERY_CHARGES: Stat = Stat {
+    name: "battery-charges",
+    unit: Unit::Count,
+};
+
+/// In the order they're reported
+static STATS: [&Stat; 4] = [&MPD_LISTENED, &MPD_SONGS, &POMODOROS, &BATTERY_CHARGES];
+
+/// One line of the history file
+#[derive(Serialize, Deserialize)]
+struct Record {
+    /// RFC 3339
+    time: String,
+    stat: String,
+    value: f64,
+}
+
+pub fn init(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// `$XDG_STATE_HOME/subar/history.jsonl`
+fn path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(dir.join("subar").join("history.jsonl"))
+}
+
+/// Add `value` to `stat` if history is kept. Failures are only logged since
+/// the statistics are a nicety.
+pub async fn record(stat: &Stat, value: f64, time: DateTime<Local>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(path) = path() else {
+        return;
+    };
+    let record = Record {
+        time: time.to_rfc3339(),
+        stat: stat.name.to_string(),
+        value,
+    };
+    let result = async {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(&line).await?;
+        anyhow::Ok(())
+    };
+    if let Err(err) = result.await {
+        eprintln!("Couldn't write {}. {err}", path.display());
+    }
+}
+
+/// The totals of each statistic in `history` from the last `days` days
+/// until `now`, one per line
+pub fn summarise(history: &str, days: u64, now: DateTime<Local>) -> String {
+    let since = now
+        .date_naive()
+        .checked_sub_days(Days::new(days.saturating_sub(1)))
+        .and_then(|day| {
+            day.and_hms_opt(0, 0, 0)?
+                .and_local_timezone(Local)
+                .earliest()
+        })
+        .unwrap_or(now);
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for line in history.lines().filter(|l| !l.trim().is_empty()) {
+        // Lines from a newer version, or cut short by a crash, are skipped
+        let Ok(record) = serde_json::from_str::<Record>(line) else {
+            continue;
+        };
+        let Ok(time) = DateTime::parse_from_rfc3339(&record.time) else {
+            continue;
+        };
+        if time < since || time > now {
+            continue;
+        }
+        if let Some(stat) = STATS.iter().find(|s| s.name == record.stat) {
+            *totals.entry(stat.name).or_default() += record.value;
+        }
+    }
+    let lines: Vec<_> = STATS
+        .iter()
+        .filter_map(|stat| {
+            let total = totals.get(stat.name)?;
+            let value = match stat.unit {
+                Unit::Seconds => format_countdown(Duration::from_secs_f64(*total)),
+                Unit::Count => format!("{total:.0}"),
+            };
+            Some(tr(&format!("report-{}", stat.name), &[("value", &value)]))
+        })
+        .collect();
+    if lines.is_empty() {
+        return tr("report-empty", &[]);
+    }
+    lines.join("\n")
+}
+
+/// `subar report [day|week] [--notify]`
+pub async fn report(args: &[String]) -> Result<()> {
+    let mut days = 7;
+    let mut send = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "day" => days = 1,
+            "week" => days = 7,
+            "--notify" => send = true,
+            // Already read for the translation to use
+            "--config" => {
+                args.next();
+            }
+            _ => bail!("Usage: subar report [day|week] [--notify]"),
+        }
+    }
+    let path = path().context("Couldn't find the history file without $HOME")?;
+    let history = match tokio::fs::read_to_string(&path).await {
+        Ok(history) => history,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("Couldn't read {}", path.display())),
+    };
+    let title = tr(
+        if days == 1 {
+            "report-today"
+        } else {
+            "report-week"
+        },
+        &[],
+    );
+    let summary = summarise(&history, days, Local::now());
+    if send {
+        notify(&system::real(), &title, &summary).await;
+    } else {
+        println!("{title}\n{summary}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::Mock;
+    use crate::system::System;
+
+    #[test]
+    fn reports_add_up_history() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let now = mock.now();
+        let record = |days_ago: i64, stat: &str, value: f64| {
+            let time = now - chrono::Duration::days(days_ago);
+            format!(
+                r#"{{"time":"{}","stat":"{stat}","value":{value}}}"#,
+                time.to_rfc3339()
+            )
+        };
+        let history = [
+            record(0, "mpd-listened", 600.0),
+            record(0, "mpd-songs", 3.0),
+            record(1, "mpd-listened", 3000.0),
+            record(8, "mpd-listened", 9000.0),
+            record(0, "bandwidth", 1.0),
+            r#"{"time":"2026-10-14T09:"#.to_string(),
+        ]
+        .join("\n");
+        let week = summarise(&history, 7, now);
+        assert_eq!(week, "Time listening to MPD: 1h 00m\nSongs played: 3");
+        let today = summarise(&history, 1, now);
+        assert_eq!(today, "Time listening to MPD: 10m\nSongs played: 3");
+        let nothing = summarise("", 1, now);
+        assert!(nothing.starts_with("Nothing recorded yet"));
+    }
+}
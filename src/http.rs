@@ -0,0 +1,56 @@
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use tokio::time::Duration;
+use ureq::Agent;
+
+static HTTP_TIMEOUT: u64 = 10;
+
+fn agent() -> &'static Agent {
+    static AGENT: OnceLock<Agent> = OnceLock::new();
+    AGENT.get_or_init(|| {
+        Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(HTTP_TIMEOUT)))
+            .user_agent(concat!("subar/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .into()
+    })
+}
+
+/// Fetch `url` and return the response body. This blocks, so async code
+/// should call it from `spawn_blocking`.
+pub fn get(url: &str) -> Result<String> {
+    get_with_headers(url, &[])
+}
+
+pub fn get_with_headers(url: &str, headers: &[(String, String)]) -> Result<String> {
+    let mut request = agent().get(url);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    request
+        .call()
+        .with_context(|| format!("Couldn't fetch {url}"))?
+        .body_mut()
+        .read_to_string()
+        .with_context(|| format!("Couldn't read response from {url}"))
+}
+
+/// Fetch `url` on a blocking thread
+pub async fn fetch_with_headers(url: &str, headers: Vec<(String, String)>) -> Result<String> {
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || get_with_headers(&url, &headers)).await?
+}
+
+/// Percent-encode `s` for use in a URL query or path segment
+pub fn encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
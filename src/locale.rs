@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::config_dir;
+
+/// The messages blocks fall back to when a translation doesn't have them
+static ENGLISH: &str = include_str!("../locales/en.ftl");
+
+static LOCALE: OnceLock<Messages> = OnceLock::new();
+
+/// Messages from a Fluent file. Only a subset of the syntax is supported:
+///
+/// - messages, `id = value`, whose ids are ASCII letters, digits, `-`, and
+///   `_`, starting with a letter
+/// - multiline values, whose indented lines are trimmed and joined with
+///   newlines. Blank lines within them are dropped.
+/// - `#`, `##`, and `###` comments at the start of a line
+/// - `{ $variable }` placeables, filled in by the block, and
+///   `{ "literal" }` ones, for text like braces or leading spaces
+///
+/// Terms, attributes, selectors (and so plurals), message references,
+/// functions, and escape sequences are errors, rather than being shown as
+/// they're written.
+struct Messages(HashMap<String, Vec<Part>>);
+
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+impl Messages {
+    fn parse(source: &str) -> Result<Self> {
+        // The line each message starts on, its id, and its value
+        let mut values: Vec<(usize, &str, String)> = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Indented lines continue the previous message
+            if line.starts_with(' ') {
+                let Some((_, _, value)) = values.last_mut() else {
+                    bail!("Line {number} continues a message that hasn't started");
+                };
+                let line = line.trim();
+                if line.starts_with('.') {
+                    bail!("Line {number} is an attribute, which isn't supported");
+                }
+                if line.starts_with(['[', '*']) {
+                    bail!("Line {number} is a variant, but selectors aren't supported");
+                }
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line);
+                continue;
+            }
+            let Some((id, value)) = line.split_once('=') else {
+                bail!("Line {number} isn't a message");
+            };
+            let id = id.trim();
+            if id.starts_with('-') {
+                bail!("Line {number} is the term {id:?}, but terms aren't supported");
+            }
+            if !is_identifier(id) {
+                bail!("Line {number} has an invalid message name {id:?}");
+            }
+            values.push((number, id, value.trim().to_string()));
+        }
+        let mut messages = HashMap::new();
+        for (number, id, value) in values {
+            if value.is_empty() {
+                bail!("Line {number} has no text for {id:?}");
+            }
+            let parts = parse_value(&value)
+                .with_context(|| format!("Line {number} has an invalid message {id:?}"))?;
+            messages.insert(id.to_string(), parts);
+        }
+        Ok(Self(messages))
+    }
+
+    /// The text of message `id`, with its variables filled in from `args`.
+    /// Missing ones are left as `$name`.
+    fn format(&self, id: &str, args: &[(&str, &str)]) -> Option<String> {
+        let parts = self.0.get(id)?;
+        let mut text = String::new();
+        for part in parts {
+            match part {
+                Part::Text(part) => text.push_str(part),
+                Part::Variable(name) => match args.iter().find(|(n, _)| n == name) {
+                    Some((_, value)) => text.push_str(value),
+                    None => {
+                        text.push('$');
+                        text.push_str(name);
+                    }
+                },
+            }
+        }
+        Some(text)
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("Couldn't parse {}", path.display()))
+    }
+}
+
+fn english() -> &'static Messages {
+    static MESSAGES: OnceLock<Messages> = OnceLock::new();
+    MESSAGES.get_or_init(|| Messages::parse(ENGLISH).expect("en.ftl is valid"))
+}
+
+/// Load the translation for `locale`, such as "de" or "pt-BR", or for the
+/// locale from the environment if none is given. Without one, or if there's
+/// no file for the locale, everything stays in English.
+pub fn init(locale: Option<&str>) -> Result<()> {
+    let configured = locale.is_some();
+    let Some(locale) = locale.map(str::to_string).or_else(env_locale) else {
+        return Ok(());
+    };
+    let Some(dir) = config_dir().map(|d| d.join("locales")) else {
+        return Ok(());
+    };
+    // Try the whole locale, then just its language
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    for name in [locale.as_str(), language] {
+        let path = dir.join(format!("{name}.ftl"));
+        if path.exists() {
+            let _ = LOCALE.set(Messages::load(&path)?);
+            return Ok(());
+        }
+    }
+    if configured && language != "en" {
+        eprintln!(
+            "Couldn't find a translation for {locale} in {}",
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// The locale for messages set in the environment, e.g. "de_DE.UTF-8" -> "de-DE"
+fn env_locale() -> Option<String> {
+    let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|v| !v.is_empty())?;
+    let locale = value.split(['.', '@']).next()?.replace('_', "-");
+    (!matches!(locale.as_str(), "" | "C" | "POSIX")).then_some(locale)
+}
+
+/// The text of message `id` in the bar's language, with each `{ $name }` in
+/// it replaced by the matching value from `args`
+pub fn tr(id: &str, args: &[(&str, &str)]) -> String {
+    LOCALE
+        .get()
+        .and_then(|l| l.format(id, args))
+        .or_else(|| english().format(id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn is_identifier(id: &str) -> bool {
+    id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Split a message's value into its text and the variables to fill in
+fn parse_value(value: &str) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find(['{', '}']) {
+        text.push_str(&rest[..start]);
+        if rest[start..].starts_with('}') {
+            bail!("Unmatched '}}'");
+        }
+        let inner = &rest[start + 1..];
+        if let Some(literal) = inner.trim_start().strip_prefix('"') {
+            let Some((literal, after)) = literal.split_once('"') else {
+                bail!("Unclosed '\"'");
+            };
+            if literal.contains('\\') {
+                bail!("Escape sequences aren't supported");
+            }
+            text.push_str(literal);
+            rest = after
+                .trim_start()
+                .strip_prefix('}')
+                .context("Unclosed '{'")?;
+            continue;
+        }
+        let Some(len) = inner.find('}') else {
+            bail!("Unclosed '{{'");
+        };
+        let placeable = inner[..len].trim();
+        if let Some(name) = placeable.strip_prefix('$') {
+            if placeable.contains("->") {
+                bail!("Selectors aren't supported");
+            }
+            if !is_identifier(name) {
+                bail!("Invalid variable {placeable:?}");
+            }
+            parts.push(Part::Text(std::mem::take(&mut text)));
+            parts.push(Part::Variable(name.to_string()));
+        } else {
+            bail!("Only {{ $variable }} and {{ \"literal\" }} placeables are supported, not {{ {placeable} }}");
+        }
+        rest = &inner[len + 1..];
+    }
+    text.push_str(rest);
+    parts.push(Part::Text(text));
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str, id: &str, args: &[(&str, &str)]) -> Option<String> {
+        Messages::parse(source).unwrap().format(id, args)
+    }
+
+    #[test]
+    fn english_messages_are_valid() {
+        assert!(Messages::parse(ENGLISH).is_ok());
+    }
+
+    #[test]
+    fn variables_are_filled_in() {
+        let source = "duration = { $hours }h {$minutes}m";
+        let args = [("hours", "1"), ("minutes", "05")];
+        assert_eq!(format(source, "duration", &args).unwrap(), "1h 05m");
+        assert_eq!(format(source, "missing", &args), None);
+    }
+
+    #[test]
+    fn missing_variables_are_left_in() {
+        let source = "duration = { $hours }h { $minutes }m";
+        let args = [("hours", "1")];
+        assert_eq!(format(source, "duration", &args).unwrap(), "1h $minutesm");
+    }
+
+    #[test]
+    fn literals_are_copied() {
+        let source = r#"braces = { "{" }{ $name }{ "}" }{ "  " }"#;
+        let args = [("name", "x")];
+        assert_eq!(format(source, "braces", &args).unwrap(), "{x}  ");
+    }
+
+    #[test]
+    fn multiline_values_are_joined_with_newlines() {
+        let source = "
+# A comment
+## A group comment
+### A file comment
+first = One
+    two
+
+      three
+second =
+    Four
+";
+        assert_eq!(format(source, "first", &[]).unwrap(), "One\ntwo\nthree");
+        assert_eq!(format(source, "second", &[]).unwrap(), "Four");
+    }
+
+    #[test]
+    fn unsupported_syntax_is_rejected() {
+        for source in [
+            "  continued = nothing",
+            "no value",
+            "1st = number",
+            "name with spaces = text",
+            "empty =",
+            "-brand = Subar",
+            "message = text\n    .title = An attribute",
+            "message = { $count ->\n    [one] one item\n   *[other] { $count } items\n}",
+            "message = { $count -> }",
+            "message = { other-message }",
+            "message = { NUMBER($count) }",
+            r#"message = { "\u00A0" }"#,
+            r#"message = { "unclosed }"#,
+            r#"message = { "literal" "#,
+            "message = { $ }",
+            "message = { $count",
+            "message = count }",
+        ] {
+            assert!(Messages::parse(source).is_err(), "{source:?}");
+        }
+    }
+}
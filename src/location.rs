@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::Duration;
+
+use crate::system::Sys;
+
+/// Guesses the location from the IP address, without a key
+static IP_LOCATION_URL: &str = "https://ipinfo.io/json";
+/// How long a found location is used before looking again, for laptops that
+/// move between cities
+static LOCATION_MAX_AGE: u64 = 30 * 60;
+
+/// How to find the location when no coordinates are configured
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locate {
+    /// GeoClue, then the IP address if that fails
+    #[default]
+    Auto,
+    Geoclue,
+    Ip,
+}
+
+/// The configured coordinates, or wherever the bar turns out to be
+pub struct Location {
+    fixed: Option<(f64, f64)>,
+    locate: Locate,
+    found: Option<((f64, f64), DateTime<Local>)>,
+}
+
+impl Location {
+    pub fn new(latitude: Option<f64>, longitude: Option<f64>, locate: Locate) -> Self {
+        Self {
+            fixed: latitude.zip(longitude),
+            locate,
+            found: None,
+        }
+    }
+
+    /// The configured coordinates, if there are any
+    pub fn fixed(&self) -> Option<(f64, f64)> {
+        self.fixed
+    }
+
+    /// The latitude and longitude, looked up again once the last ones found
+    /// are half an hour old
+    pub async fn coordinates(&mut self, sys: &Sys) -> Result<(f64, f64)> {
+        if let Some(fixed) = self.fixed {
+            return Ok(fixed);
+        }
+        let now = sys.now();
+        if let Some((found, at)) = self.found {
+            if (now - at).to_std().unwrap_or_default() < Duration::from_secs(LOCATION_MAX_AGE) {
+                return Ok(found);
+            }
+        }
+        let found = match self.locate {
+            Locate::Geoclue => geoclue(sys).await?,
+            Locate::Ip => from_ip(sys).await?,
+            Locate::Auto => match geoclue(sys).await {
+                Ok(found) => found,
+                Err(err) => {
+                    eprintln!("Couldn't get the location from GeoClue. {err:#}");
+                    from_ip(sys).await?
+                }
+            },
+        };
+        self.found = Some((found, now));
+        Ok(found)
+    }
+}
+
+/// ipinfo.io's guess, which has the coordinates like "-33.8678,151.2073"
+async fn from_ip(sys: &Sys) -> Result<(f64, f64)> {
+    let body = sys.http_get(IP_LOCATION_URL, Vec::new()).await?;
+    let found: Value = serde_json::from_str(&body)?;
+    let (latitude, longitude) = found["loc"]
+        .as_str()
+        .and_then(|loc| loc.split_once(','))
+        .with_context(|| format!("ipinfo.io sent {found}"))?;
+    Ok((latitude.trim().parse()?, longitude.trim().parse()?))
+}
+
+#[cfg(not(feature = "dbus"))]
+async fn geoclue(_sys: &Sys) -> Result<(f64, f64)> {
+    anyhow::bail!("GeoClue needs subar to be built with the dbus feature")
+}
+
+#[cfg(feature = "dbus")]
+use geoclue::geoclue;
+
+#[cfg(feature = "dbus")]
+mod geoclue {
+    use anyhow::{bail, Result};
+    use tokio::time::{sleep, Duration};
+    use zbus::proxy;
+    use zbus::zvariant::OwnedObjectPath;
+
+    use crate::system::Sys;
+
+    /// How long GeoClue gets to find the location after it's asked
+    static GEOCLUE_TIMEOUT: u64 = 10 * 1000;
+    /// GClueAccuracyLevel's city level, which is plenty for the weather
+    static GEOCLUE_CITY: u32 = 4;
+
+    #[proxy(
+        interface = "org.freedesktop.GeoClue2.Manager",
+        default_service = "org.freedesktop.GeoClue2",
+        default_path = "/org/freedesktop/GeoClue2/Manager"
+    )]
+    trait Manager {
+        fn get_client(&self) -> zbus::Result<OwnedObjectPath>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.GeoClue2.Client",
+        default_service = "org.freedesktop.GeoClue2"
+    )]
+    trait Client {
+        fn start(&self) -> zbus::Result<()>;
+        fn stop(&self) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn set_desktop_id(&self, id: &str) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn set_requested_accuracy_level(&self, level: u32) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn location(&self) -> zbus::Result<OwnedObjectPath>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.GeoClue2.Location",
+        default_service = "org.freedesktop.GeoClue2"
+    )]
+    trait Location {
+        #[zbus(property)]
+        fn latitude(&self) -> zbus::Result<f64>;
+        #[zbus(property)]
+        fn longitude(&self) -> zbus::Result<f64>;
+    }
+
+    /// Ask GeoClue on the system bus where we are, waiting for it to find
+    /// out
+    pub async fn geoclue(sys: &Sys) -> Result<(f64, f64)> {
+        let conn = sys.system_bus().await?;
+        let path = ManagerProxy::new(&conn).await?.get_client().await?;
+        let client = ClientProxy::builder(&conn).path(path)?.build().await?;
+        client.set_desktop_id("subar").await?;
+        client.set_requested_accuracy_level(GEOCLUE_CITY).await?;
+        client.start().await?;
+        let step = Duration::from_millis(500);
+        let mut waited = Duration::ZERO;
+        // The location stays at "/" until GeoClue has one
+        let path = loop {
+            let path = client.location().await?;
+            if path.as_str() != "/" {
+                break path;
+            }
+            if waited >= Duration::from_millis(GEOCLUE_TIMEOUT) {
+                let _ = client.stop().await;
+                bail!("GeoClue didn't find the location");
+            }
+            sleep(step).await;
+            waited += step;
+        };
+        let location = LocationProxy::builder(&conn).path(path)?.build().await?;
+        let found = (location.latitude().await?, location.longitude().await?);
+        let _ = client.stop().await;
+        Ok(found)
+    }
+}
@@ -1,260 +1,177 @@
-use std::env::{self, args};
-use std::future::Future;
-
-use anyhow::Result;
-use chrono::Local;
-use mpd_client::{commands, Client};
-use serde::Serialize;
-use tokio::net::{TcpStream, UnixStream};
-use tokio::process::Command;
+// Helpers only some blocks use are left unused when those blocks aren't built
+#![cfg_attr(
+    not(all(
+        feature = "audio",
+        feature = "dbus",
+        feature = "http",
+        feature = "mpd",
+        feature = "script",
+        feature = "weather"
+    )),
+    allow(dead_code)
+)]
+
+mod acpi;
+mod backoff;
+mod bar;
+mod blocks;
+mod bsd;
+#[cfg(any(feature = "http", feature = "weather"))]
+mod cache;
+mod click;
+mod config;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod events;
+mod format;
+mod history;
+#[cfg(feature = "http")]
+mod http;
+mod locale;
+#[cfg(feature = "weather")]
+mod location;
+mod notify;
+mod output;
+#[cfg(feature = "audio")]
+mod pipewire;
+mod replay;
+mod sandbox;
+mod secret;
+mod signals;
+#[cfg(feature = "audio")]
+mod sway;
+mod system;
+mod timezone;
+mod udev;
+mod watchdog;
+
+use std::env::args;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
 use tokio::sync::watch;
-use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
-use unicode_segmentation::UnicodeSegmentation;
 
-static MPD_DEFAULT_HOST: &str = "/run/mpd/socket";
-static MPD_FALLBACK: &str = "🎵 ???";
-static VOL_FALLBACK: &str = "🔊 ???";
-static WEATHER_FALLBACK: &str = "🛰️ ???";
+#[cfg(any(feature = "audio", feature = "mpd", feature = "weather"))]
+use config::BlockConfig;
+use config::Config;
+
 static MAIN_UDPDATE_FREQUENCY: u64 = 100;
-static MPD_UPDATE_FREQUENCY: u64 = 112;
-static VOL_UPDATE_FREQUENCY: u64 = 323;
-static WEATHER_UPDATE_FREQUENCY: u64 = 5137;
-static NOW_PLAYING_MAX_LEN: usize = 70;
+static SIGSTOP: u8 = 19;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
-    let mut tasks = Vec::new();
-    if !args().any(|a| a == "--no-mpd") {
-        tasks.push(Taskmaster::new(mpd_task, MPD_FALLBACK));
+    // Anything other than flags is a command, and all but `report` are for
+    // the running bar
+    let command = args().nth(1).filter(|a| !a.starts_with('-'));
+    let command_args: Vec<_> = args().skip(2).collect();
+    if let Some(command) = command.as_ref().filter(|c| *c != "report") {
+        #[cfg(feature = "dbus")]
+        return dbus::control(command, &command_args).await;
+        #[cfg(not(feature = "dbus"))]
+        anyhow::bail!("Can't {command} {command_args:?}. subar was built without D-Bus.");
     }
-    if !args().any(|a| a == "--no-vol") {
-        tasks.push(Taskmaster::new(volume_task, VOL_FALLBACK));
+    let mut config = Config::load(path_arg("--config")?)?;
+    locale::init(config.locale.as_deref())?;
+    history::init(config.history);
+    if command.is_some() {
+        return history::report(&command_args).await;
     }
-    if !args().any(|a| a == "--no-bom") {
-        tasks.push(Taskmaster::new(weather_task, WEATHER_FALLBACK));
-    }
-
-    sleep(Duration::from_millis(20)).await;
-    let mut header = Header::default();
-    if args().any(|a| a == "--no-stop-on-hide") {
-        header.cont_signal = 0;
-        header.stop_signal = 0;
-    }
-    println!("{}", serde_json::to_string(&header).unwrap());
-    println!("[");
-    let mut status = StatusLine::default();
-    let sleep_duration = Duration::from_millis(MAIN_UDPDATE_FREQUENCY);
-    loop {
-        for task in &tasks {
-            status.full_text.push_str(&task.status());
-            status.full_text.push(' ');
+    config.blocks.retain(|block| match block.kind {
+        #[cfg(feature = "mpd")]
+        BlockConfig::Mpd(_) => !has_flag("--no-mpd"),
+        #[cfg(feature = "audio")]
+        BlockConfig::Volume(_) => !has_flag("--no-vol"),
+        #[cfg(feature = "weather")]
+        BlockConfig::Weather(_) => !has_flag("--no-bom"),
+        _ => true,
+    });
+    #[cfg(feature = "weather")]
+    for block in &mut config.blocks {
+        if let BlockConfig::Weather(cfg) = &mut block.kind {
+            cfg.check |= has_flag("--check-weather");
         }
-        let now = Local::now();
-        let datetime = now.format("🗓️ %a %b %d 🕛 %T").to_string();
-        status.full_text.push_str(&datetime);
-
-        println!("[{}],", serde_json::to_string(&status).unwrap());
-        sleep(sleep_duration).await;
-        status.full_text.clear();
     }
-}
-
-#[derive(Serialize)]
-struct Header {
-    version: u8,
-    click_events: bool,
-    cont_signal: u8,
-    stop_signal: u8,
-}
-
-impl Default for Header {
-    fn default() -> Self {
-        Self {
-            version: 1,
-            click_events: false,
-            cont_signal: 18,
-            stop_signal: 19,
-        }
+    if has_flag("--no-stop-on-hide") {
+        config.stop_signal = 0;
+        config.cont_signal = 0;
     }
-}
-
-#[derive(Default, Serialize)]
-struct StatusLine {
-    full_text: String,
-}
-
-pub struct Taskmaster {
-    _handle: JoinHandle<Result<()>>,
-    rx: watch::Receiver<String>,
-}
-
-type TaskFn<R> = fn(watch::Sender<String>) -> R;
-
-impl Taskmaster {
-    pub fn new<'a>(
-        task_fn: TaskFn<impl Future<Output = Result<()>> + Send + 'a + 'static>,
-        fallback: &'a str,
-    ) -> Self {
-        let (tx, rx) = watch::channel(fallback.to_string());
-        let _handle = tokio::spawn(task_fn(tx));
-        Self { _handle, rx }
-    }
-    pub fn status(&self) -> watch::Ref<'_, String> {
-        self.rx.borrow()
+    let (pause_tx, paused) = watch::channel(false);
+    // SIGSTOP can't be handled, and suspends the process without our help.
+    // Nothing notices it being continued, so blocks aren't refreshed then.
+    if config.stop_signal != 0 && config.cont_signal != 0 && config.stop_signal != SIGSTOP {
+        let (stop, cont) = (config.stop_signal.into(), config.cont_signal.into());
+        tokio::spawn(async move {
+            if let Err(err) = signals::pause_on_signals(stop, cont, pause_tx).await {
+                eprintln!("Couldn't handle hiding the bar. {err:#}");
+            }
+        });
     }
-}
+    let sys: system::Sys = match (path_arg("--record")?, path_arg("--replay")?) {
+        (Some(path), _) => replay::Recorder::create(&path)?,
+        (None, Some(path)) => replay::Replayer::load(&path)?,
+        (None, None) => system::real(),
+    };
+    let tasks = bar::spawn(config.blocks, &sys, &paused);
 
-async fn weather_task(tx: watch::Sender<String>) -> Result<()> {
-    let mut bom_args = vec!["current"];
-    if args().any(|a| a == "--check-weather") {
-        bom_args.push("--check");
+    sleep(Duration::from_millis(20)).await;
+    let mut header = bar::Header::default();
+    header.stop_signal = config.stop_signal;
+    header.cont_signal = config.cont_signal;
+    header.click_events = tasks.iter().any(|t| t.wants_clicks());
+    if header.click_events {
+        let routes = tasks.iter().map(|t| t.route()).collect();
+        tokio::spawn(click::read_clicks(routes, sys.clone()));
     }
-    loop {
-        let Ok(cmd) = Command::new("bom-buddy").args(&bom_args).output().await else {
-            tx.send(WEATHER_FALLBACK.to_string())?;
-            sleep(Duration::from_millis(WEATHER_UPDATE_FREQUENCY)).await;
-            continue;
-        };
-        let weather = if cmd.status.success() {
-            String::from_utf8(cmd.stdout)?
-        } else {
-            WEATHER_FALLBACK.to_string()
-        };
-        tx.send(weather)?;
-        sleep(Duration::from_millis(WEATHER_UPDATE_FREQUENCY)).await;
+    #[cfg(feature = "dbus")]
+    if config.dbus {
+        let handles = tasks.iter().map(|t| t.dbus_handle()).collect();
+        let sys = sys.clone();
+        tokio::spawn(async {
+            if let Err(err) = dbus::serve(handles, sys).await {
+                eprintln!("Couldn't start the D-Bus service. {err}");
+            }
+        });
     }
-}
-
-async fn volume_task(tx: watch::Sender<String>) -> Result<()> {
-    loop {
-        let Ok(cmd) = Command::new("wpctl")
-            .arg("get-volume")
-            .arg("@DEFAULT_AUDIO_SINK@")
-            .output()
-            .await
-        else {
-            tx.send(VOL_FALLBACK.to_string())?;
-            sleep(Duration::from_millis(1000)).await;
-            continue;
-        };
-
-        if !cmd.status.success() {
-            tx.send(VOL_FALLBACK.to_string())?;
-            sleep(Duration::from_millis(1000)).await;
-            continue;
+    let mut stdout = io::stdout();
+    let header = serde_json::to_string(&header).unwrap();
+    if bar::write_line(&mut stdout, &header)? && bar::write_line(&mut stdout, "[")? {
+        watchdog::notify_systemd("READY=1");
+        let heartbeat = watchdog::Heartbeat::new();
+        let timeout = (config.watchdog > 0).then(|| Duration::from_secs(config.watchdog));
+        if let Some(timeout) = timeout {
+            watchdog::spawn_thread(heartbeat.clone(), timeout);
         }
-
-        let output = String::from_utf8(cmd.stdout)?;
-
-        let icon = if output.contains("MUTED") {
-            "🔇"
-        } else {
-            "🔊"
-        };
-
-        let volume = &output.trim()[10..12];
-        let status = format!("{} {}%", icon, volume);
-        tx.send(status)?;
-        sleep(Duration::from_millis(VOL_UPDATE_FREQUENCY)).await;
+        let renderer = Arc::new(bar::Renderer {
+            tasks: Mutex::new(tasks),
+            stale: config.stale,
+            loading: config.loading,
+            sys,
+            interval: Duration::from_millis(MAIN_UDPDATE_FREQUENCY),
+            heartbeat: heartbeat.clone(),
+        });
+        watchdog::supervise(&heartbeat, timeout, || {
+            Box::pin(renderer.clone().run(paused.clone()))
+        })
+        .await?;
     }
+    eprintln!("The bar stopped reading the status line. Exiting.");
+    Ok(())
 }
 
-async fn mpd_task(tx: watch::Sender<String>) -> Result<()> {
-    let host = if let Ok(host) = env::var("MPD_HOST") {
-        host
-    } else {
-        MPD_DEFAULT_HOST.to_string()
-    };
-    loop {
-        let connection = if host.starts_with('/') {
-            match UnixStream::connect(&host).await {
-                Ok(conn) => Client::connect(conn).await,
-                Err(err) => {
-                    eprintln!("Couldn't connect to {host}. {err}");
-                    sleep(Duration::from_millis(1000)).await;
-                    continue;
-                }
-            }
-        } else {
-            match TcpStream::connect(&host).await {
-                Ok(conn) => Client::connect(conn).await,
-                Err(err) => {
-                    eprintln!("Couldn't connect to {host}. {err}");
-                    sleep(Duration::from_millis(1000)).await;
-                    continue;
-                }
-            }
-        };
-
-        let (client, _) = match connection {
-            Ok(ok) => ok,
-            Err(err) => {
-                eprintln!("Couldn't connect to {host}. {err}");
-                sleep(Duration::from_millis(1000)).await;
-                continue;
-            }
-        };
-
-        loop {
-            let Ok(now_playing) = get_now_playing(&client).await else {
-                tx.send(MPD_FALLBACK.to_string())?;
-                break;
-            };
-            tx.send(now_playing)?;
-            sleep(Duration::from_millis(MPD_UPDATE_FREQUENCY)).await;
-        }
-    }
+fn has_flag(flag: &str) -> bool {
+    args().any(|a| a == flag)
 }
 
-async fn get_now_playing(client: &Client) -> Result<String> {
-    let Some(current) = client.command(commands::CurrentSong).await? else {
-        return Ok(MPD_FALLBACK.to_string());
-    };
-
-    let status = client.command(commands::Status).await?;
-    let artists = current.song.artists();
-    let album_artist = current.song.album_artists();
-    let artists = if artists.is_empty() && !album_artist.is_empty() {
-        album_artist
-    } else {
-        artists
-    };
-    let title = if let Some(title) = current.song.title() {
-        title
-    } else {
-        "???"
-    };
-    let artist = match artists.len() {
-        0 => "???".to_string(),
-        1 => artists[0].to_string(),
-        2 => artists.join(" & "),
-        _ => artists.join(", "),
-    };
-    let mut playing = format!("{artist} - {title}");
-    if playing.len() > NOW_PLAYING_MAX_LEN {
-        let mut iter = playing.grapheme_indices(true);
-        if let Some((offset, _)) = iter.nth(NOW_PLAYING_MAX_LEN) {
-            let idx = playing[..offset].trim_end().len();
-            playing.truncate(idx);
-            playing.push('…');
-        }
-    };
-
-    let playback_time = if let Some(elapsed) = status.elapsed {
-        let elapsed = format_duration(elapsed);
-        let duration = format_duration(status.duration.unwrap());
-        format!("{elapsed}/{duration}")
-    } else {
-        "00:00".to_string()
+/// The path given after `flag`, e.g. `--config <path>`
+fn path_arg(flag: &str) -> Result<Option<PathBuf>> {
+    let Some(i) = args().position(|a| a == flag) else {
+        return Ok(None);
     };
-
-    Ok(format!("🎵 {playing} ({playback_time})"))
-}
-
-fn format_duration(duration: Duration) -> String {
-    let total_seconds = duration.as_secs();
-    let minutes = total_seconds / 60;
-    let seconds = total_seconds % 60;
-    format!("{:02}:{:02}", minutes, seconds)
+    let path = args()
+        .nth(i + 1)
+        .with_context(|| format!("{flag} requires a path"))?;
+    Ok(Some(PathBuf::from(path)))
 }
@@ -0,0 +1,32 @@
+use anyhow::{bail, Context, Result};
+use tokio::process::Command;
+
+use crate::system::Sys;
+
+/// Show a desktop notification with notify-send. Failures are only logged
+/// since a missing notification daemon shouldn't break the block.
+pub async fn notify(sys: &Sys, summary: &str, body: &str) {
+    let mut notify_send = Command::new("notify-send");
+    notify_send.arg("--app-name=subar").arg(summary).arg(body);
+    match sys.output(&mut notify_send).await {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => eprintln!("notify-send failed with {}", output.status),
+        Err(err) => eprintln!("Couldn't run notify-send. {err}"),
+    }
+}
+
+/// Read `text` aloud with speech-dispatcher's spd-say
+pub async fn speak(sys: &Sys, text: &str) -> Result<()> {
+    let mut spd_say = Command::new("spd-say");
+    spd_say
+        .args(["--application-name", "subar", "--"])
+        .arg(text);
+    let output = sys
+        .output(&mut spd_say)
+        .await
+        .context("Couldn't run spd-say")?;
+    if !output.status.success() {
+        bail!("spd-say failed with {}", output.status);
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+
+/// What a block wants to show, sent from its task to the renderer
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockOutput {
+    pub full_text: String,
+    /// Shown instead of `full_text` when the bar is short on space
+    pub short_text: Option<String>,
+    /// Text colour as #RRGGBB or #RRGGBBAA
+    pub color: Option<String>,
+    pub background: Option<String>,
+    /// Ask the bar to draw the block as urgent
+    pub urgent: bool,
+    /// Mark the block stale, because the task knows what it's showing is out
+    /// of date
+    pub stale: bool,
+}
+
+impl BlockOutput {
+    pub fn is_empty(&self) -> bool {
+        self.full_text.is_empty()
+    }
+}
+
+impl From<String> for BlockOutput {
+    fn from(full_text: String) -> Self {
+        Self {
+            full_text,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&str> for BlockOutput {
+    fn from(full_text: &str) -> Self {
+        full_text.to_string().into()
+    }
+}
+
+/// A colour for when a reading, like CPU usage, reaches `above`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Threshold {
+    pub above: f64,
+    pub color: String,
+}
+
+impl Threshold {
+    pub fn new(above: f64, color: &str) -> Self {
+        Self {
+            above,
+            color: color.to_string(),
+        }
+    }
+
+    /// The colour of the highest threshold `value` has reached, if any
+    pub fn color(thresholds: &[Threshold], value: f64) -> Option<String> {
+        thresholds
+            .iter()
+            .filter(|t| value >= t.above)
+            .max_by(|a, b| a.above.total_cmp(&b.above))
+            .map(|t| t.color.clone())
+    }
+}
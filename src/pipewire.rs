@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::io;
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+use crate::system::{Reader, Sys};
+
+/// An application playing audio
+pub struct Stream {
+    pub id: u64,
+    pub app: String,
+    pub pid: Option<u32>,
+    /// Percent, as shown by wpctl
+    pub volume: u32,
+    pub muted: bool,
+}
+
+/// An audio device, like speakers or a microphone
+pub struct Node {
+    pub id: u64,
+    pub name: String,
+    /// Like "USB DAC Analog Stereo"
+    pub description: String,
+    /// The description of the port it's using on its card, like
+    /// "Headphones" or "HDMI / DisplayPort"
+    pub port: Option<String>,
+    /// Percent, as shown by wpctl
+    pub volume: u32,
+    pub muted: bool,
+    /// Whether something is playing to it, or recording from it
+    pub running: bool,
+}
+
+/// Every object `pw-dump` prints
+pub async fn dump(sys: &Sys) -> Result<Graph> {
+    let output = sys.output(&mut Command::new("pw-dump")).await?;
+    if !output.status.success() {
+        bail!("pw-dump failed with {}", output.status);
+    }
+    let mut graph = Graph::default();
+    graph.update(serde_json::from_slice(&output.stdout)?);
+    Ok(graph)
+}
+
+fn parse_node(object: &Value, source: bool, graph: &Graph) -> Option<Node> {
+    let props = &object["info"]["props"];
+    let class = if source { "Audio/Source" } else { "Audio/Sink" };
+    if props["media.class"] != class {
+        return None;
+    }
+    let name = props["node.name"].as_str()?.to_string();
+    let description = props["node.description"].as_str().unwrap_or(&name);
+    // Nodes are printed before their params are known
+    let params = object["info"]["params"]["Props"].as_array()?;
+    let params = params.iter().find(|p| p.get("channelVolumes").is_some())?;
+    let (volume, muted) = volume(params);
+    Some(Node {
+        id: object["id"].as_u64()?,
+        description: description.to_string(),
+        port: graph.route(props, source),
+        name,
+        volume,
+        muted,
+        running: object["info"]["state"] == "running",
+    })
+}
+
+/// Every application audio stream, from `pw-dump`
+pub async fn streams(sys: &Sys) -> Result<Vec<Stream>> {
+    let output = sys.output(&mut Command::new("pw-dump")).await?;
+    if !output.status.success() {
+        bail!("pw-dump failed with {}", output.status);
+    }
+    let objects: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    Ok(objects.iter().filter_map(parse_stream).collect())
+}
+
+fn parse_stream(object: &Value) -> Option<Stream> {
+    let props = &object["info"]["props"];
+    if props["media.class"] != "Stream/Output/Audio" {
+        return None;
+    }
+    let app = props["application.name"]
+        .as_str()
+        .or(props["node.name"].as_str())
+        .unwrap_or("???")
+        .to_string();
+    // Older versions of PipeWire give the pid as a string
+    let pid = &props["application.process.id"];
+    let pid = pid
+        .as_u64()
+        .or_else(|| pid.as_str()?.parse().ok())
+        .and_then(|pid| pid.try_into().ok());
+    let (volume, muted) = volume(&object["info"]["params"]["Props"][0]);
+    Some(Stream {
+        id: object["id"].as_u64()?,
+        app,
+        pid,
+        volume,
+        muted,
+    })
+}
+
+/// The percent volume and mute of a node's Props param
+fn volume(params: &Value) -> (u32, bool) {
+    let channels: Vec<f64> = params["channelVolumes"]
+        .as_array()
+        .map(|c| c.iter().filter_map(Value::as_f64).collect())
+        .unwrap_or_default();
+    let linear = if channels.is_empty() {
+        params["volume"].as_f64().unwrap_or(1.0)
+    } else {
+        channels.iter().sum::<f64>() / channels.len() as f64
+    };
+    // Volumes are stored cubed, like wpctl and pavucontrol show them
+    let volume = (linear.cbrt() * 100.0).round() as u32;
+    (volume, params["mute"].as_bool().unwrap_or(false))
+}
+
+/// Reads the JSON arrays `pw-dump --monitor` prints, first of every object
+/// and then of the objects that change
+pub struct Monitor {
+    reader: Reader,
+    buf: Vec<u8>,
+}
+
+impl Monitor {
+    pub fn start(sys: &Sys) -> io::Result<Self> {
+        let mut pw_dump = Command::new("pw-dump");
+        pw_dump.arg("--monitor");
+        Ok(Self {
+            reader: sys.stream(&mut pw_dump)?,
+            buf: Vec::new(),
+        })
+    }
+
+    /// The next set of changes, or None once pw-dump has stopped
+    pub async fn next(&mut self) -> Result<Option<Vec<Value>>> {
+        loop {
+            let mut values = serde_json::Deserializer::from_slice(&self.buf).into_iter();
+            match values.next() {
+                Some(Ok(changes)) => {
+                    let end = values.byte_offset();
+                    self.buf.drain(..end);
+                    return Ok(Some(changes));
+                }
+                // The rest of the array hasn't been printed yet
+                Some(Err(err)) if err.is_eof() => {}
+                Some(Err(err)) => return Err(err.into()),
+                None => {}
+            }
+            let mut chunk = [0; 8192];
+            let len = self.reader.read(&mut chunk).await?;
+            if len == 0 {
+                return Ok(None);
+            }
+            self.buf.extend_from_slice(&chunk[..len]);
+        }
+    }
+}
+
+/// Every PipeWire object by id, kept up to date from what pw-dump prints
+#[derive(Default)]
+pub struct Graph(HashMap<u64, Value>);
+
+impl Graph {
+    pub fn update(&mut self, changes: Vec<Value>) {
+        for change in changes {
+            let Some(id) = change["id"].as_u64() else {
+                continue;
+            };
+            // Removed objects are printed without their info
+            let removed =
+                [change.get("info"), change.get("metadata")].contains(&Some(&Value::Null));
+            if removed {
+                self.0.remove(&id);
+                continue;
+            }
+            match self.0.get_mut(&id) {
+                Some(object) => merge(object, change),
+                None => {
+                    self.0.insert(id, change);
+                }
+            }
+        }
+    }
+
+    /// Every sink, or every source if `source`
+    pub fn nodes(&self, source: bool) -> Vec<Node> {
+        let mut nodes: Vec<_> = self
+            .0
+            .values()
+            .filter_map(|o| parse_node(o, source, self))
+            .collect();
+        nodes.sort_by_key(|node| node.id);
+        nodes
+    }
+
+    /// The description of the route a node's card is using for it, from
+    /// the card's Route params, which say which of the card's devices each
+    /// one is for
+    fn route(&self, props: &Value, source: bool) -> Option<String> {
+        let card = self.0.get(&props["device.id"].as_u64()?)?;
+        let device = &props["card.profile.device"];
+        let direction = if source { "Input" } else { "Output" };
+        let routes = card["info"]["params"]["Route"].as_array()?;
+        let route = routes
+            .iter()
+            .find(|r| r["device"] == *device && r["direction"] == direction)?;
+        Some(route["description"].as_str()?.to_string())
+    }
+
+    /// The name of the default output, or of the default input if `source`
+    pub fn default_node(&self, source: bool) -> Option<&str> {
+        let key = if source {
+            "default.audio.source"
+        } else {
+            "default.audio.sink"
+        };
+        self.0
+            .values()
+            .filter(|o| o["props"]["metadata.name"] == "default")
+            .filter_map(|o| o["metadata"].as_array())
+            .flatten()
+            .find(|entry| entry["key"] == key)?["value"]["name"]
+            .as_str()
+    }
+}
+
+/// Apply a change to an object. Only what changed is printed, so objects are
+/// merged, metadata entries are replaced by subject and key, and anything
+/// else is replaced.
+fn merge(object: &mut Value, change: Value) {
+    match (object, change) {
+        (Value::Object(object), Value::Object(change)) => {
+            for (key, value) in change {
+                match object.get_mut(&key) {
+                    Some(Value::Array(entries)) if key == "metadata" => {
+                        merge_metadata(entries, value)
+                    }
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        object.insert(key, value);
+                    }
+                }
+            }
+        }
+        (object, change) => *object = change,
+    }
+}
+
+fn merge_metadata(entries: &mut Vec<Value>, changes: Value) {
+    let Value::Array(changes) = changes else {
+        return;
+    };
+    for change in changes {
+        let same =
+            |entry: &Value| entry["subject"] == change["subject"] && entry["key"] == change["key"];
+        entries.retain(|entry| !same(entry));
+        if !change["value"].is_null() {
+            entries.push(change);
+        }
+    }
+}
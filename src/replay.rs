@@ -0,0 +1,667 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+#[cfg(any(feature = "http", feature = "weather"))]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::{ExitStatus, Output};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+#[cfg(feature = "http")]
+use anyhow::anyhow;
+#[cfg(feature = "mpd")]
+use anyhow::bail;
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Local};
+#[cfg(feature = "mpd")]
+use mpd_client::client::Connection;
+#[cfg(feature = "mpd")]
+use mpd_client::Client;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "mpd")]
+use tokio::io::AsyncWrite;
+#[cfg(feature = "mpd")]
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
+#[cfg(feature = "mpd")]
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::time::{sleep_until, Duration, Instant};
+
+#[cfg(feature = "mpd")]
+use crate::system::connect_unix;
+use crate::system::{command_line, BoxFuture, Reader, Real, System};
+
+/// One thing that came in from the outside world, at `t` milliseconds after
+/// recording started
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    /// The first line, with the time recording started
+    Start { time: String },
+    /// A command that ran to completion, with its raw wait status, or none
+    /// if it couldn't be started
+    Output {
+        t: u64,
+        command: String,
+        status: Option<i32>,
+        stdout: String,
+    },
+    /// Something a long running command printed. Each start is recorded as
+    /// an empty chunk.
+    Stream {
+        t: u64,
+        command: String,
+        data: String,
+    },
+    Http {
+        t: u64,
+        url: String,
+        body: Result<String, String>,
+    },
+    /// MPD's response to a command, or its greeting for the empty command
+    Mpd {
+        t: u64,
+        host: String,
+        command: String,
+        response: String,
+    },
+}
+
+impl Entry {
+    /// What the entry answers, like "output:wpctl get-volume @DEFAULT_AUDIO_SINK@"
+    fn key(&self) -> Option<String> {
+        match self {
+            Self::Start { .. } => None,
+            Self::Output { command, .. } => Some(format!("output:{command}")),
+            Self::Stream { command, .. } => Some(format!("stream:{command}")),
+            Self::Http { url, .. } => Some(format!("http:{url}")),
+            Self::Mpd { host, .. } => Some(format!("mpd:{host}")),
+        }
+    }
+}
+
+/// The lines of a recording, written as they happen so a crash doesn't lose
+/// them
+struct Log {
+    file: Mutex<File>,
+    started: Instant,
+}
+
+impl Log {
+    fn t(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write(&self, entry: &Entry) {
+        let line = serde_json::to_string(entry).unwrap();
+        let mut file = self.file.lock().unwrap();
+        if let Err(err) = writeln!(file, "{line}") {
+            eprintln!("Couldn't write to the recording. {err}");
+        }
+    }
+}
+
+/// The real system, with everything blocks get from it written to a file
+pub struct Recorder {
+    real: Real,
+    log: Arc<Log>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Arc<Self>> {
+        let file =
+            File::create(path).with_context(|| format!("Couldn't create {}", path.display()))?;
+        let log = Arc::new(Log {
+            file: Mutex::new(file),
+            started: Instant::now(),
+        });
+        log.write(&Entry::Start {
+            time: Local::now().to_rfc3339(),
+        });
+        eprintln!(
+            "Recording to {}. It can include private things like API keys in URLs and \
+             what's on the clipboard, so check it before sharing it.",
+            path.display()
+        );
+        Ok(Arc::new(Self { real: Real, log }))
+    }
+}
+
+impl System for Recorder {
+    fn now(&self) -> DateTime<Local> {
+        self.real.now()
+    }
+
+    fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>> {
+        let line = command_line(command);
+        Box::pin(async move {
+            let output = self.real.output(command).await;
+            let (status, stdout) = match &output {
+                Ok(output) => (
+                    Some(output.status.into_raw()),
+                    String::from_utf8_lossy(&output.stdout).into_owned(),
+                ),
+                Err(_) => (None, String::new()),
+            };
+            self.log.write(&Entry::Output {
+                t: self.log.t(),
+                command: line,
+                status,
+                stdout,
+            });
+            output
+        })
+    }
+
+    fn stream(&self, command: &mut Command) -> io::Result<Reader> {
+        let line = command_line(command);
+        let inner = self.real.stream(command)?;
+        self.log.write(&Entry::Stream {
+            t: self.log.t(),
+            command: line.clone(),
+            data: String::new(),
+        });
+        Ok(Box::pin(RecordingReader {
+            inner,
+            log: self.log.clone(),
+            command: line,
+            partial: Vec::new(),
+        }))
+    }
+
+    #[cfg(feature = "http")]
+    fn http_get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let body = self.real.http_get(url, headers).await;
+            self.log.write(&Entry::Http {
+                t: self.log.t(),
+                url: url.to_string(),
+                body: body
+                    .as_ref()
+                    .map(String::clone)
+                    .map_err(|e| format!("{e:#}")),
+            });
+            body
+        })
+    }
+
+    #[cfg(feature = "mpd")]
+    fn connect_mpd<'a>(
+        &'a self,
+        host: &'a str,
+        password: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Connection>> {
+        Box::pin(async move {
+            // The client talks to the server through a proxy that writes
+            // down each response
+            let (client, proxy) = tokio::io::duplex(64 * 1024);
+            let (log, host_name) = (self.log.clone(), host.to_string());
+            if host.starts_with(['/', '@']) {
+                let server = connect_unix(host).await?;
+                tokio::spawn(record_mpd(proxy, server, host_name, log));
+            } else {
+                let server = TcpStream::connect(host).await?;
+                tokio::spawn(record_mpd(proxy, server, host_name, log));
+            }
+            Ok(Client::connect_with_password_opt(client, password).await?)
+        })
+    }
+
+    /// Nothing comes from the cache, so every fetch is in the recording
+    #[cfg(any(feature = "http", feature = "weather"))]
+    fn cache_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// D-Bus isn't recorded
+    #[cfg(feature = "dbus")]
+    fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        self.real.system_bus()
+    }
+
+    #[cfg(feature = "dbus")]
+    fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        self.real.session_bus()
+    }
+}
+
+/// A long running command's output that's recorded as it's read
+struct RecordingReader {
+    inner: Reader,
+    log: Arc<Log>,
+    command: String,
+    /// The start of a character split between reads
+    partial: Vec<u8>,
+}
+
+impl AsyncRead for RecordingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = self.inner.as_mut().poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let mut bytes = std::mem::take(&mut self.partial);
+            bytes.extend_from_slice(&buf.filled()[before..]);
+            let valid = match std::str::from_utf8(&bytes) {
+                Ok(_) => bytes.len(),
+                // Keep an incomplete character for the next read
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                Err(_) => bytes.len(),
+            };
+            self.partial = bytes.split_off(valid);
+            if !bytes.is_empty() {
+                self.log.write(&Entry::Stream {
+                    t: self.log.t(),
+                    command: self.command.clone(),
+                    data: String::from_utf8_lossy(&bytes).into_owned(),
+                });
+            }
+        }
+        poll
+    }
+}
+
+/// Pass everything between an MPD client and server, recording the server's
+/// response to each command
+#[cfg(feature = "mpd")]
+async fn record_mpd<S>(proxy: tokio::io::DuplexStream, server: S, host: String, log: Arc<Log>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (client_reader, mut client_writer) = tokio::io::split(proxy);
+    let (server_reader, mut server_writer) = tokio::io::split(server);
+    let mut commands = Commands::new(BufReader::new(client_reader));
+    let mut server_reader = BufReader::new(server_reader);
+    // The greeting doesn't answer a command
+    let mut sent = std::collections::VecDeque::from([String::new()]);
+    let (mut line, mut response) = (String::new(), String::new());
+    let result: io::Result<()> = async {
+        loop {
+            tokio::select! {
+                command = commands.next() => {
+                    let Some((command, raw)) = command? else {
+                        return Ok(());
+                    };
+                    server_writer.write_all(raw.as_bytes()).await?;
+                    if let Some(command) = command {
+                        sent.push_back(command);
+                    }
+                }
+                read = server_reader.read_line(&mut line) => {
+                    if read? == 0 {
+                        return Ok(());
+                    }
+                    client_writer.write_all(line.as_bytes()).await?;
+                    response.push_str(&line);
+                    let done =
+                        line == "OK\n" || line.starts_with("ACK ") || line.starts_with("OK MPD ");
+                    line.clear();
+                    if done {
+                        log.write(&Entry::Mpd {
+                            t: log.t(),
+                            host: host.clone(),
+                            command: sent.pop_front().unwrap_or_default(),
+                            response: std::mem::take(&mut response),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    .await;
+    if let Err(err) = result {
+        eprintln!("Lost the recorded connection to {host}. {err}");
+    }
+}
+
+/// Reads an MPD client's commands, putting command lists together as one
+#[cfg(feature = "mpd")]
+struct Commands<R> {
+    lines: tokio::io::Lines<R>,
+    list: Option<String>,
+}
+
+#[cfg(feature = "mpd")]
+impl<R: tokio::io::AsyncBufRead + Unpin> Commands<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            list: None,
+        }
+    }
+
+    /// The next line the client sent, and the command it completes, if it
+    /// completes one the server will answer. "noidle" is answered by ending
+    /// the idle command, so it isn't separate.
+    async fn next(&mut self) -> io::Result<Option<(Option<String>, String)>> {
+        let Some(line) = self.lines.next_line().await? else {
+            return Ok(None);
+        };
+        let raw = format!("{line}\n");
+        let command = match (&mut self.list, line.as_str()) {
+            (None, "command_list_begin" | "command_list_ok_begin") => {
+                self.list = Some(line);
+                None
+            }
+            (Some(list), "command_list_end") => {
+                list.push('\n');
+                list.push_str(&line);
+                self.list.take()
+            }
+            (Some(list), _) => {
+                list.push('\n');
+                list.push_str(&line);
+                None
+            }
+            (None, "noidle") => None,
+            // Passwords are left out of recordings
+            (None, _) if line.starts_with("password ") => Some("password".to_string()),
+            (None, _) => Some(line),
+        };
+        Ok(Some((command, raw)))
+    }
+}
+
+/// A system that answers blocks from a recording. Each command, URL, or MPD
+/// command gets the recorded answers in the order they were recorded, and
+/// the last one once they run out. Streams and MPD's changes arrive when
+/// they did while recording, and the clock starts when recording did.
+pub struct Replayer {
+    start: DateTime<Local>,
+    started: Instant,
+    entries: HashMap<String, Vec<Entry>>,
+    /// How many times each thing has been answered
+    answered: Arc<Answered>,
+}
+
+#[derive(Default)]
+struct Answered(Mutex<HashMap<String, usize>>);
+
+impl Answered {
+    /// The index of the next of `count` answers to `key`
+    fn next(&self, key: &str, count: usize) -> usize {
+        let mut answered = self.0.lock().unwrap();
+        let n = answered.entry(key.to_string()).or_default();
+        *n += 1;
+        (*n - 1).min(count.saturating_sub(1))
+    }
+
+    fn count(&self, key: &str) -> usize {
+        self.0.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+}
+
+impl Replayer {
+    pub fn load(path: &Path) -> Result<Arc<Self>> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        Self::parse(&source).with_context(|| format!("Couldn't parse {}", path.display()))
+    }
+
+    pub fn parse(source: &str) -> Result<Arc<Self>> {
+        let mut start = Local::now();
+        let mut entries: HashMap<String, Vec<Entry>> = HashMap::new();
+        for (i, line) in source.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry =
+                serde_json::from_str(line).with_context(|| format!("Line {} is invalid", i + 1))?;
+            match entry.key() {
+                Some(key) => entries.entry(key).or_default().push(entry),
+                None => {
+                    if let Entry::Start { time } = &entry {
+                        start = DateTime::parse_from_rfc3339(time)?.with_timezone(&Local);
+                    }
+                }
+            }
+        }
+        Ok(Arc::new(Self {
+            start,
+            started: Instant::now(),
+            entries,
+            answered: Arc::default(),
+        }))
+    }
+
+    fn next(&self, key: &str) -> Option<&Entry> {
+        let entries = self.entries.get(key)?;
+        entries.get(self.answered.next(key, entries.len()))
+    }
+}
+
+impl System for Replayer {
+    fn now(&self) -> DateTime<Local> {
+        self.start + chrono::Duration::from_std(self.started.elapsed()).unwrap()
+    }
+
+    fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>> {
+        let entry = self.next(&format!("output:{}", command_line(command)));
+        let output = match entry {
+            Some(Entry::Output {
+                status: Some(status),
+                stdout,
+                ..
+            }) => Ok(Output {
+                status: ExitStatus::from_raw(*status),
+                stdout: stdout.clone().into_bytes(),
+                stderr: Vec::new(),
+            }),
+            _ => Err(io::ErrorKind::NotFound.into()),
+        };
+        Box::pin(async { output })
+    }
+
+    fn stream(&self, command: &mut Command) -> io::Result<Reader> {
+        let key = format!("stream:{}", command_line(command));
+        let entries = self.entries.get(&key).ok_or(io::ErrorKind::NotFound)?;
+        // Each time the command is started, play what it printed after the
+        // matching start
+        let starts = |e: &Entry| matches!(e, Entry::Stream { data, .. } if data.is_empty());
+        let runs: Vec<usize> = (0..entries.len())
+            .filter(|&i| starts(&entries[i]))
+            .collect();
+        let from = runs
+            .get(self.answered.next(&key, runs.len()))
+            .copied()
+            .unwrap_or(0);
+        let chunks: Vec<Entry> = entries[from..]
+            .iter()
+            .enumerate()
+            .take_while(|(i, e)| *i == 0 || !starts(e))
+            .map(|(_, e)| e.clone())
+            .collect();
+        let (reader, mut writer) = tokio::io::duplex(64 * 1024);
+        let started = self.started;
+        tokio::spawn(async move {
+            for chunk in chunks {
+                if let Entry::Stream { t, data, .. } = chunk {
+                    sleep_until(started + Duration::from_millis(t)).await;
+                    if writer.write_all(data.as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            // Like the command, keep running once it's said everything
+            std::future::pending::<()>().await;
+        });
+        Ok(Box::pin(reader))
+    }
+
+    #[cfg(feature = "http")]
+    fn http_get<'a>(
+        &'a self,
+        url: &'a str,
+        _headers: Vec<(String, String)>,
+    ) -> BoxFuture<'a, Result<String>> {
+        let body = match self.next(&format!("http:{url}")) {
+            Some(Entry::Http { body, .. }) => body.clone().map_err(|e| anyhow!(e)),
+            _ => Err(anyhow!("{url} isn't in the recording")),
+        };
+        Box::pin(async { body })
+    }
+
+    #[cfg(feature = "mpd")]
+    fn connect_mpd<'a>(
+        &'a self,
+        host: &'a str,
+        password: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Connection>> {
+        let responses = self.entries.get(&format!("mpd:{host}")).cloned();
+        let (started, answered) = (self.started, self.answered.clone());
+        let host = host.to_string();
+        Box::pin(async move {
+            let Some(responses) = responses else {
+                bail!("{host} isn't in the recording");
+            };
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            tokio::spawn(replay_mpd(server, host, responses, started, answered));
+            Ok(Client::connect_with_password_opt(client, password).await?)
+        })
+    }
+
+    #[cfg(any(feature = "http", feature = "weather"))]
+    fn cache_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// D-Bus isn't in recordings, so blocks that use it talk to the real
+    /// buses
+    #[cfg(feature = "dbus")]
+    fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        Real.system_bus()
+    }
+
+    #[cfg(feature = "dbus")]
+    fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        Real.session_bus()
+    }
+}
+
+/// Answer an MPD client with the recorded responses to the same commands,
+/// and end its idling when the recorded server said something changed
+#[cfg(feature = "mpd")]
+async fn replay_mpd(
+    stream: tokio::io::DuplexStream,
+    host: String,
+    responses: Vec<Entry>,
+    started: Instant,
+    answered: Arc<Answered>,
+) -> io::Result<()> {
+    let responses: Vec<(u64, String, String)> = responses
+        .into_iter()
+        .filter_map(|e| match e {
+            Entry::Mpd {
+                t,
+                command,
+                response,
+                ..
+            } => Some((t, command, response)),
+            _ => None,
+        })
+        .collect();
+    // Idling that was only ended to send a command didn't see any changes
+    let changes: Vec<(u64, String)> = responses
+        .iter()
+        .filter(|(_, command, response)| command == "idle" && response.starts_with("changed: "))
+        .map(|(t, _, response)| (*t, response.clone()))
+        .collect();
+    // A reconnected client carries on from where the last one got to
+    let changes_key = format!("mpd:{host}:changes");
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut commands = Commands::new(BufReader::new(reader));
+    let greeting = responses.iter().find(|(_, command, _)| command.is_empty());
+    let greeting = greeting.map_or("OK MPD 0.23.5\n", |(_, _, response)| response);
+    writer.write_all(greeting.as_bytes()).await?;
+    loop {
+        let Some((command, _)) = commands.next().await? else {
+            return Ok(());
+        };
+        let Some(command) = command else {
+            continue;
+        };
+        let reply = if command == "idle" {
+            let due = changes.get(answered.count(&changes_key)).map(|(t, _)| *t);
+            let due = async {
+                match due {
+                    Some(t) => sleep_until(started + Duration::from_millis(t)).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = due => changes[answered.next(&changes_key, changes.len())].1.clone(),
+                // The only thing a client can send while idling is "noidle"
+                _ = commands.next() => "OK\n".to_string(),
+            }
+        } else {
+            let answers: Vec<&String> = responses
+                .iter()
+                .filter(|(_, c, _)| *c == command)
+                .map(|(_, _, response)| response)
+                .collect();
+            let key = format!("mpd:{host}:{command}");
+            match answers.get(answered.next(&key, answers.len())) {
+                Some(response) => response.to_string(),
+                None => format!("ACK [5@0] {{{command}}} unknown command \"{command}\"\n"),
+            }
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+}
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    use tokio::sync::watch;
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+    use crate::bar::{spawn, status_line};
+    use crate::config::{Config, Loading, Stale};
+    use crate::system::Sys;
+
+    #[tokio::test(start_paused = true)]
+    async fn replays_recorded_command_output_in_order() {
+        let recording = concat!(
+            r#"{"kind":"start","time":"2026-10-14T09:30:00+00:00"}"#,
+            "\n",
+            r#"{"kind":"output","t":0,"command":"wpctl get-volume @DEFAULT_AUDIO_SINK@","status":0,"stdout":"Volume: 0.45\n"}"#,
+            "\n",
+            r#"{"kind":"output","t":330,"command":"wpctl get-volume @DEFAULT_AUDIO_SINK@","status":0,"stdout":"Volume: 0.50\n"}"#,
+        );
+        let sys: Sys = Replayer::parse(recording).unwrap();
+        let config: Config = toml::from_str("[[block]]\nblock = \"volume\"").unwrap();
+        let (_, paused) = watch::channel(false);
+        let mut tasks = spawn(config.blocks, &sys, &paused);
+        sleep(Duration::from_millis(50)).await;
+        let line = status_line(
+            &mut tasks,
+            &Stale::default(),
+            &Loading::default(),
+            sys.now(),
+        );
+        assert!(line.starts_with(r#"[{"full_text":"🔉 45% ","name":"volume""#));
+
+        // Once the recording runs out the last answer is repeated
+        sleep(Duration::from_secs(2)).await;
+        let line = status_line(
+            &mut tasks,
+            &Stale::default(),
+            &Loading::default(),
+            sys.now(),
+        );
+        assert!(line.starts_with(r#"[{"full_text":"🔉 50% ","name":"volume""#));
+        let now = DateTime::parse_from_rfc3339("2026-10-14T09:30:02.050+00:00").unwrap();
+        assert_eq!(sys.now(), now);
+    }
+}
@@ -0,0 +1,198 @@
+use std::env;
+use std::io;
+#[cfg(any(feature = "http", feature = "weather"))]
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::Arc;
+
+#[cfg(any(feature = "dbus", feature = "http", feature = "mpd"))]
+use anyhow::Result;
+use chrono::{DateTime, Local};
+#[cfg(feature = "mpd")]
+use mpd_client::client::Connection;
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::system::{BoxFuture, Reader, Sys, System};
+
+/// Kept for systemd-run even when the environment is restricted, so it can
+/// reach the user's service manager
+static SYSTEMD_ENV: [&str; 2] = ["XDG_RUNTIME_DIR", "DBUS_SESSION_BUS_ADDRESS"];
+
+/// Limits on the commands a block runs, including its click commands
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sandbox {
+    /// Start commands in their own process group, so they can't signal the
+    /// bar or be caught up in signals meant for it
+    pub process_group: bool,
+    /// The only environment variables commands get, e.g. ["PATH", "HOME"].
+    /// Without this they get all of the bar's.
+    pub env: Option<Vec<String>>,
+    /// Run commands in a transient scope with `systemd-run --user --scope`.
+    /// Setting any of the limits below does this too.
+    pub systemd: bool,
+    /// systemd's MemoryMax, e.g. "200M"
+    pub memory_max: Option<String>,
+    /// systemd's CPUQuota, e.g. "20%"
+    pub cpu_quota: Option<String>,
+    /// systemd's TasksMax, how many processes and threads there can be
+    pub tasks_max: Option<u32>,
+}
+
+impl Sandbox {
+    fn properties(&self) -> Vec<String> {
+        let mut properties = Vec::new();
+        if let Some(max) = &self.memory_max {
+            properties.push(format!("MemoryMax={max}"));
+        }
+        if let Some(quota) = &self.cpu_quota {
+            properties.push(format!("CPUQuota={quota}"));
+        }
+        if let Some(max) = self.tasks_max {
+            properties.push(format!("TasksMax={max}"));
+        }
+        properties
+    }
+
+    /// `command` as it's run in the sandbox
+    fn apply(&self, command: &Command) -> Command {
+        let std = command.as_std();
+        let properties = self.properties();
+        let systemd = self.systemd || !properties.is_empty();
+        let mut sandboxed = if systemd {
+            let mut run = Command::new("systemd-run");
+            run.args(["--user", "--scope", "--quiet", "--collect"]);
+            for property in properties {
+                run.arg("-p").arg(property);
+            }
+            run.arg("--").arg(std.get_program());
+            run
+        } else {
+            Command::new(std.get_program())
+        };
+        sandboxed.args(std.get_args());
+        if let Some(keep) = &self.env {
+            sandboxed.env_clear();
+            let systemd_env = SYSTEMD_ENV.iter().filter(|_| systemd).copied();
+            for name in keep.iter().map(String::as_str).chain(systemd_env) {
+                if let Some(value) = env::var_os(name) {
+                    sandboxed.env(name, value);
+                }
+            }
+        }
+        for (name, value) in std.get_envs() {
+            match value {
+                Some(value) => sandboxed.env(name, value),
+                None => sandboxed.env_remove(name),
+            };
+        }
+        if let Some(dir) = std.get_current_dir() {
+            sandboxed.current_dir(dir);
+        }
+        if self.process_group {
+            sandboxed.process_group(0);
+        }
+        sandboxed
+    }
+}
+
+/// Runs a block's commands in its sandbox, and passes everything else on
+struct Sandboxed {
+    inner: Sys,
+    sandbox: Sandbox,
+}
+
+pub fn wrap(sys: Sys, sandbox: Sandbox) -> Sys {
+    Arc::new(Sandboxed {
+        inner: sys,
+        sandbox,
+    })
+}
+
+impl System for Sandboxed {
+    fn now(&self) -> DateTime<Local> {
+        self.inner.now()
+    }
+
+    fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>> {
+        let mut command = self.sandbox.apply(command);
+        Box::pin(async move { self.inner.output(&mut command).await })
+    }
+
+    fn stream(&self, command: &mut Command) -> io::Result<Reader> {
+        self.inner.stream(&mut self.sandbox.apply(command))
+    }
+
+    #[cfg(feature = "http")]
+    fn http_get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'a, Result<String>> {
+        self.inner.http_get(url, headers)
+    }
+
+    #[cfg(feature = "mpd")]
+    fn connect_mpd<'a>(
+        &'a self,
+        host: &'a str,
+        password: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Connection>> {
+        self.inner.connect_mpd(host, password)
+    }
+
+    #[cfg(any(feature = "http", feature = "weather"))]
+    fn cache_dir(&self) -> Option<PathBuf> {
+        self.inner.cache_dir()
+    }
+
+    #[cfg(feature = "dbus")]
+    fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        self.inner.system_bus()
+    }
+
+    #[cfg(feature = "dbus")]
+    fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        self.inner.session_bus()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bar::testing::{render, start};
+    use crate::click::{ClickEvent, BUTTON_LEFT};
+    use crate::system::mock::Mock;
+    use crate::system::Sys;
+
+    #[tokio::test(start_paused = true)]
+    async fn sandboxed_commands_run_in_a_systemd_scope() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        let scope = "systemd-run --user --scope --quiet --collect -p MemoryMax=50M -- sh -c";
+        mock.command(&format!("{scope} fortune -s"), 0, "Sandboxed\n");
+        mock.command(&format!("{scope} echo clicked"), 0, "");
+        let config = r#"
+            [[block]]
+            block = "fortune"
+            format = "{text}"
+            on_click.left = "echo clicked"
+            sandbox = { env = ["PATH"], memory_max = "50M" }
+        "#;
+        let mut tasks = start(config, &mock);
+        let line = render(&mut tasks, &mock).await;
+        assert!(line.starts_with(r#"[{"full_text":"Sandboxed ","name":"fortune""#));
+
+        let routes: Vec<_> = tasks.iter().map(|t| t.route()).collect();
+        let event = ClickEvent {
+            name: "fortune".to_string(),
+            instance: "0".to_string(),
+            button: BUTTON_LEFT,
+            modifiers: Vec::new(),
+        };
+        let sys: Sys = mock.clone();
+        crate::click::dispatch(&routes, &sys, event);
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        assert_eq!(ran[1], format!("{scope} echo clicked"));
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::system::Sys;
+
+/// A password or API token. Either the value itself, or where to find it so
+/// it doesn't have to be kept in the config file:
+///
+/// ```toml
+/// token = { env = "GITHUB_TOKEN" }
+/// token = { file = "~/.config/subar/github-token" }
+/// token = { command = "pass show github/subar" }
+/// ```
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Secret {
+    Value(String),
+    Env { env: String },
+    File { file: PathBuf },
+    Command { command: String },
+}
+
+impl Secret {
+    pub async fn resolve(&self, sys: &Sys) -> Result<String> {
+        let secret = match self {
+            Self::Value(value) => value.clone(),
+            Self::Env { env } => {
+                std::env::var(env).with_context(|| format!("Couldn't read ${env}"))?
+            }
+            Self::File { file } => {
+                let path = expand_home(file);
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("Couldn't read {}", path.display()))?
+            }
+            Self::Command { command } => {
+                let output = sys
+                    .output(Command::new("sh").arg("-c").arg(command))
+                    .await?;
+                if !output.status.success() {
+                    bail!("`{command}` failed with {}", output.status);
+                }
+                String::from_utf8(output.stdout)?
+            }
+        };
+        // Files and commands usually end with a newline
+        let secret = secret.trim();
+        if secret.is_empty() {
+            bail!("The secret is empty");
+        }
+        Ok(secret.to_string())
+    }
+}
+
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
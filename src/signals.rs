@@ -0,0 +1,19 @@
+use anyhow::{Context, Result};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+/// Pause the bar when the stop signal arrives and resume it on the continue
+/// signal. swaybar and i3bar send these when the bar is hidden and shown.
+pub async fn pause_on_signals(stop: i32, cont: i32, paused: watch::Sender<bool>) -> Result<()> {
+    let mut stop_signal = signal(SignalKind::from_raw(stop))
+        .with_context(|| format!("Couldn't listen for signal {stop}"))?;
+    let mut cont_signal = signal(SignalKind::from_raw(cont))
+        .with_context(|| format!("Couldn't listen for signal {cont}"))?;
+    loop {
+        tokio::select! {
+            Some(()) = stop_signal.recv() => paused.send_replace(true),
+            Some(()) = cont_signal.recv() => paused.send_replace(false),
+            else => return Ok(()),
+        };
+    }
+}
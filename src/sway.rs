@@ -0,0 +1,28 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::system::Sys;
+
+/// The process id of the focused window
+pub async fn focused_pid(sys: &Sys) -> Result<Option<u32>> {
+    let output = sys
+        .output(Command::new("swaymsg").arg("-t").arg("get_tree"))
+        .await?;
+    if !output.status.success() {
+        bail!("swaymsg failed with {}", output.status);
+    }
+    let tree: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(find_focused(&tree).and_then(|node| node["pid"].as_u64()?.try_into().ok()))
+}
+
+fn find_focused(node: &Value) -> Option<&Value> {
+    if node["focused"] == true {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(find_focused)
+}
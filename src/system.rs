@@ -0,0 +1,531 @@
+use std::future::Future;
+use std::io;
+#[cfg(any(feature = "http", feature = "weather"))]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[cfg(any(feature = "dbus", feature = "http", feature = "mpd"))]
+use anyhow::Result;
+use chrono::{DateTime, Local};
+#[cfg(feature = "mpd")]
+use mpd_client::client::Connection;
+#[cfg(feature = "mpd")]
+use mpd_client::Client;
+use tokio::io::{AsyncRead, ReadBuf};
+#[cfg(feature = "mpd")]
+use tokio::net::{TcpStream, UnixStream};
+use tokio::process::{Child, ChildStdout, Command};
+
+#[cfg(feature = "http")]
+use crate::http;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Everything blocks need from the outside world. Blocks go through this
+/// rather than running commands or reading the clock themselves so the whole
+/// bar can be run against mocks.
+pub trait System: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+    /// Run a command to completion and collect its output
+    fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>>;
+    /// Start a long running command and read its output as it's written.
+    /// The command is killed when the reader is dropped.
+    fn stream(&self, command: &mut Command) -> io::Result<Reader>;
+    #[cfg(feature = "http")]
+    fn http_get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'a, Result<String>>;
+    /// Connect to MPD at a socket path, an @abstract socket, or host:port,
+    /// and send it `password` if there is one
+    #[cfg(feature = "mpd")]
+    fn connect_mpd<'a>(
+        &'a self,
+        host: &'a str,
+        password: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Connection>>;
+    /// Where blocks can cache what they fetch, or None to always fetch it
+    #[cfg(any(feature = "http", feature = "weather"))]
+    fn cache_dir(&self) -> Option<PathBuf>;
+    /// Connect to the system bus, where daemons like UPower are
+    #[cfg(feature = "dbus")]
+    fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>>;
+    /// Connect to the user's session bus, where media players are
+    #[cfg(feature = "dbus")]
+    fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>>;
+}
+
+pub type Sys = Arc<dyn System>;
+
+pub type Reader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// The real clock, processes, network, MPD server, and D-Bus
+pub struct Real;
+
+pub fn real() -> Sys {
+    Arc::new(Real)
+}
+
+impl System for Real {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>> {
+        Box::pin(command.output())
+    }
+
+    fn stream(&self, command: &mut Command) -> io::Result<Reader> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdout = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+        Ok(Box::pin(ChildReader {
+            _child: child,
+            stdout,
+        }))
+    }
+
+    #[cfg(feature = "http")]
+    fn http_get<'a>(
+        &'a self,
+        url: &'a str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(http::fetch_with_headers(url, headers))
+    }
+
+    #[cfg(feature = "mpd")]
+    fn connect_mpd<'a>(
+        &'a self,
+        host: &'a str,
+        password: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Connection>> {
+        Box::pin(async move {
+            let connection = if host.starts_with(['/', '@']) {
+                Client::connect_with_password_opt(connect_unix(host).await?, password).await?
+            } else {
+                Client::connect_with_password_opt(TcpStream::connect(host).await?, password).await?
+            };
+            Ok(connection)
+        })
+    }
+
+    #[cfg(any(feature = "http", feature = "weather"))]
+    fn cache_dir(&self) -> Option<PathBuf> {
+        let dir = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        };
+        Some(dir.join("subar"))
+    }
+
+    #[cfg(feature = "dbus")]
+    fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        Box::pin(async { Ok(zbus::Connection::system().await?) })
+    }
+
+    #[cfg(feature = "dbus")]
+    fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+        Box::pin(async { Ok(zbus::Connection::session().await?) })
+    }
+}
+
+/// Connect to a Unix socket by its path, or by its name if it starts with @
+#[cfg(feature = "mpd")]
+pub async fn connect_unix(path: &str) -> io::Result<UnixStream> {
+    let Some(name) = path.strip_prefix('@') else {
+        return UnixStream::connect(path).await;
+    };
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        // Connecting to a local socket doesn't wait for anything
+        let stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+        stream.set_nonblocking(true)?;
+        UnixStream::from_std(stream)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = name;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Abstract sockets are only supported on Linux",
+        ))
+    }
+}
+
+/// A command as a space separated line, e.g. "wpctl get-volume @DEFAULT_AUDIO_SINK@"
+pub fn command_line(command: &Command) -> String {
+    let std = command.as_std();
+    std::iter::once(std.get_program())
+        .chain(std.get_args())
+        .map(|s| s.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A child's stdout that keeps the child alive
+struct ChildReader {
+    _child: Child,
+    stdout: ChildStdout,
+}
+
+impl AsyncRead for ChildReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::collections::HashMap;
+    #[cfg(feature = "mpd")]
+    use std::collections::HashSet;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    #[cfg(feature = "mpd")]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[cfg(any(feature = "dbus", feature = "http"))]
+    use anyhow::anyhow;
+    #[cfg(feature = "mpd")]
+    use anyhow::bail;
+    use chrono::TimeZone;
+    use tokio::io::AsyncReadExt;
+    #[cfg(feature = "mpd")]
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    #[cfg(feature = "dbus")]
+    use tokio::net::UnixStream;
+    #[cfg(feature = "mpd")]
+    use tokio::sync::broadcast;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    /// A system with canned command output, HTTP responses, MPD replies, and
+    /// D-Bus objects. The clock starts at a fixed time and follows tokio's
+    /// clock, so tests with paused time are deterministic.
+    pub struct Mock {
+        start: DateTime<Local>,
+        started: Instant,
+        commands: Mutex<HashMap<String, (i32, String)>>,
+        streams: Mutex<HashMap<String, Vec<u8>>>,
+        #[cfg(feature = "http")]
+        urls: Mutex<HashMap<String, String>>,
+        #[cfg(feature = "mpd")]
+        mpd: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+        #[cfg(feature = "mpd")]
+        mpd_changes: broadcast::Sender<String>,
+        /// Servers that hang up as soon as they've greeted a client
+        #[cfg(feature = "mpd")]
+        mpd_hanging_up: Mutex<HashSet<String>>,
+        /// Every command that was run, as a space separated line
+        pub ran: Mutex<Vec<String>>,
+        /// Every command sent to MPD, other than idling
+        #[cfg(feature = "mpd")]
+        pub mpd_ran: Arc<Mutex<Vec<String>>>,
+        /// How many times a client has connected to MPD
+        #[cfg(feature = "mpd")]
+        pub mpd_connections: AtomicUsize,
+        /// The blocks' ends of the fake buses
+        #[cfg(feature = "dbus")]
+        system_bus: Mutex<Option<zbus::Connection>>,
+        #[cfg(feature = "dbus")]
+        session_bus: Mutex<Option<zbus::Connection>>,
+    }
+
+    impl Mock {
+        pub fn new(start: &str) -> Arc<Self> {
+            let start = chrono::NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M:%S").unwrap();
+            Arc::new(Self {
+                start: Local.from_local_datetime(&start).unwrap(),
+                started: Instant::now(),
+                commands: Mutex::default(),
+                streams: Mutex::default(),
+                #[cfg(feature = "http")]
+                urls: Mutex::default(),
+                #[cfg(feature = "mpd")]
+                mpd: Arc::default(),
+                #[cfg(feature = "mpd")]
+                mpd_changes: broadcast::channel(8).0,
+                #[cfg(feature = "mpd")]
+                mpd_hanging_up: Mutex::default(),
+                ran: Mutex::default(),
+                #[cfg(feature = "mpd")]
+                mpd_ran: Arc::default(),
+                #[cfg(feature = "mpd")]
+                mpd_connections: AtomicUsize::new(0),
+                #[cfg(feature = "dbus")]
+                system_bus: Mutex::default(),
+                #[cfg(feature = "dbus")]
+                session_bus: Mutex::default(),
+            })
+        }
+
+        /// Make `command`, e.g. "wpctl get-volume @DEFAULT_AUDIO_SINK@", exit
+        /// with `code` after printing `stdout`. Other commands don't exist.
+        pub fn command(&self, command: &str, code: i32, stdout: &str) {
+            let mut commands = self.commands.lock().unwrap();
+            commands.insert(command.to_string(), (code, stdout.to_string()));
+        }
+
+        /// Make a long running `command` print `stdout`, then keep running
+        pub fn stream(&self, command: &str, stdout: Vec<u8>) {
+            let mut streams = self.streams.lock().unwrap();
+            streams.insert(command.to_string(), stdout);
+        }
+
+        #[cfg(feature = "http")]
+        pub fn url(&self, url: &str, body: &str) {
+            let mut urls = self.urls.lock().unwrap();
+            urls.insert(url.to_string(), body.to_string());
+        }
+
+        /// Start an MPD server at the default socket that answers each
+        /// command with its response, or replace the responses of the running
+        /// one. Without one, connecting to MPD fails.
+        #[cfg(feature = "mpd")]
+        pub fn mpd(&self, responses: &[(&str, &str)]) {
+            self.mpd_at("/run/mpd/socket", responses);
+        }
+
+        /// Start an MPD server at `host`, like [`Mock::mpd`]
+        #[cfg(feature = "mpd")]
+        pub fn mpd_at(&self, host: &str, responses: &[(&str, &str)]) {
+            let responses = responses
+                .iter()
+                .map(|(cmd, res)| (cmd.to_string(), res.to_string()))
+                .collect();
+            self.mpd.lock().unwrap().insert(host.to_string(), responses);
+        }
+
+        /// Make the MPD server at the default socket hang up on each client
+        /// straight after greeting it
+        #[cfg(feature = "mpd")]
+        pub fn mpd_hang_up(&self) {
+            self.mpd(&[]);
+            let mut hanging_up = self.mpd_hanging_up.lock().unwrap();
+            hanging_up.insert("/run/mpd/socket".to_string());
+        }
+
+        /// Start a fake system bus and return its end, for the test to serve
+        /// the objects blocks look for. It's a direct connection rather than
+        /// a real bus, so every name reaches the same objects and signals
+        /// have no sender. Without one, connecting to the system bus fails.
+        #[cfg(feature = "dbus")]
+        pub async fn serve_system_bus(&self) -> zbus::Connection {
+            serve_bus(&self.system_bus).await
+        }
+
+        /// Start a fake session bus, like [`Mock::serve_system_bus`]
+        #[cfg(feature = "dbus")]
+        pub async fn serve_session_bus(&self) -> zbus::Connection {
+            serve_bus(&self.session_bus).await
+        }
+
+        /// Tell idling MPD clients that a subsystem such as "player" changed
+        #[cfg(feature = "mpd")]
+        pub fn mpd_changed(&self, subsystem: &str) {
+            let _ = self.mpd_changes.send(subsystem.to_string());
+        }
+    }
+
+    impl Mock {
+        /// Remember that `command` ran and return it as a line
+        fn record(&self, command: &Command) -> String {
+            let line = command_line(command);
+            self.ran.lock().unwrap().push(line.clone());
+            line
+        }
+    }
+
+    /// Output from a command that's still running but has nothing to say
+    struct Running;
+
+    impl AsyncRead for Running {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+    }
+
+    impl System for Mock {
+        fn now(&self) -> DateTime<Local> {
+            let elapsed = chrono::Duration::from_std(self.started.elapsed()).unwrap();
+            self.start + elapsed
+        }
+
+        fn output<'a>(&'a self, command: &'a mut Command) -> BoxFuture<'a, io::Result<Output>> {
+            let line = self.record(command);
+            let canned = self.commands.lock().unwrap().get(&line).cloned();
+            Box::pin(async move {
+                let (code, stdout) = canned.ok_or(io::ErrorKind::NotFound)?;
+                Ok(Output {
+                    status: ExitStatus::from_raw(code << 8),
+                    stdout: stdout.into_bytes(),
+                    stderr: Vec::new(),
+                })
+            })
+        }
+
+        fn stream(&self, command: &mut Command) -> io::Result<Reader> {
+            let line = self.record(command);
+            let stdout = self.streams.lock().unwrap().get(&line).cloned();
+            let stdout = stdout.ok_or(io::ErrorKind::NotFound)?;
+            Ok(Box::pin(io::Cursor::new(stdout).chain(Running)))
+        }
+
+        #[cfg(feature = "http")]
+        fn http_get<'a>(
+            &'a self,
+            url: &'a str,
+            _headers: Vec<(String, String)>,
+        ) -> BoxFuture<'a, Result<String>> {
+            let body = self.urls.lock().unwrap().get(url).cloned();
+            Box::pin(async move { body.ok_or_else(|| anyhow!("Couldn't fetch {url}")) })
+        }
+
+        #[cfg(feature = "mpd")]
+        fn connect_mpd<'a>(
+            &'a self,
+            host: &'a str,
+            password: Option<&'a str>,
+        ) -> BoxFuture<'a, Result<Connection>> {
+            let running = self.mpd.lock().unwrap().contains_key(host);
+            let hang_up = self.mpd_hanging_up.lock().unwrap().contains(host);
+            let responses = self.mpd.clone();
+            let changes = self.mpd_changes.subscribe();
+            let ran = self.mpd_ran.clone();
+            Box::pin(async move {
+                if !running {
+                    bail!("No MPD at {host}");
+                }
+                self.mpd_connections.fetch_add(1, Ordering::Relaxed);
+                let (client, mut server) = tokio::io::duplex(4096);
+                if hang_up {
+                    server.write_all(b"OK MPD 0.23.5\n").await?;
+                    drop(server);
+                    return Ok(Client::connect_with_password_opt(client, password).await?);
+                }
+                let host = host.to_string();
+                tokio::spawn(serve_mpd(server, host, responses, changes, ran));
+                Ok(Client::connect_with_password_opt(client, password).await?)
+            })
+        }
+
+        /// Nothing is cached, so tests don't depend on what earlier runs saved
+        #[cfg(any(feature = "http", feature = "weather"))]
+        fn cache_dir(&self) -> Option<PathBuf> {
+            None
+        }
+
+        #[cfg(feature = "dbus")]
+        fn system_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+            let conn = self.system_bus.lock().unwrap().clone();
+            Box::pin(async { conn.ok_or_else(|| anyhow!("No system bus")) })
+        }
+
+        #[cfg(feature = "dbus")]
+        fn session_bus(&self) -> BoxFuture<'_, Result<zbus::Connection>> {
+            let conn = self.session_bus.lock().unwrap().clone();
+            Box::pin(async { conn.ok_or_else(|| anyhow!("No session bus")) })
+        }
+    }
+
+    /// Connect a server to a client that's kept in `client`, and return the
+    /// server
+    #[cfg(feature = "dbus")]
+    async fn serve_bus(client: &Mutex<Option<zbus::Connection>>) -> zbus::Connection {
+        let (server, peer) = UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+        let (server, peer) = tokio::try_join!(
+            zbus::connection::Builder::unix_stream(server)
+                .server(guid)
+                .unwrap()
+                .p2p()
+                .build(),
+            zbus::connection::Builder::unix_stream(peer).p2p().build(),
+        )
+        .unwrap();
+        // Start answering straight away, so calls to objects that aren't
+        // served get an error like they would from a real bus
+        server.object_server();
+        *client.lock().unwrap() = Some(peer);
+        server
+    }
+
+    #[cfg(feature = "mpd")]
+    async fn serve_mpd(
+        stream: tokio::io::DuplexStream,
+        host: String,
+        responses: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+        mut changes: broadcast::Receiver<String>,
+        ran: Arc<Mutex<Vec<String>>>,
+    ) -> io::Result<()> {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"OK MPD 0.23.5\n").await?;
+        // The client idles between commands and cancels it to send one
+        let mut idling = false;
+        let mut pending = Vec::new();
+        loop {
+            let reply = tokio::select! {
+                line = lines.next_line() => match line?.as_deref() {
+                    None => return Ok(()),
+                    Some("idle") if pending.is_empty() => {
+                        idling = true;
+                        continue;
+                    }
+                    Some("idle") => changed(&mut pending),
+                    Some("noidle") if idling => {
+                        idling = false;
+                        "OK\n".to_string()
+                    }
+                    Some("noidle") => continue,
+                    Some(cmd) => {
+                        ran.lock().unwrap().push(cmd.to_string());
+                        let responses = responses.lock().unwrap();
+                        match responses.get(&host).and_then(|r| r.get(cmd)) {
+                            Some(response) => format!("{response}OK\n"),
+                            None => format!("ACK [5@0] {{{cmd}}} unknown command \"{cmd}\"\n"),
+                        }
+                    }
+                },
+                Ok(subsystem) = changes.recv() => {
+                    pending.push(subsystem);
+                    if !idling {
+                        continue;
+                    }
+                    idling = false;
+                    changed(&mut pending)
+                }
+            };
+            writer.write_all(reply.as_bytes()).await?;
+        }
+    }
+
+    fn changed(pending: &mut Vec<String>) -> String {
+        let mut reply: String = pending
+            .drain(..)
+            .map(|s| format!("changed: {s}\n"))
+            .collect();
+        reply.push_str("OK\n");
+        reply
+    }
+}
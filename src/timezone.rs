@@ -0,0 +1,526 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+
+/// A time zone from the system's tz database, like "America/New_York"
+pub struct Zone {
+    /// When each change of offset happens, in seconds since the epoch, and
+    /// the index of the type in effect from then
+    transitions: Vec<(i64, usize)>,
+    types: Vec<LocalType>,
+    /// How the offset changes after the last transition
+    rule: Option<Rule>,
+}
+
+#[derive(Clone)]
+pub struct LocalType {
+    /// Seconds ahead of UTC
+    pub offset: i32,
+    /// Like "EDT" or "+0530"
+    pub abbreviation: String,
+}
+
+impl Zone {
+    /// Read the zone from $TZDIR, or /usr/share/zoneinfo
+    pub fn load(name: &str) -> Result<Self> {
+        if name.split('/').any(|part| part == "..") {
+            bail!("Invalid time zone {name:?}");
+        }
+        let dir = env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/zoneinfo"));
+        let path = dir.join(name);
+        let data = std::fs::read(&path)
+            .with_context(|| format!("Couldn't read the time zone {}", path.display()))?;
+        Self::parse(&data).with_context(|| format!("Couldn't parse {}", path.display()))
+    }
+
+    /// Parse a TZif file, using the 64-bit data from version 2 onwards
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader { data, position: 0 };
+        let counts = reader.header()?;
+        if counts.version == 0 {
+            return reader.block(&counts, 4);
+        }
+        reader.skip(counts.block_len(4).context("It's too long")?)?;
+        let counts = reader.header()?;
+        let mut zone = reader.block(&counts, 8)?;
+        let footer = String::from_utf8_lossy(reader.rest());
+        let footer = footer.trim();
+        if !footer.is_empty() {
+            zone.rule = Some(Rule::parse(footer)?);
+        }
+        Ok(zone)
+    }
+
+    /// The offset and abbreviation at `time`, in seconds since the epoch
+    pub fn at(&self, time: i64) -> LocalType {
+        let last = self.transitions.last().map(|(at, _)| *at);
+        if let Some(rule) = self
+            .rule
+            .as_ref()
+            .filter(|_| last.is_none_or(|l| time >= l))
+        {
+            return rule.at(time);
+        }
+        let index = match self.transitions.iter().rposition(|(at, _)| time >= *at) {
+            Some(i) => self.transitions[i].1,
+            // Before the first transition, the first type applies
+            None => 0,
+        };
+        self.types.get(index).cloned().unwrap_or(LocalType {
+            offset: 0,
+            abbreviation: "UTC".to_string(),
+        })
+    }
+}
+
+struct Counts {
+    version: u8,
+    isut: usize,
+    isstd: usize,
+    leap: usize,
+    time: usize,
+    types: usize,
+    chars: usize,
+}
+
+impl Counts {
+    /// How long the data block is, or None if it couldn't fit in memory
+    fn block_len(&self, time_size: usize) -> Option<usize> {
+        [
+            self.time.checked_mul(time_size)?,
+            self.time,
+            self.types.checked_mul(6)?,
+            self.chars,
+            self.leap.checked_mul(time_size + 4)?,
+            self.isstd,
+            self.isut,
+        ]
+        .into_iter()
+        .try_fold(0, usize::checked_add)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.position.checked_add(len);
+        let Some(bytes) = end.and_then(|end| self.data.get(self.position..end)) else {
+            bail!("It ends too soon");
+        };
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.position.min(self.data.len())..]
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn time(&mut self, size: usize) -> Result<i64> {
+        let bytes = self.take(size)?;
+        Ok(match size {
+            4 => i32::from_be_bytes(bytes.try_into()?) as i64,
+            _ => i64::from_be_bytes(bytes.try_into()?),
+        })
+    }
+
+    fn header(&mut self) -> Result<Counts> {
+        if self.take(4)? != b"TZif" {
+            bail!("It isn't a TZif file");
+        }
+        let version = match self.take(1)?[0] {
+            0 => 0,
+            digit => digit.saturating_sub(b'0'),
+        };
+        self.skip(15)?;
+        let mut count = || Ok::<_, anyhow::Error>(self.u32()? as usize);
+        Ok(Counts {
+            version,
+            isut: count()?,
+            isstd: count()?,
+            leap: count()?,
+            time: count()?,
+            types: count()?,
+            chars: count()?,
+        })
+    }
+
+    fn block(&mut self, counts: &Counts, time_size: usize) -> Result<Zone> {
+        // Check the counts against the data before allocating anything
+        let len = counts.block_len(time_size).context("It's too long")?;
+        if len > self.rest().len() {
+            bail!("It ends too soon");
+        }
+        let times = (0..counts.time)
+            .map(|_| self.time(time_size))
+            .collect::<Result<Vec<_>>>()?;
+        let indices = self.take(counts.time)?;
+        let mut types = Vec::with_capacity(counts.types);
+        for _ in 0..counts.types {
+            let offset = self.u32()? as i32;
+            let info = self.take(2)?;
+            types.push((offset, info[1] as usize));
+        }
+        let chars = self.take(counts.chars)?;
+        self.skip(counts.leap * (time_size + 4) + counts.isstd + counts.isut)?;
+        let types = types
+            .into_iter()
+            .map(|(offset, start)| {
+                let name = chars.get(start..).unwrap_or_default();
+                let end = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+                LocalType {
+                    offset,
+                    abbreviation: String::from_utf8_lossy(&name[..end]).into_owned(),
+                }
+            })
+            .collect();
+        let transitions = times
+            .into_iter()
+            .zip(indices.iter().map(|i| *i as usize))
+            .collect();
+        Ok(Zone {
+            transitions,
+            types,
+            rule: None,
+        })
+    }
+}
+
+/// A POSIX TZ string such as "EST5EDT,M3.2.0,M11.1.0", which TZif files end
+/// with for times after their last transition
+struct Rule {
+    standard: LocalType,
+    /// Daylight time and when it starts and ends each year
+    daylight: Option<(LocalType, Change, Change)>,
+}
+
+/// When daylight saving starts or ends, and the local time it happens at
+struct Change {
+    day: Day,
+    /// Seconds after midnight, which can be negative or past a day
+    time: i64,
+}
+
+enum Day {
+    /// The day of the year from 1, never counting the 29th of February
+    Julian(u32),
+    /// The day of the year from 0, counting leap days
+    Zero(u32),
+    /// The month, week from 1 to 5 (the last), and weekday from Sunday
+    Month(u32, u32, u32),
+}
+
+impl Rule {
+    fn parse(tz: &str) -> Result<Self> {
+        let mut rest = tz;
+        let standard_name = name(&mut rest)?;
+        let standard_offset = -offset(&mut rest).context("No standard offset")?;
+        let standard = LocalType {
+            offset: standard_offset as i32,
+            abbreviation: standard_name,
+        };
+        if rest.is_empty() {
+            return Ok(Self {
+                standard,
+                daylight: None,
+            });
+        }
+        let daylight_name = name(&mut rest)?;
+        let daylight_offset = if rest.starts_with(',') {
+            standard_offset + 3600
+        } else {
+            -offset(&mut rest)?
+        };
+        let daylight = LocalType {
+            offset: daylight_offset as i32,
+            abbreviation: daylight_name,
+        };
+        let mut changes = rest.strip_prefix(',').unwrap_or_default().split(',');
+        let (Some(start), Some(end)) = (changes.next(), changes.next()) else {
+            bail!("No daylight saving rules in {tz:?}");
+        };
+        let rule = Self {
+            standard,
+            daylight: Some((daylight, Change::parse(start)?, Change::parse(end)?)),
+        };
+        Ok(rule)
+    }
+
+    fn at(&self, time: i64) -> LocalType {
+        let Some((daylight, start, end)) = &self.daylight else {
+            return self.standard.clone();
+        };
+        let local = time.saturating_add(self.standard.offset as i64);
+        let year = chrono::DateTime::from_timestamp(local, 0).map_or(1970, |t| t.year());
+        // Both changes are written in the local time before they happen
+        let starts = start.at(year) - self.standard.offset as i64;
+        let ends = end.at(year) - daylight.offset as i64;
+        let in_daylight = if starts < ends {
+            time >= starts && time < ends
+        } else {
+            // Southern hemisphere daylight saving spans the new year
+            time >= starts || time < ends
+        };
+        if in_daylight {
+            daylight.clone()
+        } else {
+            self.standard.clone()
+        }
+    }
+}
+
+impl Change {
+    fn parse(change: &str) -> Result<Self> {
+        let (day, time) = match change.split_once('/') {
+            Some((day, time)) => {
+                let mut time = time;
+                (day, offset(&mut time)?)
+            }
+            None => (change, 2 * 3600),
+        };
+        let invalid = || format!("Invalid daylight saving rule {change:?}");
+        let day = if let Some(julian) = day.strip_prefix('J') {
+            Day::Julian(julian.parse().with_context(invalid)?)
+        } else if let Some(month) = day.strip_prefix('M') {
+            let parts: Vec<u32> = month
+                .split('.')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .with_context(invalid)?;
+            let [month, week, weekday] = parts[..] else {
+                bail!(invalid());
+            };
+            Day::Month(month, week, weekday)
+        } else {
+            Day::Zero(day.parse().with_context(invalid)?)
+        };
+        let valid = match day {
+            Day::Julian(day) => (1..=365).contains(&day),
+            Day::Zero(day) => day <= 365,
+            Day::Month(month, week, weekday) => {
+                (1..=12).contains(&month) && (1..=5).contains(&week) && weekday <= 6
+            }
+        };
+        if !valid {
+            bail!(invalid());
+        }
+        Ok(Self { day, time })
+    }
+
+    /// Seconds since the epoch in local time that it happens in `year`
+    fn at(&self, year: i32) -> i64 {
+        let date = match self.day {
+            Day::Julian(day) => {
+                let date = NaiveDate::from_yo_opt(year, day.min(365));
+                // Day 60 is always the 1st of March
+                let leap = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+                date.and_then(|d| {
+                    if leap && day >= 60 {
+                        d.succ_opt()
+                    } else {
+                        Some(d)
+                    }
+                })
+            }
+            Day::Zero(day) => NaiveDate::from_yo_opt(year, day + 1),
+            Day::Month(month, week, weekday) => {
+                NaiveDate::from_ymd_opt(year, month, 1).and_then(|first| {
+                    let first_weekday = first.weekday().num_days_from_sunday();
+                    let mut day = 1 + (weekday + 7 - first_weekday) % 7 + (week - 1) * 7;
+                    let days_in_month = (28..=31)
+                        .rev()
+                        .find(|d| NaiveDate::from_ymd_opt(year, month, *d).is_some())
+                        .unwrap_or(28);
+                    while day > days_in_month {
+                        day -= 7;
+                    }
+                    NaiveDate::from_ymd_opt(year, month, day)
+                })
+            }
+        };
+        let midnight = date
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map_or(0, |d| d.and_utc().timestamp());
+        midnight + self.time
+    }
+}
+
+/// A zone abbreviation, either letters or quoted like "<+0530>"
+fn name(rest: &mut &str) -> Result<String> {
+    let (name, after) = if let Some(quoted) = rest.strip_prefix('<') {
+        let end = quoted.find('>').context("Unclosed '<' in a time zone")?;
+        (&quoted[..end], &quoted[end + 1..])
+    } else {
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        (&rest[..end], &rest[end..])
+    };
+    if name.is_empty() {
+        bail!("No time zone name in {rest:?}");
+    }
+    *rest = after;
+    Ok(name.to_string())
+}
+
+/// A signed time like "5", "-10", or "5:30", in seconds. Hours go up to 167,
+/// as POSIX allows for the times of daylight saving changes.
+fn offset(rest: &mut &str) -> Result<i64> {
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '+' || c == '-'))
+        .unwrap_or(rest.len());
+    let (offset, after) = rest.split_at(end);
+    *rest = after;
+    let (sign, offset) = match offset.strip_prefix('-') {
+        Some(offset) => (-1, offset),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+    let invalid = || format!("Invalid time zone offset {offset:?}");
+    let parts: Vec<_> = offset.split(':').collect();
+    if parts.len() > 3 {
+        bail!(invalid());
+    }
+    let mut seconds = 0;
+    for ((part, scale), max) in parts.into_iter().zip([3600, 60, 1]).zip([167, 59, 59]) {
+        let part: i64 = part.parse().with_context(invalid)?;
+        if part > max {
+            bail!(invalid());
+        }
+        seconds += part * scale;
+    }
+    Ok(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A version 2 TZif file with an empty version 1 block
+    fn tzif(transitions: &[(i64, u8)], types: &[(i32, u8)], chars: &[u8], footer: &str) -> Vec<u8> {
+        let header = |counts: [usize; 6]| {
+            let mut header = b"TZif2".to_vec();
+            header.extend([0; 15]);
+            for count in counts {
+                header.extend((count as u32).to_be_bytes());
+            }
+            header
+        };
+        let mut data = header([0; 6]);
+        data.extend(header([
+            0,
+            0,
+            0,
+            transitions.len(),
+            types.len(),
+            chars.len(),
+        ]));
+        for (at, _) in transitions {
+            data.extend(at.to_be_bytes());
+        }
+        data.extend(transitions.iter().map(|(_, index)| index));
+        for (offset, abbreviation) in types {
+            data.extend(offset.to_be_bytes());
+            data.extend([0, *abbreviation]);
+        }
+        data.extend(chars);
+        data.extend(format!("\n{footer}\n").into_bytes());
+        data
+    }
+
+    #[test]
+    fn tzif_files_are_read_up_to_their_rule() {
+        let data = tzif(
+            &[(0, 1), (1_000_000, 0)],
+            &[(3600, 0), (7200, 4)],
+            b"CET\0CEST\0",
+            "EST5EDT,M3.2.0,M11.1.0",
+        );
+        let zone = Zone::parse(&data).unwrap();
+        assert_eq!(zone.at(-1).abbreviation, "CET");
+        assert_eq!(zone.at(10).offset, 7200);
+        assert_eq!(zone.at(10).abbreviation, "CEST");
+        // 2026-07-01 and 2026-12-01 are after the last transition
+        assert_eq!(zone.at(1_782_864_000).abbreviation, "EDT");
+        assert_eq!(zone.at(1_796_083_200).offset, -5 * 3600);
+    }
+
+    #[test]
+    fn malformed_tzif_files_are_errors() {
+        let data = tzif(&[(0, 0)], &[(3600, 0)], b"CET\0", "CET-1");
+        assert!(Zone::parse(&data).is_ok());
+        assert!(Zone::parse(b"").is_err());
+        assert!(Zone::parse(b"TZjf2").is_err());
+        // Cut off part way through the data
+        for len in [4, 20, 44, 60, 80] {
+            assert!(Zone::parse(&data[..len]).is_err(), "{len}");
+        }
+        // Counts far bigger than the file
+        let mut huge = data.clone();
+        huge[44 + 32..44 + 36].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(Zone::parse(&huge).is_err());
+        let mut huge_v1 = data.clone();
+        huge_v1[20..44].copy_from_slice(&[0xff; 24]);
+        assert!(Zone::parse(&huge_v1).is_err());
+        // A bad footer
+        let data = tzif(&[], &[(0, 0)], b"UTC\0", "EST5EDT,M3.0.0,M11.1.0");
+        assert!(Zone::parse(&data).is_err());
+    }
+
+    #[test]
+    fn tz_strings_follow_daylight_saving() {
+        let rule = Rule::parse("<+1030>-10:30<+11>-11,M10.1.0,M4.1.0").unwrap();
+        // Daylight saving through the southern summer
+        assert_eq!(rule.at(1_796_083_200).abbreviation, "+11");
+        assert_eq!(rule.at(1_782_864_000).offset, 10 * 3600 + 1800);
+        let rule = Rule::parse("IST-2IDT,M3.4.4/26,M10.5.0").unwrap();
+        assert_eq!(rule.at(1_782_864_000).offset, 3 * 3600);
+        let rule = Rule::parse("XXX3YYY,J60/-1,300/167").unwrap();
+        assert_eq!(rule.at(1_782_864_000).abbreviation, "YYY");
+        assert_eq!(Rule::parse("UTC0").unwrap().at(0).offset, 0);
+    }
+
+    #[test]
+    fn malformed_tz_strings_are_errors() {
+        for tz in [
+            "",
+            "5",
+            "EST",
+            "<EST5",
+            "EST5EDT",
+            "EST5EDT,M3.2.0",
+            "EST5EDT,M3.0.0,M11.1.0",
+            "EST5EDT,M3.6.0,M11.1.0",
+            "EST5EDT,M0.2.0,M11.1.0",
+            "EST5EDT,M13.2.0,M11.1.0",
+            "EST5EDT,M3.2.7,M11.1.0",
+            "EST5EDT,M3.2,M11.1.0",
+            "EST5EDT,M3.2.0.1,M11.1.0",
+            "EST5EDT,J0,J365",
+            "EST5EDT,J1,J366",
+            "EST5EDT,0,366",
+            "EST5EDT,Mx.2.0,M11.1.0",
+            "EST5EDT,M3.2.0/2:00:00:00,M11.1.0",
+            "EST168EDT,M3.2.0,M11.1.0",
+            "EST5:60EDT,M3.2.0,M11.1.0",
+            "EST99999999999999999999EDT,M3.2.0,M11.1.0",
+            "EST9223372036854775807EDT,M3.2.0,M11.1.0",
+        ] {
+            assert!(Rule::parse(tz).is_err(), "{tz:?}");
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{sleep, Duration};
+
+use crate::backoff::Backoff;
+use crate::events::BlockEvent;
+use crate::system::Sys;
+
+static UDEV_MAX_RETRY: u64 = 10 * 60 * 1000;
+/// How long to wait after a device appears before refreshing, since plugging
+/// one in sends a burst of events and it takes a moment for things like
+/// PipeWire to pick it up
+static UDEV_SETTLE: u64 = 1000;
+
+/// The subsystem of each device that's added, removed, or changed, like
+/// "sound" or "block"
+pub type DeviceEvents = broadcast::Sender<String>;
+
+/// Start watching for devices in any of `subsystems`, restarting `udevadm
+/// monitor` if it stops
+pub fn monitor(sys: &Sys, subsystems: &[String]) -> DeviceEvents {
+    let (tx, _) = broadcast::channel(64);
+    let mut command = Command::new("udevadm");
+    command.args(["monitor", "--udev"]);
+    for subsystem in subsystems {
+        command.arg(format!("--subsystem-match={subsystem}"));
+    }
+    tokio::spawn(read_events(sys.clone(), command, tx.clone()));
+    tx
+}
+
+async fn read_events(sys: Sys, mut command: Command, tx: DeviceEvents) {
+    let mut backoff = Backoff::new(
+        Duration::from_secs(1),
+        Duration::from_millis(UDEV_MAX_RETRY),
+    );
+    loop {
+        match sys.stream(&mut command) {
+            Ok(reader) => {
+                let mut lines = BufReader::new(reader).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    backoff.reset();
+                    if let Some(subsystem) = subsystem(&line) {
+                        let _ = tx.send(subsystem.to_string());
+                    }
+                }
+                eprintln!("udevadm monitor stopped");
+            }
+            Err(err) => eprintln!("Couldn't watch for devices. {err}"),
+        }
+        sleep(backoff.fail()).await;
+    }
+}
+
+/// The subsystem of an event line, like
+/// "UDEV  [1234.567890] add      /devices/.../sound/card1 (sound)"
+fn subsystem(line: &str) -> Option<&str> {
+    let event = line.strip_prefix("UDEV")?;
+    let (_, subsystem) = event.trim_end().strip_suffix(')')?.rsplit_once(" (")?;
+    Some(subsystem)
+}
+
+/// Refresh a block once the devices in one of `subsystems` settle after a
+/// change
+pub fn refresh_on(
+    devices: &DeviceEvents,
+    subsystems: Vec<String>,
+    block: mpsc::Sender<BlockEvent>,
+) {
+    let mut events = devices.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(subsystem) if subsystems.contains(&subsystem) => {
+                    sleep(Duration::from_millis(UDEV_SETTLE)).await;
+                    // The rest of the burst is covered by this refresh
+                    events = events.resubscribe();
+                    if block.send(BlockEvent::Refresh).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{sleep, Duration};
+
+    use crate::bar::testing::{render, start};
+    use crate::system::mock::Mock;
+
+    #[tokio::test(start_paused = true)]
+    async fn device_changes_refresh_blocks() {
+        let mock = Mock::new("2026-10-14 09:30:00");
+        mock.command("sh -c cat /proc/asound/cards", 0, "0 [PCH]\n");
+        mock.stream(
+            "udevadm monitor --udev --subsystem-match=block --subsystem-match=sound",
+            concat!(
+                "monitor will print the received events for:\n",
+                "UDEV - the event which udev sends out after rule processing\n\n",
+                "UDEV  [1234.500000] add      /devices/pci0000:00/usb1/1-2/sound/card1 (sound)\n",
+                "UDEV  [1234.510000] add      /devices/pci0000:00/usb1/1-2/sound/card1/pcmC1D0p (sound)\n",
+                "UDEV  [1240.000000] add      /devices/pci0000:00/usb2/2-1/block/sdb (block)\n",
+            )
+            .as_bytes()
+            .to_vec(),
+        );
+        let config = r#"
+            [[block]]
+            block = "fortune"
+            source = { type = "command", command = "cat /proc/asound/cards" }
+            refresh_on_devices = ["sound"]
+
+            [[block]]
+            block = "fortune"
+            source = { type = "command", command = "cat /proc/asound/cards" }
+            refresh_on_devices = ["block"]
+        "#;
+        let mut tasks = start(config, &mock);
+        sleep(Duration::from_secs(2)).await;
+        render(&mut tasks, &mock).await;
+        let ran = mock.ran.lock().unwrap();
+        let runs = ran.iter().filter(|c| c.contains("asound")).count();
+        // Each block once at the start, then once for its burst of changes
+        assert_eq!(runs, 4);
+    }
+}
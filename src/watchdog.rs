@@ -0,0 +1,236 @@
+use std::env;
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+use std::os::unix::net::SocketAddr;
+use std::os::unix::net::UnixDatagram;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::system::BoxFuture;
+
+/// What the renderer was last doing, for diagnosing a stall
+#[derive(Clone, Copy)]
+pub enum Stage {
+    Rendering = 0,
+    Writing = 1,
+    Sleeping = 2,
+}
+
+impl Stage {
+    fn describe(stage: u8) -> &'static str {
+        match stage {
+            0 => "rendering the status line",
+            1 => "writing the status line, so the bar may have stopped reading it",
+            _ => "waiting for the next update",
+        }
+    }
+}
+
+/// Updated by the renderer every time round its loop, so a stall can be
+/// noticed from outside it
+pub struct Heartbeat {
+    start: Instant,
+    /// Milliseconds after `start` of the last beat
+    last: AtomicU64,
+    stage: AtomicU8,
+    /// The renderer waits without beating while the bar is hidden
+    hidden: AtomicBool,
+}
+
+impl Heartbeat {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            start: Instant::now(),
+            last: AtomicU64::new(0),
+            stage: AtomicU8::new(Stage::Sleeping as u8),
+            hidden: AtomicBool::new(false),
+        })
+    }
+
+    pub fn beat(&self, stage: Stage) {
+        let now = self.start.elapsed().as_millis() as u64;
+        self.last.store(now, Ordering::Relaxed);
+        self.stage.store(stage as u8, Ordering::Relaxed);
+    }
+
+    pub fn set_hidden(&self, hidden: bool) {
+        self.beat(Stage::Sleeping);
+        self.hidden.store(hidden, Ordering::Relaxed);
+    }
+
+    /// How long since the last beat, which is zero while the bar is hidden
+    fn silence(&self) -> Duration {
+        if self.hidden.load(Ordering::Relaxed) {
+            return Duration::ZERO;
+        }
+        let last = Duration::from_millis(self.last.load(Ordering::Relaxed));
+        self.start.elapsed().saturating_sub(last)
+    }
+}
+
+/// Run the renderer that `start` returns until it finishes, starting a new
+/// one if it panics or goes `timeout` without beating
+pub async fn supervise(
+    heartbeat: &Heartbeat,
+    timeout: Option<Duration>,
+    mut start: impl FnMut() -> BoxFuture<'static, Result<()>>,
+) -> Result<()> {
+    loop {
+        let mut render = tokio::spawn(start());
+        tokio::select! {
+            result = &mut render => match result {
+                Ok(result) => return result,
+                Err(err) => eprintln!("The renderer crashed. Restarting it. {err}"),
+            },
+            _ = stalled(heartbeat, timeout.unwrap_or_default()), if timeout.is_some() => {
+                eprintln!("Restarting the renderer.");
+                render.abort();
+            }
+        }
+    }
+}
+
+/// Wait until the renderer has gone `timeout` without beating. This runs on
+/// the same thread as the renderer, so it only notices the renderer getting
+/// stuck on something it's awaiting.
+async fn stalled(heartbeat: &Heartbeat, timeout: Duration) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let silence = heartbeat.silence();
+        if silence > timeout {
+            let stage = Stage::describe(heartbeat.stage.load(Ordering::Relaxed));
+            eprintln!(
+                "The status line hasn't updated for {}s. It was {stage}.",
+                silence.as_secs()
+            );
+            return;
+        }
+    }
+}
+
+/// Watch for the whole main thread being blocked, which nothing on it can
+/// notice, and keep systemd's watchdog fed while the renderer is beating, so
+/// systemd restarts the bar if it stops
+pub fn spawn_thread(heartbeat: Arc<Heartbeat>, timeout: Duration) {
+    let systemd = systemd_watchdog();
+    let check = systemd.map_or(Duration::from_secs(1), |interval| interval / 2);
+    let check = check.min(Duration::from_secs(1));
+    thread::spawn(move || {
+        let mut warned = false;
+        loop {
+            thread::sleep(check);
+            let silence = heartbeat.silence();
+            // Twice the timeout gives the renderer a chance to be restarted
+            if silence > timeout * 2 {
+                if !warned {
+                    let stage = Stage::describe(heartbeat.stage.load(Ordering::Relaxed));
+                    eprintln!(
+                        "The bar has been blocked for {}s while {stage}.",
+                        silence.as_secs()
+                    );
+                    warned = true;
+                }
+                continue;
+            }
+            warned = false;
+            if systemd.is_some() {
+                notify_systemd("WATCHDOG=1");
+            }
+        }
+    });
+}
+
+/// How often systemd expects to hear from its watchdog, if it's enabled for
+/// this process
+fn systemd_watchdog() -> Option<Duration> {
+    let usec = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse() != Ok(std::process::id()) {
+            return None;
+        }
+    }
+    Some(Duration::from_micros(usec))
+}
+
+/// Tell systemd something like "READY=1", if it's listening
+pub fn notify_systemd(state: &str) {
+    let Ok(path) = env::var("NOTIFY_SOCKET") else {
+        return;
+    };
+    let sent = UnixDatagram::unbound().and_then(|socket| match path.strip_prefix('@') {
+        // Abstract sockets are Linux only
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            let addr = SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)
+        }
+        _ => socket.send_to(state.as_bytes(), &path),
+    });
+    if let Err(err) = sent {
+        eprintln!("Couldn't notify systemd. {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+
+    use tokio::sync::oneshot;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    /// Real time, since the heartbeat follows the real clock
+    #[tokio::test]
+    async fn stalled_and_crashed_renderers_are_restarted() {
+        let heartbeat = Heartbeat::new();
+        let (stuck, aborted) = oneshot::channel::<()>();
+        let mut stuck = Some(stuck);
+        let mut starts = 0;
+        let supervised = supervise(&heartbeat, Some(Duration::from_millis(500)), || {
+            starts += 1;
+            match starts {
+                // Never beats, so it's aborted once the watchdog notices
+                1 => {
+                    let stuck = stuck.take();
+                    Box::pin(async move {
+                        let _stuck = stuck;
+                        pending().await
+                    })
+                }
+                2 => Box::pin(async { panic!("The renderer broke") }),
+                _ => Box::pin(async { Ok(()) }),
+            }
+        });
+        timeout(Duration::from_secs(5), supervised)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(starts, 3);
+        // Aborting the task drops its end of the channel
+        let aborted = timeout(Duration::from_secs(1), aborted).await.unwrap();
+        assert!(aborted.is_err());
+    }
+
+    #[tokio::test]
+    async fn renderers_can_run_without_a_watchdog() {
+        let heartbeat = Heartbeat::new();
+        let mut starts = 0;
+        let supervised = supervise(&heartbeat, None, || {
+            starts += 1;
+            // Silent for longer than any watchdog check
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(1500)).await;
+                anyhow::bail!("The bar went away")
+            })
+        });
+        let err = supervised.await.unwrap_err();
+        assert_eq!(err.to_string(), "The bar went away");
+        assert_eq!(starts, 1);
+    }
+}